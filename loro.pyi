@@ -1,3 +1,4 @@
+import os
 import typing
 from enum import Enum
 
@@ -12,6 +13,8 @@ LoroValue = typing.Union[
     typing.List["LoroValue"],
     typing.Dict[str, "LoroValue"],
     ContainerID,
+    "LoroValueList",
+    "LoroValueMap",
 ]
 
 Container = typing.Union[
@@ -20,10 +23,316 @@ Container = typing.Union[
 
 ContainerId = typing.Union[str, ContainerID]
 
+class LoroError(Exception):
+    """Base class of every error raised by Loro."""
+
+class DecodeError(LoroError):
+    """The imported data is corrupted, incompatible or cannot be decoded."""
+
+class EncodeError(LoroError):
+    """The document cannot be exported in the requested mode."""
+
+class OutOfBoundError(LoroError):
+    """The given index or range is out of the bounds of the container."""
+
+    pos: int
+    len: int
+    info: str
+    start: int
+    end: int
+
+class ContainerNotFoundError(LoroError):
+    """The target container does not exist in the document."""
+
+    container_ids: list[ContainerID]
+
+class ContainerDeletedError(ContainerNotFoundError):
+    """The target container has been deleted."""
+
+    container_id: ContainerID
+
+class DetachedEditError(LoroError):
+    """The document is detached and detached editing is not enabled."""
+
+class DetachedContainerError(LoroError):
+    """The method is not allowed on a container that is not attached to a document."""
+
+    method: str
+
+class CursorNotFoundError(LoroError):
+    """The position of the cursor cannot be resolved."""
+
+class VersionNotFoundError(LoroError):
+    """The given version is not included in the document."""
+
+    id: ID
+
+class PeerIdError(LoroError):
+    """The PeerID is invalid or conflicts with existing operations."""
+
+    id: ID
+    peer: int
+    expected: int
+    found: int
+
+class TransactionError(LoroError):
+    """The operation conflicts with the current transaction."""
+
+class TreeError(LoroError):
+    """The operation on a LoroTree is invalid."""
+
+    tree_id: TreeID
+
+class UndoError(LoroError):
+    """The operation on an UndoManager is invalid."""
+
+class StreamOverflowError(LoroError):
+    """The buffer of an event stream is full because its consumer is too slow."""
+
+class InvalidArgumentError(LoroError):
+    """An argument passed to Loro is invalid."""
+
+class ImportRejectedError(LoroError):
+    """The import hook rejected the imported updates."""
+
+class SchemaError(LoroError):
+    """The document does not match its schema, or cannot be migrated."""
+
+    violations: list[SchemaViolation]
+
+class JsonPatchError(LoroError):
+    """The JSON patch is invalid, or one of its operations cannot be applied."""
+
+class OverflowPolicy(Enum):
+    r"""
+    What an `EventStream` does with a new item when its buffer is full.
+    """
+    Error = 0
+    r"""
+    Close the stream. The consumer gets the buffered items, then
+    `StreamOverflowError` is raised.
+    """
+    DropOldest = 1
+    r"""
+    Discard the oldest buffered item to make room for the new one.
+    """
+    DropNewest = 2
+    r"""
+    Discard the new item.
+    """
+
+class RedactAction(Enum):
+    r"""
+    What `LoroDoc.redact` does with the content of an operation.
+
+    Child containers are always kept, so that the structure of the document
+    and the operations inside of them stay valid.
+    """
+    Keep = 0
+    r"""
+    Keep the content.
+    """
+    Null = 1
+    r"""
+    Replace values with null, text with U+FFFD and counter updates with 0.
+    """
+    Hash = 2
+    r"""
+    Replace values with `"sha256:<hex>"`, the hash of the salt followed by
+    the JSON of the value, so that equal values can still be matched.
+    """
+    Drop = 3
+    r"""
+    Turn map insertions into deletions of the key, and redact the rest
+    like `Null`.
+    """
+
+class RedactPolicy:
+    r"""
+    How `LoroDoc.redact` redacts each operation.
+
+    The action of a map entry is the one of its key in `keys`. Otherwise, the
+    action is the one of the nearest container in `containers` on the path
+    from the root to the operation's container, or `default`. The values of
+    the text marks in `marks` are kept whatever the action.
+    """
+    def __new__(
+        cls,
+        default: RedactAction = RedactAction.Null,
+        *,
+        containers: typing.Optional[dict[ContainerID, RedactAction]] = None,
+        keys: typing.Optional[dict[str, RedactAction]] = None,
+        marks: typing.Optional[typing.Sequence[str]] = None,
+        salt: typing.Optional[bytes] = None,
+    ) -> RedactPolicy: ...
+
+def set_lazy_threshold(threshold: typing.Optional[int] = None) -> None:
+    r"""
+    Return the lists and maps with at least `threshold` items as
+    `LoroValueList` and `LoroValueMap` proxies, or always convert them to
+    lists and dicts if `threshold` is `None`, which is the default.
+    """
+    ...
+
+def set_conversion_hook(
+    ty: type, hook: typing.Optional[typing.Callable[[typing.Any], typing.Any]]
+) -> None:
+    r"""
+    Convert the objects of type `ty`, and of its subclasses, with `hook` when
+    they are stored in a document. `hook` takes the object and returns an
+    object that can be stored, such as a string or a dict. If `hook` is `None`,
+    the hook of `ty` is removed.
+
+    By default, `decimal.Decimal` objects are converted to strings, and
+    `datetime.datetime`, `datetime.date` and `datetime.time` objects to their
+    ISO 8601 strings. Tuples and other sequences, except sets, are converted
+    to lists, and `bytearray` and `memoryview` objects to bytes.
+    """
+    ...
+
+def register_encoder(
+    ty: type,
+    encoder: typing.Optional[typing.Callable[[typing.Any], typing.Any]],
+    tag: typing.Optional[str] = None,
+) -> None:
+    r"""
+    Store the objects of type `ty`, and of its subclasses, as tagged maps
+    `{"$type": tag, "$value": encoder(obj)}`, which the decoder registered for
+    `tag` turns back into objects. The tag defaults to `module.qualname` of
+    `ty`. The encoder replaces the conversion hook of `ty`, if any. If
+    `encoder` is `None`, the encoder of `ty` is removed.
+    """
+    ...
+
+def register_decoder(
+    tag: str, decoder: typing.Optional[typing.Callable[[typing.Any], typing.Any]]
+) -> None:
+    r"""
+    Return `decoder(value)` instead of the tagged maps
+    `{"$type": tag, "$value": value}` read from documents. If `decoder` is
+    `None`, the decoder of `tag` is removed.
+
+    A tagged map whose tag has no decoder is returned as a dict, and so is a
+    tagged map whose decoder raises, after the exception is passed to
+    `sys.unraisablehook`.
+    """
+    ...
+
+class LoroValueList(typing.Sequence[LoroValue]):
+    r"""
+    A read-only proxy of a list value, whose items are converted when they
+    are accessed.
+
+    Returned instead of a list when the list has at least the number of items
+    set by `set_lazy_threshold`. Like a list, it compares equal to the lists
+    and list proxies with the same items, and not to tuples, and is hashable.
+    """
+    def __len__(self) -> int: ...
+    @typing.overload
+    def __getitem__(self, index: int) -> LoroValue: ...
+    @typing.overload
+    def __getitem__(self, index: slice) -> LoroValue: ...
+    def __iter__(self) -> typing.Iterator[LoroValue]: ...
+    def __contains__(self, value: object) -> bool: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def to_python(self) -> list[typing.Any]:
+        r"""
+        Convert the whole list to a Python list, without proxies.
+        """
+        ...
+
+class LoroValueMap(typing.Mapping[str, LoroValue]):
+    r"""
+    A read-only proxy of a map value, whose values are converted when they
+    are accessed.
+
+    Returned instead of a dict when the map has at least the number of items
+    set by `set_lazy_threshold`. It compares equal to the dicts and map proxies
+    with the same items, and is hashable. `keys()`, `values()` and `items()`
+    return `LoroValueMapView`s.
+    """
+    def __len__(self) -> int: ...
+    def __getitem__(self, key: str) -> LoroValue: ...
+    def __iter__(self) -> typing.Iterator[str]: ...
+    def __contains__(self, key: object) -> bool: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def to_python(self) -> dict[str, typing.Any]:
+        r"""
+        Convert the whole map to a Python dict, without proxies.
+        """
+        ...
+
+class LoroValueMapView(typing.Collection[typing.Any]):
+    r"""
+    The keys, values or items of a `LoroValueMap`, which can be iterated any
+    number of times.
+    """
+    def __len__(self) -> int: ...
+    def __iter__(self) -> typing.Iterator[typing.Any]: ...
+    def __contains__(self, value: object) -> bool: ...
+
+class DeepPolicy:
+    r"""
+    Which containers `LoroMap.set_deep`, `LoroMap.update_deep` and
+    `LoroDoc.from_json` create for the parts of a value.
+
+    Dicts become `LoroMap`s, and lists become `LoroList`s, or
+    `LoroMovableList`s if `movable_lists` is true. Strings become `LoroText`s
+    if `texts` is true or if they are the value of a key in `text_keys`, and
+    are stored as plain values otherwise. The values of the keys in
+    `value_keys` are always stored as plain values.
+    """
+    def __new__(
+        cls,
+        *,
+        texts: bool = False,
+        movable_lists: bool = False,
+        text_keys: typing.Optional[typing.Sequence[str]] = None,
+        value_keys: typing.Optional[typing.Sequence[str]] = None,
+    ) -> DeepPolicy: ...
+
+_T = typing.TypeVar("_T")
+_M = typing.TypeVar("_M", bound="LoroModel")
+
+class EventStream(typing.Generic[_T]):
+    r"""
+    An asynchronous iterator over the items produced by a Loro subscription.
+
+    Items are buffered in a queue of at most `maxsize` items. When the queue
+    is full, the `overflow` policy decides whether the stream is closed with a
+    `StreamOverflowError`, or whether the oldest or the newest item is dropped.
+
+    The subscription is removed when the stream is closed or garbage collected.
+    """
+    closed: bool
+    r"""
+    Whether the stream stopped receiving new items.
+    """
+    def __aiter__(self) -> EventStream[_T]: ...
+    def __anext__(self) -> typing.Awaitable[_T]: ...
+    def __len__(self) -> int:
+        r"""
+        The number of buffered items.
+        """
+        ...
+
+    def close(self) -> None:
+        r"""
+        Unsubscribe and stop receiving new items.
+
+        The items that are already buffered can still be consumed, after that the
+        iteration stops.
+        """
+        ...
+
 class AbsolutePosition:
     pos: int
     side: Side
 
+    def __new__(cls, pos: int, side: Side = Side.Middle): ...
+
 class Awareness:
     all_states: dict[int, PeerInfo]
     peer: int
@@ -49,6 +358,106 @@ class ChangeMeta:
     message: typing.Optional[str]
     deps: Frontiers
     len: int
+    ops: typing.Optional[list[Op]]
+    r"""
+    The decoded ops of the change.
+
+    It is only filled by `LoroDoc.changes()` and for the hook of
+    `LoroDoc.import_checked()`, and is `None` otherwise.
+    """
+
+class ChangeIter:
+    r"""
+    Iterator over the changes between two versions, yielding `ChangeMeta`
+    with their ops decoded.
+
+    The changes are sorted in causal order (by lamport, then by peer). The
+    ops of a change are only decoded when the change is reached.
+    """
+    def __iter__(self) -> ChangeIter: ...
+    def __next__(self) -> ChangeMeta: ...
+    def __length_hint__(self) -> int: ...
+
+class Op:
+    r"""
+    A single operation of a change.
+    """
+    id: ID
+    lamport: int
+    container: ContainerID
+    content: OpContent
+
+class OpContent:
+    r"""
+    The decoded content of an op.
+
+    `LoroList` and `LoroMovableList` share the `ListInsert` and `ListDelete`
+    variants; the container id of the op tells them apart.
+    """
+    class ListInsert(OpContent):
+        pos: int
+        values: list[LoroValue]
+
+    class ListDelete(OpContent):
+        pos: int
+        len: int
+        start_id: ID
+
+    class MovableListMove(OpContent):
+        from_: int
+        to: int
+        elem_id: IdLp
+
+    class MovableListSet(OpContent):
+        elem_id: IdLp
+        value: LoroValue
+
+    class MapInsert(OpContent):
+        key: str
+        value: LoroValue
+
+    class MapDelete(OpContent):
+        key: str
+
+    class TextInsert(OpContent):
+        pos: int
+        text: str
+
+    class TextDelete(OpContent):
+        pos: int
+        len: int
+        start_id: ID
+
+    class TextMark(OpContent):
+        start: int
+        end: int
+        key: str
+        value: LoroValue
+        info: int
+
+    class TextMarkEnd(OpContent): ...
+
+    class TreeCreate(OpContent):
+        target: TreeID
+        parent: typing.Optional[TreeID]
+        fractional_index: str
+
+    class TreeMove(OpContent):
+        target: TreeID
+        parent: typing.Optional[TreeID]
+        fractional_index: str
+
+    class TreeDelete(OpContent):
+        target: TreeID
+
+    class CounterIncrement(OpContent):
+        value: float
+
+    class Unknown(OpContent):
+        r"""
+        An op of a container type unknown to this version of Loro.
+        """
+        prop: int
 
 class Configure:
     def __new__(cls) -> Configure: ...
@@ -136,6 +545,88 @@ class Cursor:
         """
         ...
 
+class DocView:
+    r"""
+    A read-only view of a document at a given version, created by
+    `LoroDoc.view_at`.
+
+    The view does not change when the document is edited, and several views
+    of different versions can be held at the same time.
+    """
+
+    @property
+    def frontiers(self) -> Frontiers:
+        r"""
+        The version of the view.
+        """
+        ...
+
+    def get_value(self) -> LoroValue:
+        r"""
+        Get the shallow value of the document at the version of the view.
+        """
+        ...
+
+    def get_deep_value(self) -> LoroValue:
+        r"""
+        Get the entire state of the document at the version of the view.
+        """
+        ...
+
+    def get_movable_list(self, obj: ContainerId) -> LoroMovableList:
+        r"""
+        Get a read-only [LoroMovableList] by container id.
+        """
+        ...
+
+    def get_list(self, obj: ContainerId) -> LoroList:
+        r"""
+        Get a read-only [LoroList] by container id.
+        """
+        ...
+
+    def get_map(self, obj: ContainerId) -> LoroMap:
+        r"""
+        Get a read-only [LoroMap] by container id.
+        """
+        ...
+
+    def get_text(self, obj: ContainerId) -> LoroText:
+        r"""
+        Get a read-only [LoroText] by container id.
+        """
+        ...
+
+    def get_tree(self, obj: ContainerId) -> LoroTree:
+        r"""
+        Get a read-only [LoroTree] by container id.
+        """
+        ...
+
+    def get_counter(self, obj: ContainerId) -> LoroCounter:
+        r"""
+        Get a read-only [LoroCounter] by container id.
+        """
+        ...
+
+    def get_by_path(self, path: typing.Sequence[Index]) -> typing.Optional[ValueOrContainer]:
+        r"""
+        Get the value or container by the path.
+        """
+        ...
+
+    def get_by_str_path(self, path: str) -> typing.Optional[ValueOrContainer]:
+        r"""
+        Get the value or container by the string path.
+        """
+        ...
+
+    def jsonpath(self, path: str) -> list[ValueOrContainer]:
+        r"""
+        Evaluate a JSONPath expression on the document at the version of the view.
+        """
+        ...
+
 class DiffEvent:
     triggered_by: EventTriggerKind
     origin: str
@@ -181,6 +672,10 @@ class ImportBlobMetadata:
 class ImportStatus:
     success: VersionRange
     pending: typing.Optional[VersionRange]
+    rejected: typing.Optional[VersionRange]
+    r"""
+    The changes rejected by the hook of `import_checked`.
+    """
 
 class LoroCounter:
     id: ContainerID
@@ -257,6 +752,8 @@ class LoroDoc:
 
         When called in detached mode, it will fork at the current state frontiers.
         It will have the same effect as `fork_at(&self.state_frontiers())`.
+
+        The GIL is released while the document is copied.
         """
         ...
 
@@ -265,6 +762,20 @@ class LoroDoc:
         Fork the document at the given frontiers.
 
         The created doc will only contain the history before the specified frontiers.
+
+        The GIL is released while the document is copied.
+        """
+        ...
+
+    def view_at(self, frontiers: Frontiers) -> DocView:
+        r"""
+        Get a read-only view of the document at the given frontiers.
+
+        Unlike `checkout`, the document stays attached, and unlike `fork_at`,
+        the returned containers are read-only. Several views can be held at the
+        same time.
+
+        The GIL is released while the view is created.
         """
         ...
 
@@ -286,6 +797,19 @@ class LoroDoc:
         """
         ...
 
+    @classmethod
+    def from_json(
+        cls, obj: dict[str, typing.Any], policy: typing.Optional[DeepPolicy] = None
+    ) -> LoroDoc:
+        r"""
+        Create a document from a dict whose entries are its root containers.
+
+        The values become `LoroMap`s, `LoroList`s or `LoroText`s, and so do
+        their nested dicts, lists and strings, according to `policy`. The
+        document is committed once all of them are created.
+        """
+        ...
+
     @classmethod
     def decode_import_blob_meta(
         cls, bytes: bytes, check_checksum: bool
@@ -366,6 +890,8 @@ class LoroDoc:
         recorded in the `OpLog` without being applied to the `DocState`.
 
         You should call `attach` to attach the `DocState` to the latest version of `OpLog`.
+
+        The GIL is released during the checkout. It is re-acquired only to dispatch events.
         """
         ...
 
@@ -408,7 +934,16 @@ class LoroDoc:
         """
         ...
 
-    def import_batch(self, bytes: typing.Sequence[bytes]) -> ImportStatus: ...
+    def import_batch(self, bytes: typing.Sequence[bytes]) -> ImportStatus:
+        r"""
+        Import a batch of updates/snapshot.
+
+        The data can be in arbitrary order. The import result will be the same.
+
+        The GIL is released during the import. It is re-acquired only to dispatch events.
+        """
+        ...
+
     def get_movable_list(self, obj: ContainerId) -> LoroMovableList:
         r"""
         Get a [LoroMovableList] by container id.
@@ -503,6 +1038,8 @@ class LoroDoc:
     def import_(self, bytes: bytes) -> ImportStatus:
         r"""
         Import updates/snapshot exported by [`LoroDoc::export_snapshot`] or [`LoroDoc::export_from`].
+
+        The GIL is released during the import. It is re-acquired only to dispatch events.
         """
         ...
 
@@ -512,6 +1049,44 @@ class LoroDoc:
 
         It marks the import with a custom `origin` string. It can be used to track the import source
         in the generated events.
+
+        The GIL is released during the import. It is re-acquired only to dispatch events.
+        """
+        ...
+
+    def import_checked(
+        self,
+        bytes: bytes,
+        check: typing.Callable[
+            [ImportBlobMetadata, list[ChangeMeta]],
+            typing.Union[None, bool, typing.Iterable[int]],
+        ],
+        *,
+        origin: typing.Optional[str] = None,
+    ) -> ImportStatus:
+        r"""
+        Import updates or a snapshot after checking them with `check`.
+
+        `check(meta, changes)` receives the `ImportBlobMetadata` of the blob and
+        the list of the changes it would add to the document, as `ChangeMeta`
+        with their ops decoded, in causal order. It is called before the
+        document is modified, and returns:
+
+        - `None` or `True` to import every change,
+        - `False` to reject the blob, which raises `ImportRejectedError`,
+        - an iterable of peer ids to drop the changes of those peers, together
+          with the changes that depend on them. The rest is imported, and the
+          dropped changes are reported in `ImportStatus.rejected`.
+
+        Exceptions raised by `check` are propagated and nothing is imported.
+        Changes whose dependencies are missing are never imported and are
+        reported in `ImportStatus.pending`, so that they cannot be applied
+        later without being checked.
+
+        The blob is decoded by importing it into a copy of the history of the
+        document. The copy is made by the first call, and is then kept up to
+        date with the changes of the document, unless changes were rejected or
+        pending. The GIL is released while the blob is imported.
         """
         ...
 
@@ -639,6 +1214,46 @@ class LoroDoc:
         - `doc.export(mode)` is called.
         - `doc.import(data)` is called.
         - `doc.checkout(version)` is called.
+
+        Exceptions raised by the callback cannot interrupt the transaction. They are
+        reported through `sys.unraisablehook` and the subscription stays alive.
+        """
+        ...
+
+    def events(
+        self,
+        container_id: typing.Optional[ContainerID] = None,
+        *,
+        maxsize: int = 1024,
+        overflow: OverflowPolicy = OverflowPolicy.Error,
+    ) -> EventStream[DiffEvent]:
+        r"""
+        Return an async iterator over the `DiffEvent`s of the document.
+
+        If `container_id` is given, only the events of that container are yielded.
+        The events are buffered until they are consumed, in a buffer of at most
+        `maxsize` events; `overflow` decides what happens when it is full.
+
+        ```python
+        async for event in doc.events():
+            ...
+        ```
+        """
+        ...
+
+    def local_updates(
+        self,
+        *,
+        maxsize: int = 1024,
+        overflow: OverflowPolicy = OverflowPolicy.Error,
+    ) -> EventStream[bytes]:
+        r"""
+        Return an async iterator over the local updates of the document.
+
+        It yields the same encoded updates as `subscribe_local_update`, buffered in
+        a buffer of at most `maxsize` updates; `overflow` decides what happens when
+        it is full. Dropping updates breaks synchronization, so the default policy
+        closes the stream instead.
         """
         ...
 
@@ -647,6 +1262,9 @@ class LoroDoc:
     ) -> Subscription:
         r"""
         Subscribe the local update of the document.
+
+        The callback receives the encoded update as `bytes`. Returning `False` removes the
+        subscription; any other return value keeps it alive.
         """
         ...
 
@@ -718,9 +1336,27 @@ class LoroDoc:
         """
         ...
 
-    def export(self, mode: ExportMode) -> bytes:
+    def export(
+        self,
+        mode: ExportMode,
+        *,
+        containers: typing.Optional[typing.Sequence[ContainerID]] = None,
+    ) -> bytes:
         r"""
         Export the document in the given mode.
+
+        If `containers` is given, only these containers and their descendants
+        are exported, along with the entries leading to them from the root, so
+        that the receiving document can resolve their path. The output is then
+        a snapshot of the history up to the version of `mode`, rewritten so
+        that it never contains the other containers: the modes `Snapshot`,
+        `ShallowSnapshot`, `StateOnly` and `SnapshotAt` are accepted. It is at
+        the same version as the document, so that the two documents can
+        exchange updates afterwards. The other items of the lists on the path
+        to the containers are exported as `None`. Shallow documents are not
+        accepted.
+
+        The GIL is released while the document is encoded.
         """
         ...
 
@@ -732,6 +1368,29 @@ class LoroDoc:
         """
         ...
 
+    def validate(self, schema: DocSchema) -> list[SchemaViolation]:
+        r"""
+        Check the document against `schema`, and return the places where it
+        does not match.
+
+        This is typically called after importing updates from an untrusted
+        peer. Every violation is addressed by the path returned by
+        `get_path_to_container`.
+        """
+        ...
+
+    def migrate(self, schema: DocSchema) -> int:
+        r"""
+        Upgrade the document to the version of `schema` by running its pending
+        migrations, and return the version the document was at.
+
+        All the migrations are applied in a single commit. They run on a fork
+        of the document, so if one of them raises, or if the result does not
+        match the schema, the document is left untouched and the error (a
+        `SchemaError` for violations) is raised.
+        """
+        ...
+
     def jsonpath(self, path: str) -> list[ValueOrContainer]:
         r"""
         Evaluate a JSONPath expression on the document and return matching values or handlers.
@@ -789,6 +1448,8 @@ class LoroDoc:
 
         * `ids` - The IDs of the Change to start the traversal from.
         * `cb` - A callback function that is called for each ancestor. It can return `True` to stop the traversal.
+
+        If the callback raises, the traversal stops and the exception is re-raised.
         """
         ...
 
@@ -798,6 +1459,33 @@ class LoroDoc:
         """
         ...
 
+    def changes(
+        self,
+        from_: typing.Union[Frontiers, VersionVector, None] = None,
+        to: typing.Union[Frontiers, VersionVector, None] = None,
+        *,
+        peers: typing.Optional[typing.Iterable[int]] = None,
+        start_time: typing.Optional[int] = None,
+        end_time: typing.Optional[int] = None,
+        containers: typing.Optional[typing.Sequence[ContainerID]] = None,
+    ) -> ChangeIter:
+        r"""
+        Iterate over the changes between two versions with their ops decoded.
+
+        `from_` and `to` can be `Frontiers` or a `VersionVector`. They default to
+        the empty version and the latest version of the oplog. Changes are
+        yielded in causal order as `ChangeMeta` whose `ops` are filled. A change
+        that is only partially included in the range is trimmed to it.
+
+        The results can be filtered by the authors of the changes (`peers`),
+        their timestamp (`start_time <= timestamp < end_time`) and the
+        containers they modified. When `containers` is given, only the ops on
+        those containers are kept.
+
+        **NOTE:** This method will implicitly commit if `containers` is given.
+        """
+        ...
+
     def get_changed_containers_in(self, id: ID, len: int) -> set[ContainerID]:
         r"""
         Gets container IDs modified in the given ID range.
@@ -821,6 +1509,8 @@ class LoroDoc:
         This will generate a series of local operations that can revert the
         current doc to the target version. It will calculate the diff between the current
         state and the target state, and apply the diff to the current state.
+
+        The GIL is released while the diff is calculated and applied.
         """
         ...
 
@@ -832,9 +1522,26 @@ class LoroDoc:
         """
         ...
 
+    def apply_json_patch(self, patch: list[dict[str, typing.Any]]) -> None:
+        r"""
+        Apply a JSON patch (RFC 6902), given as a list of operations, and commit it.
+
+        The paths are JSON pointers from the root of the document, whose first
+        token is the name of a root container. Objects and arrays added by the
+        patch become `LoroMap`s and `LoroList`s, a string replacing a `LoroText`
+        updates it, and moves inside a `LoroMovableList` use `mov`.
+
+        The patch is checked before it is applied, so either all of it is
+        applied or, if an operation fails, including a `test`, a
+        `JsonPatchError` is raised and the document is left unchanged.
+        """
+        ...
+
     def diff(self, a: Frontiers, b: Frontiers) -> DiffBatch:
         r"""
         Calculate the diff between two versions
+
+        The GIL is released while the diff is calculated.
         """
         ...
 
@@ -914,6 +1621,25 @@ class LoroDoc:
         """
         ...
 
+    def redact(
+        self,
+        version_range: VersionRange,
+        policy: RedactPolicy,
+        *,
+        snapshot: bool = True,
+    ) -> bytes:
+        r"""
+        Redacts the operations within the specified version range according to
+        `policy`, and exports the whole redacted history.
+
+        Unlike `redact_json_updates`, the content can be kept, nulled, hashed
+        or dropped per container and per map key, and the values of some text
+        marks can be preserved. The operations keep their ids, so the result
+        can be imported by peers that already have the unredacted history.
+
+        Returns a snapshot, or all the updates if `snapshot` is false.
+        """
+
     def set_next_commit_options(
         self,
         origin: typing.Optional[str] = None,
@@ -985,6 +1711,40 @@ class LoroDoc:
         """
         ...
 
+    def __copy__(self) -> LoroDoc:
+        r"""
+        Shallow copy of the document, same as `fork`.
+        """
+        ...
+
+    def __deepcopy__(self, memo: typing.Any) -> LoroDoc:
+        r"""
+        Deep copy of the document, same as `fork`.
+        """
+        ...
+
+    def __getstate__(self) -> bytes:
+        r"""
+        Pickle the document as a full snapshot.
+
+        The unpickled document gets a new random PeerID, just like `fork`.
+        """
+        ...
+
+    def __setstate__(self, state: bytes) -> None: ...
+
+class LoroListIter:
+    r"""
+    Iterator over the elements of a `LoroList` or a `LoroMovableList`.
+
+    Elements are fetched lazily one at a time, so iterating a large list does
+    not copy it. Like Python lists, edits made to the list during iteration are
+    visible to the iterator.
+    """
+    def __iter__(self) -> LoroListIter: ...
+    def __next__(self) -> ValueOrContainer: ...
+    def __length_hint__(self) -> int: ...
+
 class LoroList:
     is_attached: bool
     id: ContainerID
@@ -993,7 +1753,10 @@ class LoroList:
     ): ...
     def insert(self, pos: int, v: LoroValue) -> None:
         r"""
-        Insert a value at the given position.
+        Insert a value before the given position.
+
+        Like `list.insert`, a negative position counts from the end, and a
+        position out of range inserts at the start or at the end.
         """
         ...
 
@@ -1023,9 +1786,12 @@ class LoroList:
         """
         ...
 
-    def pop(self) -> typing.Optional[LoroValue]:
+    def pop(self, index: typing.Optional[int] = None) -> LoroValue:
         r"""
-        Pop the last element of the list.
+        Remove and return the element at `index` (the last one by default).
+
+        Raises `IndexError` if the list is empty or the index is out of
+        range. A sub-container is returned as its container id.
         """
         ...
 
@@ -1044,6 +1810,67 @@ class LoroList:
     def for_each(self, f: typing.Callable[[ValueOrContainer], None]) -> None:
         r"""
         Iterate over the elements of the list.
+
+        If the callback raises, the remaining elements are skipped and the
+        exception is re-raised.
+        """
+        ...
+
+    def __iter__(self) -> LoroListIter: ...
+
+    def __reversed__(self) -> LoroListIter: ...
+
+    def __contains__(self, value: typing.Any) -> bool:
+        r"""
+        Whether the list contains the given value or container.
+
+        Containers are compared by their id.
+        """
+        ...
+
+    def index(self, value: typing.Any, start: int = 0, stop: typing.Optional[int] = None) -> int:
+        r"""
+        Return the index of the first occurrence of `value` in `list[start:stop]`.
+
+        Raises `ValueError` if the value is not present.
+        """
+        ...
+
+    def count(self, value: typing.Any) -> int:
+        r"""
+        Return the number of occurrences of `value`.
+        """
+        ...
+
+    def append(self, v: LoroValue) -> None:
+        r"""
+        Append a value to the end of the list. It is an alias of `push`.
+        """
+        ...
+
+    def extend(self, values: typing.Iterable[LoroValue]) -> None:
+        r"""
+        Append all the values of an iterable to the end of the list.
+        """
+        ...
+
+    def __iadd__(self, values: typing.Iterable[LoroValue]) -> LoroList: ...
+
+    def remove(self, value: typing.Any) -> None:
+        r"""
+        Remove the first occurrence of `value`.
+
+        Raises `ValueError` if the value is not present.
+        """
+        ...
+
+    def reverse(self) -> None:
+        r"""
+        Reverse the list in place.
+
+        A `LoroList` can only be reversed by deleting and re-inserting its
+        values, so this raises `TypeError` if the list contains containers.
+        Use a `LoroMovableList` to reorder containers.
         """
         ...
 
@@ -1195,6 +2022,28 @@ class LoroList:
         """
         ...
 
+class LoroMapIter:
+    r"""
+    Iterator over the keys, values or items of a `LoroMap`.
+
+    Only the keys are collected when the iterator is created; values are
+    fetched lazily. Entries deleted during the iteration are skipped.
+    """
+    def __iter__(self) -> LoroMapIter: ...
+    def __next__(self) -> typing.Any: ...
+    def __length_hint__(self) -> int: ...
+
+class LoroMapView:
+    r"""
+    View of the keys, values or items of a `LoroMap`.
+
+    The view reflects the current entries of the map: it can be iterated over
+    several times, and its length and membership follow the edits of the map.
+    """
+    def __len__(self) -> int: ...
+    def __iter__(self) -> LoroMapIter: ...
+    def __contains__(self, obj: typing.Any) -> bool: ...
+
 class LoroMap:
     is_attached: bool
     id: ContainerID
@@ -1234,9 +2083,45 @@ class LoroMap:
         """
         ...
 
-    def get(self, key: str) -> typing.Optional[ValueOrContainer]:
+    def __iter__(self) -> LoroMapIter: ...
+
+    def get(self, key: str, default: typing.Any = None) -> typing.Optional[ValueOrContainer]:
+        r"""
+        Get the value of the map with the given key, or `default` if the key is absent.
+        """
+        ...
+
+    def pop(self, key: str, *default: typing.Any) -> ValueOrContainer:
+        r"""
+        Remove `key` and return its value.
+
+        If the key is absent, `default` is returned if given, otherwise
+        `KeyError` is raised.
+        """
+        ...
+
+    def popitem(self) -> tuple[str, ValueOrContainer]:
+        r"""
+        Remove and return an arbitrary `(key, value)` pair.
+
+        Raises `KeyError` if the map is empty.
+        """
+        ...
+
+    def setdefault(self, key: str, default: LoroValue = None) -> ValueOrContainer:
+        r"""
+        Return the value of `key`, inserting `default` first if the key is absent.
+        """
+        ...
+
+    def update(
+        self,
+        other: typing.Union[typing.Mapping[str, LoroValue], typing.Iterable[tuple[str, LoroValue]], None] = None,
+        **kwargs: LoroValue,
+    ) -> None:
         r"""
-        Get the value of the map with the given key.
+        Update the map from a mapping or an iterable of `(key, value)` pairs,
+        then from keyword arguments, like `dict.update`.
         """
         ...
 
@@ -1259,6 +2144,30 @@ class LoroMap:
         """
         ...
 
+    def set_deep(
+        self, key: str, value: LoroValue, policy: typing.Optional[DeepPolicy] = None
+    ) -> None:
+        r"""
+        Insert a value whose dicts, lists and strings are stored as containers,
+        according to `policy`, so that concurrent edits of its parts merge.
+
+        An existing value of the key is replaced.
+        """
+        ...
+
+    def update_deep(
+        self, key: str, value: LoroValue, policy: typing.Optional[DeepPolicy] = None
+    ) -> None:
+        r"""
+        Update the value of a key like `set_deep`, but only edit the parts of
+        the existing value that changed.
+
+        Maps are updated entry by entry, lists item by item, and texts with
+        `LoroText.update`. A part stored in a container of another type than
+        the one `policy` gives is replaced.
+        """
+        ...
+
     def get_value(self) -> LoroValue:
         r"""
         Get the shallow value of the map.
@@ -1287,19 +2196,23 @@ class LoroMap:
         """
         ...
 
-    def keys(self) -> list[str]:
+    def keys(self) -> LoroMapView:
         r"""
-        Get the keys of the map.
+        A view of the keys of the map.
         """
         ...
 
-    def values(self) -> list[ValueOrContainer]:
+    def values(self) -> LoroMapView:
         r"""
-        Get the values of the map.
+        A view of the values of the map.
         """
         ...
 
-    def items(self) -> list[tuple[str, ValueOrContainer]]: ...
+    def items(self) -> LoroMapView:
+        r"""
+        A view of the `(key, value)` pairs of the map.
+        """
+        ...
 
     def get_last_editor(self, key: str) -> typing.Optional[int]:
         r"""
@@ -1337,19 +2250,79 @@ class LoroMovableList:
     ): ...
     def insert(self, pos: int, v: LoroValue) -> None:
         r"""
-        Insert a value at the given position.
+        Insert a value before the given position.
+
+        Like `list.insert`, a negative position counts from the end, and a
+        position out of range inserts at the start or at the end.
         """
         ...
 
     def delete(self, pos: int, len: int) -> None:
         r"""
-        Delete the value at the given position.
+        Delete the value at the given position.
+        """
+        ...
+
+    def get(self, index: int) -> typing.Optional[ValueOrContainer]:
+        r"""
+        Get the value at the given position.
+        """
+        ...
+
+    def __iter__(self) -> LoroListIter: ...
+
+    def __reversed__(self) -> LoroListIter: ...
+
+    def __contains__(self, value: typing.Any) -> bool:
+        r"""
+        Whether the list contains the given value or container.
+
+        Containers are compared by their id.
+        """
+        ...
+
+    def index(self, value: typing.Any, start: int = 0, stop: typing.Optional[int] = None) -> int:
+        r"""
+        Return the index of the first occurrence of `value` in `list[start:stop]`.
+
+        Raises `ValueError` if the value is not present.
+        """
+        ...
+
+    def count(self, value: typing.Any) -> int:
+        r"""
+        Return the number of occurrences of `value`.
+        """
+        ...
+
+    def append(self, v: LoroValue) -> None:
+        r"""
+        Append a value to the end of the list. It is an alias of `push`.
+        """
+        ...
+
+    def extend(self, values: typing.Iterable[LoroValue]) -> None:
+        r"""
+        Append all the values of an iterable to the end of the list.
+        """
+        ...
+
+    def __iadd__(self, values: typing.Iterable[LoroValue]) -> LoroMovableList: ...
+
+    def remove(self, value: typing.Any) -> None:
+        r"""
+        Remove the first occurrence of `value`.
+
+        Raises `ValueError` if the value is not present.
         """
         ...
 
-    def get(self, index: int) -> typing.Optional[ValueOrContainer]:
+    def reverse(self) -> None:
         r"""
-        Get the value at the given position.
+        Reverse the list in place by moving its elements.
+
+        The elements keep their identity, so concurrent edits on them are
+        preserved.
         """
         ...
 
@@ -1381,9 +2354,12 @@ class LoroMovableList:
         """
         ...
 
-    def pop(self) -> typing.Optional[ValueOrContainer]:
+    def pop(self, index: typing.Optional[int] = None) -> ValueOrContainer:
         r"""
-        Pop the last element of the list.
+        Remove and return the element at `index` (the last one by default).
+
+        Raises `IndexError` if the list is empty or the index is out of
+        range.
         """
         ...
 
@@ -1502,6 +2478,9 @@ class LoroMovableList:
     def for_each(self, f: typing.Any) -> None:
         r"""
         Iterate over the elements of the list.
+
+        If the callback raises, the remaining elements are skipped and the
+        exception is re-raised.
         """
         ...
 
@@ -1696,6 +2675,98 @@ class LoroText:
         """
         ...
 
+    def to_markdown(
+        self, marks: typing.Optional[typing.Mapping[str, typing.Optional[MarkStyle]]] = None
+    ) -> str:
+        r"""
+        Render the text as Markdown.
+
+        Marks are rendered with `MarkStyle.markdown_defaults()`, updated with
+        `marks`. Mapping a key to `None` ignores that mark, as are the marks
+        without a style. Overlapping marks are closed and reopened so that the
+        output is properly nested, and the text is escaped.
+
+        Marks are closed at line breaks, and whitespace at the edges of a mark
+        is moved outside of it. Emphasis delimiters that Markdown would not read
+        as such, e.g. `**` between a letter and punctuation, are replaced by the
+        HTML tags `<strong>`, `<em>` and `<s>`, which `import_markdown()` reads.
+        """
+        ...
+
+    def to_html(
+        self, marks: typing.Optional[typing.Mapping[str, typing.Optional[MarkStyle]]] = None
+    ) -> str:
+        r"""
+        Render the text as HTML.
+
+        Marks are rendered with `MarkStyle.html_defaults()`, updated with
+        `marks`. Mapping a key to `None` ignores that mark, as are the marks
+        without a style. Overlapping marks are closed and reopened so that the
+        output is properly nested, the text is escaped and line breaks are
+        rendered as `<br>`.
+        """
+        ...
+
+    def import_markdown(
+        self,
+        markdown: str,
+        *,
+        keys: typing.Optional[typing.Mapping[str, typing.Optional[str]]] = None,
+        diff: bool = False,
+    ) -> None:
+        r"""
+        Replace the content of the text with the given Markdown.
+
+        Emphasis, `**bold**`, `~~strikethrough~~`, code spans, links and
+        `<u>underline</u>` become the marks `italic`, `bold`, `strikethrough`,
+        `code`, `link` and `underline`, as do the HTML tags `<em>`,
+        `<strong>` and `<s>`. `keys` maps these names to other mark
+        keys, or to `None` to drop the mark. Block syntax such as headings and
+        lists is kept as text.
+
+        The marks are applied with the expand behavior configured with
+        `LoroDoc.config_text_style`, and each of their keys must be configured:
+        otherwise `InvalidArgumentError` is raised before the text is changed.
+        `StyleConfigMap.default_rich_text_config()` does not configure
+        `strikethrough`, which can be configured, or mapped with `keys`.
+
+        If `diff` is true, the text is changed with `update()` and only the marks
+        that changed are applied, so that importing a document close to the
+        current content generates few ops. Marks with other keys, such as
+        comments, are kept.
+        """
+        ...
+
+    def import_html(
+        self,
+        html: str,
+        *,
+        keys: typing.Optional[typing.Mapping[str, typing.Optional[str]]] = None,
+        diff: bool = False,
+    ) -> None:
+        r"""
+        Replace the content of the text with the given HTML.
+
+        `<b>`/`<strong>`, `<i>`/`<em>`, `<u>`, `<s>`/`<del>`, `<code>`,
+        `<a href>` and `<mark>` become the marks `bold`, `italic`, `underline`,
+        `strikethrough`, `code`, `link` and `highlight`. `keys` maps these names
+        to other mark keys, or to `None` to drop the mark. `<br>` and block
+        elements become line breaks, other tags are ignored and whitespace is
+        collapsed outside of `<pre>`.
+
+        The marks are applied with the expand behavior configured with
+        `LoroDoc.config_text_style`, and each of their keys must be configured:
+        otherwise `InvalidArgumentError` is raised before the text is changed.
+        `StyleConfigMap.default_rich_text_config()` does not configure
+        `strikethrough`, which can be configured, or mapped with `keys`.
+
+        If `diff` is true, the text is changed with `update()` and only the marks
+        that changed are applied, so that importing a document close to the
+        current content generates few ops. Marks with other keys, such as
+        comments, are kept.
+        """
+        ...
+
     def get_richtext_value(self) -> LoroValue:
         r"""
         Get the rich text value of the text container.
@@ -1783,11 +2854,36 @@ class LoroText:
         """
         ...
 
+class LoroTreeWalk:
+    r"""
+    Depth-first, pre-order iterator over the nodes of a `LoroTree`.
+
+    The children of a node are only looked up once the node is reached, so
+    the tree is not copied up front. Siblings are visited in their order in
+    the tree.
+    """
+    def __iter__(self) -> LoroTreeWalk: ...
+    def __next__(self) -> TreeID: ...
+
 class LoroTree:
     is_attached: bool
     roots: list[TreeID]
     id: ContainerID
     def __contains__(self, target: TreeID) -> bool: ...
+    def __iter__(self) -> LoroTreeWalk:
+        r"""
+        Iterate over all the nodes of the forest in depth-first pre-order.
+        """
+        ...
+
+    def walk(self, parent: typing.Optional[TreeID] = None) -> LoroTreeWalk:
+        r"""
+        Walk the nodes in depth-first pre-order.
+
+        If `parent` is `None`, the whole forest is walked. Otherwise only the
+        subtree rooted at `parent` is walked, starting with `parent` itself.
+        """
+        ...
     def __new__(
         cls,
     ): ...
@@ -2193,15 +3289,50 @@ class TreeNode:
     index: int
 
 class UndoItemMeta:
+    r"""
+    The metadata of an undo/redo item, returned by the `on_push` listener.
+    """
+
     value: LoroValue
     cursors: list[CursorWithPos]
 
+    def __new__(
+        cls,
+        value: LoroValue = None,
+        cursors: typing.Optional[typing.Sequence[CursorWithPos]] = None,
+    ): ...
+
 class CursorWithPos:
+    r"""
+    A cursor and its position when the undo/redo item was pushed.
+    """
+
     cursor: Cursor
     pos: AbsolutePosition
 
+    def __new__(cls, cursor: Cursor, pos: AbsolutePosition): ...
+
 class UndoManager:
-    def __new__(cls, doc: LoroDoc): ...
+    def __new__(
+        cls,
+        doc: LoroDoc,
+        containers: typing.Optional[typing.Sequence[ContainerID]] = None,
+        *,
+        exportable: bool = False,
+    ):
+        r"""
+        Create a new UndoManager.
+
+        If `containers` is given, only the local changes to these containers
+        and their descendants are recorded and reverted, so that independent
+        parts of a document can have their own undo stacks. The other local
+        changes are treated like remote changes. Otherwise, Loro's own undo
+        manager records the local changes of the whole document, unless
+        `exportable` is true: its stacks cannot be exported, so the local
+        changes of the whole document are then recorded the same way as the
+        ones of a scoped manager, and `export_state` can be used.
+        """
+        ...
     def undo(self) -> bool:
         r"""
         Undo the last change made by the peer.
@@ -2266,12 +3397,17 @@ class UndoManager:
     def set_on_push(
         self,
         on_push: typing.Callable[
-            [UndoOrRedo, CounterSpan, typing.Optional[DiffEvent]], UndoItemMeta
+            [UndoOrRedo, CounterSpan, typing.Optional[DiffEvent]],
+            typing.Optional[UndoItemMeta],
         ],
     ) -> None:
         r"""
         Set the listener for push events.
         The listener will be called when a new undo/redo item is pushed into the stack.
+
+        It should return an `UndoItemMeta`, or `None` for no metadata. If it returns
+        anything else or raises, the error is reported through `sys.unraisablehook`
+        and an empty meta is stored instead.
         """
         ...
 
@@ -2281,6 +3417,9 @@ class UndoManager:
         r"""
         Set the listener for pop events.
         The listener will be called when an undo/redo item is popped from the stack.
+
+        It is called once the item has been applied, with the cursors of its meta
+        resolved in the current version of the document.
         """
         ...
 
@@ -2337,6 +3476,28 @@ class UndoManager:
         """
         ...
 
+    def export_state(self) -> bytes:
+        r"""
+        Export the undo and redo stacks, with the metadata of their items.
+
+        The state can be imported with `import_state` by an undo manager of the
+        same document and the same containers, e.g. after the application
+        restarts. An undo manager of the whole document supports it only if it
+        is created with `exportable=True`, since the stacks of Loro's own undo
+        manager are not accessible.
+        """
+        ...
+
+    def import_state(self, state: bytes) -> bool:
+        r"""
+        Replace the undo and redo stacks with the ones exported by `export_state`.
+
+        If the document does not include the version the state was exported
+        at, the state is stale: it is discarded, the stacks are left unchanged
+        and `False` is returned.
+        """
+        ...
+
 class VersionVector:
     def __new__(
         cls,
@@ -2439,6 +3600,14 @@ class ContainerID:
         counter: int
         container_type: ContainerType
 
+    @classmethod
+    def parse(cls, s: str) -> ContainerID:
+        r"""
+        Parse a container id from its canonical string form,
+        e.g. `cid:root-text:Text` or `cid:0@1:Map`.
+        """
+        ...
+
 class ContainerType:
     class Text(ContainerType):
         pass
@@ -2466,6 +3635,64 @@ class DiffBatch:
     def __init__(self): ...
     def push(self, cid: ContainerID, diff: Diff) -> None: ...
     def get_diff(self) -> list[tuple[ContainerID, Diff]]: ...
+    def to_report(self) -> DiffReport:
+        r"""
+        Create a human-readable report of the changes, addressed by the paths
+        of the containers in the document.
+
+        Text changes are also rendered as unified diffs, and map and list changes
+        as JSON patches (RFC 6902). The paths and the unified diffs are only
+        available if the batch was calculated by `LoroDoc.diff`; otherwise the
+        containers are addressed by their ids.
+        """
+        ...
+
+    def to_json_patch(self) -> list[dict[str, typing.Any]]:
+        r"""
+        Convert the changes to a JSON patch (RFC 6902), as a list of operations
+        that `LoroDoc.apply_json_patch` can apply.
+
+        Maps and lists are patched, while texts, counters and trees are replaced
+        by their new values. The root containers that do not exist before the
+        changes are added empty first, so that the patch applies to a document
+        at that version. Only a batch calculated by `LoroDoc.diff` can be
+        converted, since the paths are resolved in the document.
+        """
+        ...
+
+class DiffReport:
+    r"""
+    A human-readable report of the changes in a `DiffBatch`, created by
+    `DiffBatch.to_report`.
+
+    `str(report)` renders all the entries.
+    """
+
+    entries: list[DiffReportEntry]
+
+class DiffReportEntry:
+    r"""
+    The changes of a container in a `DiffReport`.
+    """
+
+    container: ContainerID
+    path: str
+    r"""
+    The JSON pointer of the container in the document, or its id if it
+    cannot be resolved.
+    """
+    changes: list[str]
+    r"""
+    One line per change.
+    """
+    unified_diff: typing.Optional[str]
+    r"""
+    The unified diff of a text, if the text before the changes is known.
+    """
+    json_patch: typing.Optional[list[dict[str, typing.Any]]]
+    r"""
+    The JSON patch of a map or a list.
+    """
 
 class Diff:
     class List(Diff):
@@ -2729,6 +3956,415 @@ class EphemeralStore:
         """
         ...
 
+    def updates(
+        self,
+        *,
+        maxsize: int = 1024,
+        overflow: OverflowPolicy = OverflowPolicy.Error,
+    ) -> EventStream[EphemeralStoreEvent]:
+        r"""
+        Return an async iterator over the `EphemeralStoreEvent`s of the store.
+
+        The events are buffered until they are consumed, in a buffer of at most
+        `maxsize` events; `overflow` decides what happens when it is full.
+        """
+        ...
+
+    def local_updates(
+        self,
+        *,
+        maxsize: int = 1024,
+        overflow: OverflowPolicy = OverflowPolicy.Error,
+    ) -> EventStream[bytes]:
+        r"""
+        Return an async iterator over the encoded local updates of the store.
+
+        It yields the same bytes as `subscribe_local_updates`, buffered in a buffer
+        of at most `maxsize` updates; `overflow` decides what happens when it is full.
+        """
+        ...
+
+class MessageKind(Enum):
+    r"""
+    The kind of a sync protocol message.
+    """
+    Hello = 1
+    r"""
+    The version vector of the sender. The receiver answers with the updates
+    the sender is missing.
+    """
+    Updates = 2
+    r"""
+    Updates exported by the sender.
+    """
+    Ack = 3
+    r"""
+    The version vector of the sender after it imported updates.
+    """
+    Ephemeral = 4
+    r"""
+    Encoded `EphemeralStore` states.
+    """
+
+class MarkStyle:
+    r"""
+    How a mark is rendered: the text it covers is wrapped between `open` and
+    `close`.
+
+    `{value}` in `open` or `close` is replaced by the value of the mark, escaped
+    for the output format. It is how `link` marks render their URL.
+
+    The text covered by a `verbatim` mark is not escaped in Markdown, which is
+    what code spans need. HTML text is always escaped. If the delimiters of a
+    `verbatim` mark are backticks, more of them are used when the text contains
+    a run of backticks as long, so that the code span is not closed early.
+
+    `link` marks whose URL has a scheme other than `http`, `https` or `mailto`,
+    such as `javascript:`, are not rendered.
+    """
+    open: str
+    close: str
+    verbatim: bool
+    def __new__(cls, open: str, close: str, verbatim: bool = False) -> MarkStyle: ...
+    @staticmethod
+    def markdown_defaults() -> dict[str, MarkStyle]:
+        r"""
+        The styles used by `LoroText.to_markdown()` by default.
+        """
+        ...
+    @staticmethod
+    def html_defaults() -> dict[str, MarkStyle]:
+        r"""
+        The styles used by `LoroText.to_html()` by default.
+        """
+        ...
+
+class LoroStore:
+    r"""
+    Persists a `LoroDoc` as a base snapshot plus an append-only log of its
+    local updates.
+
+    Every local update of the document is appended to the log as soon as it is
+    committed. With `fsync=True`, it is durable once `commit()` returns;
+    otherwise it is only guaranteed to be durable after `flush()`. When the log
+    exceeds `compact_threshold` bytes, it is compacted into a new snapshot, a
+    `ShallowSnapshot` if `shallow` is true.
+
+    Imported updates are not local updates: they are persisted by `flush()`,
+    `compact()` or `close()`.
+
+    Errors that occur while appending an update in the middle of a commit are
+    raised by the next call to the store.
+    """
+    doc: LoroDoc
+    r"""
+    The persisted document.
+    """
+    log_size: int
+    r"""
+    The size of the log, in bytes.
+    """
+    closed: bool
+    r"""
+    Whether the store has been closed.
+    """
+    @staticmethod
+    def open_directory(
+        path: typing.Union[str, os.PathLike[str]],
+        doc: typing.Optional[LoroDoc] = None,
+        *,
+        compact_threshold: int = 1048576,
+        shallow: bool = False,
+        fsync: bool = True,
+    ) -> LoroStore:
+        r"""
+        Open the store kept in the directory `path`, creating it if needed.
+
+        The stored snapshot and log are imported into `doc`, or into a new
+        document if it is not given. The ops `doc` already had are appended to
+        the log.
+        """
+        ...
+    @staticmethod
+    def open_sqlite(
+        path: typing.Union[str, os.PathLike[str]],
+        doc: typing.Optional[LoroDoc] = None,
+        *,
+        compact_threshold: int = 1048576,
+        shallow: bool = False,
+        fsync: bool = True,
+    ) -> LoroStore:
+        r"""
+        Open the store kept in the SQLite database `path`, creating it if needed.
+
+        The stored snapshot and log are imported into `doc`, or into a new
+        document if it is not given. The ops `doc` already had are appended to
+        the log.
+        """
+        ...
+    def flush(self) -> None:
+        r"""
+        Persist the ops that are not stored yet, such as imported ones, and make
+        the log durable.
+        """
+        ...
+    def compact(self) -> None:
+        r"""
+        Replace the snapshot with a new one and clear the log.
+        """
+        ...
+    def close(self) -> None:
+        r"""
+        Flush the store and stop persisting the document.
+
+        Closing a closed store does nothing.
+        """
+        ...
+    def __enter__(self) -> LoroStore: ...
+    def __exit__(self, *args: typing.Any) -> None: ...
+
+class SyncSession:
+    r"""
+    One end of a sync session between two peers editing the same document.
+
+    The session does not do any I/O: it produces frames that must be delivered
+    to the other peer, and consumes the frames received from it. The handshake
+    goes as follows:
+
+    1. Both peers call `start()` and send the `Hello` frame carrying their
+       version vector. A peer that receives a `Hello` before calling `start()`
+       answers with its own `Hello`.
+    2. On `Hello`, a peer answers with the `Updates` the other peer is missing.
+    3. On `Updates`, a peer imports them and answers with an `Ack` carrying its
+       new version vector. If the updates depend on ops it does not have, it
+       sends a `Hello` again to get them.
+
+    Local edits are sent with `flush()`. If an `EphemeralStore` is given, its
+    local updates are sent by `flush()` as well, and the received ones are
+    applied to it.
+
+    Every frame is made of a 6 bytes header (protocol version, `MessageKind`,
+    big-endian `u32` payload length) followed by the payload.
+    """
+    remote_version: typing.Optional[VersionVector]
+    r"""
+    The latest version vector announced or acknowledged by the remote peer.
+    """
+    is_synced: bool
+    r"""
+    Whether the remote peer acknowledged all the ops of the local document.
+    """
+    def __new__(
+        cls, doc: LoroDoc, ephemeral: typing.Optional[EphemeralStore] = None
+    ) -> SyncSession: ...
+    def start(self) -> list[bytes]:
+        r"""
+        Start the handshake.
+
+        Returns the frames to send to the remote peer: a `Hello`, followed by
+        the whole `EphemeralStore` state if there is one.
+        """
+        ...
+
+    def receive(self, data: bytes) -> list[bytes]:
+        r"""
+        Consume bytes received from the remote peer.
+
+        `data` may contain any number of frames, and may end with an incomplete
+        frame which is kept until the rest of it is received. Returns the frames
+        to send back to the remote peer.
+
+        If a frame cannot be decoded or handled, it is dropped and the error is
+        raised. The bytes after it are kept, and the frames produced for the
+        frames before it are returned by the next `receive` or `flush`.
+        """
+        ...
+
+    def flush(self) -> list[bytes]:
+        r"""
+        Return the frames carrying the local changes the remote peer has not
+        received yet.
+
+        Document updates are only sent once the remote version is known, that
+        is after the remote `Hello` has been received.
+        """
+        ...
+
+class LoroModel:
+    r"""
+    Base class of typed views over a `LoroMap`.
+
+    Subclasses declare their fields with annotations:
+
+    ```python
+    class Task(LoroModel):
+        title: str
+        done: bool = False
+        notes: LoroText
+        assignee: Optional[Person]
+    ```
+
+    Every field reads and writes the map entry with the same name. Values are
+    converted to `LoroValue` and checked against the annotation on write,
+    which raises `TypeError` on mismatch; `int` is accepted for `float`, and
+    `list[...]`, `dict[str, ...]` and `Optional`/`Union` are checked
+    recursively. A missing value reads as a copy of the class default, or
+    `None` for an optional field: like the values read from the map, it is
+    not written back when mutated, and must be assigned instead. The defaults
+    are checked against the annotations when the class is created, or when
+    the annotations are first resolved if they refer to a class declared
+    later.
+
+    Fields annotated with a container type (`LoroText`, `LoroList`,
+    `LoroMovableList`, `LoroMap`, `LoroCounter`, `LoroTree`) or with another
+    model are child containers, created with `get_or_create_container` on first
+    access. They are edited in place, except text fields which accept a `str`
+    and update their content to it.
+    """
+    map: LoroMap
+    r"""
+    The map the model is bound to.
+    """
+    def __new__(cls: typing.Type[_M], map: LoroMap, **fields: typing.Any) -> _M:
+        r"""
+        Bind the model to `map`, then assign the given fields.
+        """
+    def __eq__(self, other: object) -> bool:
+        r"""
+        Models are equal if they have the same class and are bound to the same
+        map.
+        """
+
+class ModelField:
+    r"""
+    A field of a `LoroModel`, stored in the entry of the bound map with the
+    same name.
+
+    Fields are created from the annotations of the model class; they do not
+    need to be declared explicitly.
+    """
+    name: str
+    r"""
+    The name of the field and of its map entry.
+    """
+    def __get__(self, instance: typing.Any, owner: typing.Any = None) -> typing.Any: ...
+    def __set__(self, instance: LoroModel, value: typing.Any) -> None: ...
+    def __delete__(self, instance: LoroModel) -> None: ...
+
+class Schema:
+    r"""
+    The schema of a container or of a value.
+
+    Schemas are built with the static methods of this class and combined
+    into a `DocSchema`.
+    """
+    @staticmethod
+    def any() -> Schema:
+        r"""
+        Accept any value or container.
+        """
+    @staticmethod
+    def value(annotation: typing.Any) -> Schema:
+        r"""
+        A value matching the type annotation `annotation`, checked like the
+        fields of a `LoroModel`: `int` is accepted for `float`, and
+        `list[...]`, `dict[str, ...]` and `Optional`/`Union` are checked
+        recursively.
+        """
+    @staticmethod
+    def text() -> Schema:
+        r"""
+        A `LoroText`.
+        """
+    @staticmethod
+    def counter() -> Schema:
+        r"""
+        A `LoroCounter`.
+        """
+    @staticmethod
+    def list(item: Schema) -> Schema:
+        r"""
+        A `LoroList` whose elements match `item`.
+        """
+    @staticmethod
+    def movable_list(item: Schema) -> Schema:
+        r"""
+        A `LoroMovableList` whose elements match `item`.
+        """
+    @staticmethod
+    def map(
+        fields: typing.Mapping[str, Schema],
+        *,
+        required: typing.Optional[typing.Sequence[str]] = None,
+        extra: typing.Optional[Schema] = None,
+    ) -> Schema:
+        r"""
+        A `LoroMap` with the given fields.
+
+        The keys in `required` must be present. Keys that are not fields are
+        rejected, unless `extra` is given, in which case their entries must
+        match it.
+        """
+    @staticmethod
+    def tree(meta: typing.Optional[Schema] = None) -> Schema:
+        r"""
+        A `LoroTree`. If `meta` is given, the metadata map of every node must
+        match it.
+        """
+    @staticmethod
+    def one_of(*options: Schema) -> Schema:
+        r"""
+        Accept anything matching one of `options`.
+        """
+
+class DocSchema:
+    r"""
+    The schema of a document.
+
+    `roots` maps the name of every root container to its schema. With
+    `strict`, non-empty root containers that are not in `roots` are reported.
+
+    `version` is the current version of the schema, and `migrations` maps a
+    version `n` to a function that rewrites a document of version `n - 1`
+    into version `n`. The version of a document is stored under `"version"`
+    in the `"$schema"` root map; documents without one are at version 0.
+    """
+    version: int
+    r"""
+    The current version of the schema.
+    """
+    def __new__(
+        cls,
+        roots: typing.Mapping[str, Schema],
+        *,
+        strict: bool = True,
+        version: int = 0,
+        migrations: typing.Optional[
+            typing.Mapping[int, typing.Callable[[LoroDoc], None]]
+        ] = None,
+    ) -> DocSchema: ...
+    @staticmethod
+    def version_of(doc: LoroDoc) -> int:
+        r"""
+        The version of the schema `doc` was written with.
+        """
+
+class SchemaViolation:
+    r"""
+    A place where a document does not match its schema.
+    """
+    path: list[Index]
+    r"""
+    The path from the root of the document, as returned by
+    `get_path_to_container`, followed by the key or index of the offending
+    value if it is not a container.
+    """
+    container: typing.Optional[ContainerID]
+    r"""
+    The innermost container on the path.
+    """
+    message: str
+
 class EphemeralStoreEvent:
     r"""
     An event that represents changes in the EphemeralStore.
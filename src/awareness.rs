@@ -4,7 +4,13 @@ use std::{borrow::Cow, collections::HashMap};
 use loro::{awareness::EphemeralEventTrigger, LoroError, PeerID};
 use pyo3::{prelude::*, types::PyBytes};
 
-use crate::{err::PyLoroResult, event::Subscription, value::LoroValue};
+use crate::{
+    callback,
+    err::PyLoroResult,
+    event::Subscription,
+    stream::{EventStream, OverflowPolicy, DEFAULT_MAXSIZE},
+    value::LoroValue,
+};
 
 pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Awareness>()?;
@@ -88,7 +94,7 @@ pub struct PeerInfo {
 }
 
 #[pyclass]
-pub struct EphemeralStore(loro::awareness::EphemeralStore);
+pub struct EphemeralStore(pub(crate) loro::awareness::EphemeralStore);
 
 #[pymethods]
 impl EphemeralStore {
@@ -106,9 +112,7 @@ impl EphemeralStore {
     }
 
     pub fn apply(&mut self, data: &[u8]) -> PyLoroResult<()> {
-        self.0
-            .apply(data)
-            .map_err(|e| LoroError::DecodeError(e.into()))?;
+        self.0.apply(data).map_err(LoroError::DecodeError)?;
         Ok(())
     }
 
@@ -142,37 +146,48 @@ impl EphemeralStore {
 
     pub fn subscribe_local_updates(&self, callback: PyObject) -> Subscription {
         let subscription = self.0.subscribe_local_updates(Box::new(move |updates| {
-            Python::with_gil(|py| {
-                let b = callback.call1(py, (updates,)).unwrap();
-                b.extract::<bool>(py).unwrap()
-            })
+            Python::attach(|py| callback::call_keep_alive(py, &callback, (updates,)))
         }));
         subscription.into()
     }
 
     pub fn subscribe(&self, callback: PyObject) -> Subscription {
         let subscription = self.0.subscribe(Box::new(move |updates| {
-            Python::with_gil(|py| {
-                let b = callback
-                    .call1(
-                        py,
-                        (EphemeralStoreEvent {
-                            by: match updates.by {
-                                EphemeralEventTrigger::Local => "Local".to_string(),
-                                EphemeralEventTrigger::Import => "Import".to_string(),
-                                EphemeralEventTrigger::Timeout => "Timeout".to_string(),
-                            },
-                            added: updates.added.to_vec(),
-                            updated: updates.updated.to_vec(),
-                            removed: updates.removed.to_vec(),
-                        },),
-                    )
-                    .unwrap();
-                b.extract::<bool>(py).unwrap()
+            Python::attach(|py| {
+                callback::call_keep_alive(py, &callback, (EphemeralStoreEvent::from(updates),))
             })
         }));
         subscription.into()
     }
+
+    /// Return an async iterator over the `EphemeralStoreEvent`s of the store.
+    ///
+    /// The events are buffered until they are consumed, in a buffer of at most
+    /// `maxsize` events; `overflow` decides what happens when it is full.
+    #[pyo3(signature = (*, maxsize=DEFAULT_MAXSIZE, overflow=OverflowPolicy::Error))]
+    pub fn updates(&self, maxsize: usize, overflow: OverflowPolicy) -> PyResult<EventStream> {
+        EventStream::new(maxsize, overflow, |sender| {
+            Some(self.0.subscribe(Box::new(move |updates| {
+                Python::attach(|py| match Py::new(py, EphemeralStoreEvent::from(updates)) {
+                    Ok(event) => sender.send(py, event.into_any()),
+                    Err(_) => true,
+                })
+            })))
+        })
+    }
+
+    /// Return an async iterator over the encoded local updates of the store.
+    ///
+    /// It yields the same bytes as `subscribe_local_updates`, buffered in a buffer
+    /// of at most `maxsize` updates; `overflow` decides what happens when it is full.
+    #[pyo3(signature = (*, maxsize=DEFAULT_MAXSIZE, overflow=OverflowPolicy::Error))]
+    pub fn local_updates(&self, maxsize: usize, overflow: OverflowPolicy) -> PyResult<EventStream> {
+        EventStream::new(maxsize, overflow, |sender| {
+            Some(self.0.subscribe_local_updates(Box::new(move |updates| {
+                Python::attach(|py| sender.send(py, PyBytes::new(py, updates).into_any().unbind()))
+            })))
+        })
+    }
 }
 
 #[pyclass(get_all, str)]
@@ -193,3 +208,18 @@ impl std::fmt::Display for EphemeralStoreEvent {
         )
     }
 }
+
+impl From<&loro::awareness::EphemeralStoreEvent> for EphemeralStoreEvent {
+    fn from(updates: &loro::awareness::EphemeralStoreEvent) -> Self {
+        Self {
+            by: match updates.by {
+                EphemeralEventTrigger::Local => "Local".to_string(),
+                EphemeralEventTrigger::Import => "Import".to_string(),
+                EphemeralEventTrigger::Timeout => "Timeout".to_string(),
+            },
+            added: updates.added.to_vec(),
+            updated: updates.updated.to_vec(),
+            removed: updates.removed.to_vec(),
+        }
+    }
+}
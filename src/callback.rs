@@ -0,0 +1,64 @@
+//! Helpers to invoke Python callbacks from inside Loro.
+//!
+//! Subscribers are called by Loro in the middle of `commit()`, `import_()`,
+//! `checkout()` and friends, where there is no way to hand a Python exception
+//! back to the caller. Instead of panicking, exceptions raised by a callback
+//! are reported through `sys.unraisablehook`, the same way Python reports
+//! exceptions raised in `__del__` or in weakref callbacks.
+use pyo3::{call::PyCallArgs, prelude::*};
+
+/// Call `callback` with `args`.
+///
+/// If the callback raises, the exception is reported through
+/// `sys.unraisablehook` and `None` is returned.
+pub fn call<'py, A>(py: Python<'py>, callback: &Py<PyAny>, args: A) -> Option<Bound<'py, PyAny>>
+where
+    A: PyCallArgs<'py>,
+{
+    let callback = callback.bind(py);
+    match callback.call1(args) {
+        Ok(ret) => Some(ret),
+        Err(e) => {
+            e.write_unraisable(py, Some(callback));
+            None
+        }
+    }
+}
+
+/// Call a subscriber whose return value tells whether it should stay subscribed.
+///
+/// Returning `False` removes the subscription. Any other return value, including
+/// `None` and a raised exception, keeps the subscription alive.
+pub fn call_keep_alive<'py, A>(py: Python<'py>, callback: &Py<PyAny>, args: A) -> bool
+where
+    A: PyCallArgs<'py>,
+{
+    call(py, callback, args).is_none_or(|ret| ret.extract::<bool>().unwrap_or(true))
+}
+
+/// Call `callback` and keep the first exception it raises in `err`.
+///
+/// It is used by the synchronous iteration helpers (`for_each`,
+/// `travel_change_ancestors`, ...) which can re-raise the exception to the
+/// caller once Loro returns. Once an exception is stored, further calls are
+/// skipped and `None` is returned.
+pub fn call_capture<'py, A>(
+    py: Python<'py>,
+    callback: &Py<PyAny>,
+    args: A,
+    err: &mut Option<PyErr>,
+) -> Option<Bound<'py, PyAny>>
+where
+    A: PyCallArgs<'py>,
+{
+    if err.is_some() {
+        return None;
+    }
+    match callback.bind(py).call1(args) {
+        Ok(ret) => Some(ret),
+        Err(e) => {
+            *err = Some(e);
+            None
+        }
+    }
+}
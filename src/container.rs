@@ -33,7 +33,17 @@ pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     tree::register_class(m)?;
     m.add_class::<LoroList>()?;
     m.add_class::<LoroMovableList>()?;
+    m.add_class::<list::LoroListIter>()?;
     m.add_class::<LoroCounter>()?;
     m.add_class::<LoroUnknown>()?;
+
+    // Register the containers as virtual subclasses of the `collections.abc`
+    // interfaces they implement, so that generic helpers accept them.
+    let abc = m.py().import("collections.abc")?;
+    abc.getattr("MutableMapping")?
+        .call_method1("register", (m.py().get_type::<LoroMap>(),))?;
+    let sequence = abc.getattr("MutableSequence")?;
+    sequence.call_method1("register", (m.py().get_type::<LoroList>(),))?;
+    sequence.call_method1("register", (m.py().get_type::<LoroMovableList>(),))?;
     Ok(())
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    callback,
     doc::LoroDoc,
     err::PyLoroResult,
     event::{DiffEvent, Subscription},
@@ -118,7 +119,7 @@ impl LoroCounter {
     pub fn subscribe(&self, callback: Py<PyAny>) -> Option<Subscription> {
         let subscription = self.0.subscribe(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.map(|s| s.into())
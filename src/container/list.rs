@@ -3,12 +3,16 @@ use std::sync::Arc;
 use loro::{ContainerTrait, LoroList as LoroListInner};
 use pyo3::prelude::*;
 use pyo3::{
-    exceptions::{PyIndexError, PyValueError},
+    exceptions::{PyIndexError, PyTypeError, PyValueError},
     BoundObject,
 };
 
-use crate::container::utils::{py_any_to_loro_values, slice_indices_positions, SliceOrInt};
+use crate::container::utils::{
+    clamp_index, find_index, normalize_index, py_any_to_loro_values, slice_indices_positions,
+    Needle, SliceOrInt,
+};
 use crate::{
+    callback,
     doc::LoroDoc,
     err::{PyLoroError, PyLoroResult},
     event::{DiffEvent, Subscription},
@@ -17,6 +21,89 @@ use crate::{
 
 use super::{Container, Cursor, Side};
 
+/// The list-like container walked by a [LoroListIter].
+#[derive(Debug, Clone)]
+pub(crate) enum ListHandler {
+    List(LoroListInner),
+    MovableList(loro::LoroMovableList),
+}
+
+impl ListHandler {
+    fn len(&self) -> usize {
+        match self {
+            Self::List(l) => l.len(),
+            Self::MovableList(l) => l.len(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<loro::ValueOrContainer> {
+        match self {
+            Self::List(l) => l.get(index),
+            Self::MovableList(l) => l.get(index),
+        }
+    }
+}
+
+/// Iterator over the elements of a `LoroList` or a `LoroMovableList`.
+///
+/// Elements are fetched lazily one at a time, so iterating a large list does
+/// not copy it. Like Python lists, edits made to the list during iteration are
+/// visible to the iterator.
+#[pyclass]
+pub struct LoroListIter {
+    list: ListHandler,
+    next: usize,
+    reversed: bool,
+}
+
+impl LoroListIter {
+    pub(crate) fn new(list: ListHandler, reversed: bool) -> Self {
+        let next = if reversed { list.len() } else { 0 };
+        Self {
+            list,
+            next,
+            reversed,
+        }
+    }
+}
+
+#[pymethods]
+impl LoroListIter {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__(&mut self) -> Option<ValueOrContainer> {
+        if self.reversed {
+            if self.next == 0 {
+                return None;
+            }
+            self.next = self.next.min(self.list.len());
+            self.next = self.next.checked_sub(1)?;
+            self.list.get(self.next).map(ValueOrContainer::from)
+        } else {
+            let item = self.list.get(self.next)?;
+            self.next += 1;
+            Some(item.into())
+        }
+    }
+
+    pub fn __length_hint__(&self) -> usize {
+        if self.reversed {
+            self.next.min(self.list.len())
+        } else {
+            self.list.len().saturating_sub(self.next)
+        }
+    }
+}
+
+pub(crate) fn value_of(item: loro::ValueOrContainer) -> loro::LoroValue {
+    match item {
+        loro::ValueOrContainer::Value(v) => v,
+        loro::ValueOrContainer::Container(c) => loro::LoroValue::Container(c.id()),
+    }
+}
+
 #[pyclass(frozen, sequence)]
 #[derive(Debug, Clone, Default)]
 pub struct LoroList(pub LoroListInner);
@@ -41,9 +128,12 @@ impl LoroList {
         self.0.is_attached()
     }
 
-    /// Insert a value at the given position.
-    pub fn insert(&self, pos: usize, v: LoroValue) -> PyLoroResult<()> {
-        self.0.insert(pos, &v)?;
+    /// Insert a value before the given position.
+    ///
+    /// Like `list.insert`, a negative position counts from the end, and a
+    /// position out of range inserts at the start or at the end.
+    pub fn insert(&self, pos: isize, v: LoroValue) -> PyLoroResult<()> {
+        self.0.insert(clamp_index(pos, self.0.len()), &v)?;
         Ok(())
     }
 
@@ -81,10 +171,27 @@ impl LoroList {
         self.0.id().into()
     }
 
-    /// Pop the last element of the list.
-    #[inline]
-    pub fn pop(&self) -> PyLoroResult<Option<LoroValue>> {
-        let ans = self.0.pop()?.map(LoroValue::from);
+    /// Remove and return the element at `index` (the last one by default).
+    ///
+    /// Raises `IndexError` if the list is empty or the index is out of
+    /// range. A sub-container is
+    /// returned as its container id.
+    #[pyo3(signature = (index=None))]
+    pub fn pop(&self, index: Option<isize>) -> PyLoroResult<LoroValue> {
+        let len = self.0.len();
+        let index = match index {
+            Some(index) => normalize_index(index, len)?,
+            None if len == 0 => {
+                return Err(PyIndexError::new_err("pop from empty list").into());
+            }
+            None => len - 1,
+        };
+        let ans = self
+            .0
+            .get(index)
+            .map(|v| LoroValue::from(value_of(v)))
+            .ok_or(PyIndexError::new_err("index out of range"))?;
+        self.0.delete(index, 1)?;
         Ok(ans)
     }
 
@@ -103,12 +210,15 @@ impl LoroList {
     }
 
     /// Iterate over the elements of the list.
-    pub fn for_each(&self, f: Py<PyAny>) {
-        Python::attach(|py| {
-            self.0.for_each(&mut |v| {
-                f.call1(py, (ValueOrContainer::from(v),)).unwrap();
-            });
-        })
+    ///
+    /// If the callback raises, the remaining elements are skipped and the
+    /// exception is re-raised.
+    pub fn for_each(&self, py: Python<'_>, f: Py<PyAny>) -> PyResult<()> {
+        let mut err = None;
+        self.0.for_each(&mut |v| {
+            callback::call_capture(py, &f, (ValueOrContainer::from(v),), &mut err);
+        });
+        err.map_or(Ok(()), Err)
     }
 
     pub fn __getitem__<'py>(
@@ -136,7 +246,7 @@ impl LoroList {
             SliceOrInt::Int(idx) => {
                 let value: ValueOrContainer = self
                     .0
-                    .get(usize::try_from(idx)?)
+                    .get(normalize_index(idx, self.0.len())?)
                     .ok_or(PyIndexError::new_err("index out of range"))?
                     .into();
                 Ok(value.into_pyobject(py)?.into_any().into_bound())
@@ -151,6 +261,7 @@ impl LoroList {
     ) -> PyLoroResult<()> {
         match index {
             SliceOrInt::Int(idx) => {
+                let idx = normalize_index(idx, self.0.len())?;
                 let extracted: LoroValue = value.extract().map_err(PyLoroError::from)?;
                 self.0.delete(idx, 1).map_err(PyLoroError::from)?;
                 self.0.insert(idx, extracted.0).map_err(PyLoroError::from)?;
@@ -167,10 +278,8 @@ impl LoroList {
                         .delete(indices.start as usize, indices.slicelength)
                         .map_err(PyLoroError::from)?;
 
-                    let mut pos = indices.start as usize;
-                    for v in values {
+                    for (pos, v) in (indices.start as usize..).zip(values) {
                         self.0.insert(pos, v).map_err(PyLoroError::from)?;
-                        pos += 1;
                     }
                     Ok(())
                 } else {
@@ -184,7 +293,7 @@ impl LoroList {
                     }
 
                     let positions = slice_indices_positions(&indices);
-                    for (pos, v) in positions.into_iter().zip(values.into_iter()) {
+                    for (pos, v) in positions.into_iter().zip(values) {
                         self.0.delete(pos, 1).map_err(PyLoroError::from)?;
                         self.0.insert(pos, v).map_err(PyLoroError::from)?;
                     }
@@ -196,7 +305,7 @@ impl LoroList {
 
     pub fn __delitem__<'py>(&self, index: SliceOrInt<'py>) -> PyLoroResult<()> {
         match index {
-            SliceOrInt::Int(idx) => self.delete(idx, 1),
+            SliceOrInt::Int(idx) => self.delete(normalize_index(idx, self.0.len())?, 1),
             SliceOrInt::Slice(slice) => {
                 let len = self.__len__() as isize;
                 let indices = slice.indices(len).map_err(PyLoroError::from)?;
@@ -219,6 +328,107 @@ impl LoroList {
         }
     }
 
+    pub fn __iter__(&self) -> LoroListIter {
+        LoroListIter::new(ListHandler::List(self.0.clone()), false)
+    }
+
+    pub fn __reversed__(&self) -> LoroListIter {
+        LoroListIter::new(ListHandler::List(self.0.clone()), true)
+    }
+
+    /// Whether the list contains the given value or container.
+    ///
+    /// Containers are compared by their id.
+    pub fn __contains__(&self, value: &Bound<'_, PyAny>) -> bool {
+        Needle::extract(value)
+            .is_some_and(|n| find_index(0, self.0.len(), |i| self.0.get(i), &n).is_some())
+    }
+
+    /// Return the index of the first occurrence of `value` in `list[start:stop]`.
+    ///
+    /// Raises `ValueError` if the value is not present.
+    #[pyo3(signature = (value, start=0, stop=None))]
+    pub fn index(
+        &self,
+        value: &Bound<'_, PyAny>,
+        start: isize,
+        stop: Option<isize>,
+    ) -> PyResult<usize> {
+        let len = self.0.len();
+        let start = clamp_index(start, len);
+        let stop = stop.map_or(len, |s| clamp_index(s, len));
+        Needle::extract(value)
+            .and_then(|n| find_index(start, stop, |i| self.0.get(i), &n))
+            .ok_or_else(|| PyValueError::new_err("value is not in list"))
+    }
+
+    /// Return the number of occurrences of `value`.
+    pub fn count(&self, value: &Bound<'_, PyAny>) -> usize {
+        let Some(needle) = Needle::extract(value) else {
+            return 0;
+        };
+        let mut ans = 0;
+        self.0.for_each(|v| {
+            if needle.matches(&v) {
+                ans += 1;
+            }
+        });
+        ans
+    }
+
+    /// Append a value to the end of the list. It is an alias of `push`.
+    #[inline]
+    pub fn append(&self, v: LoroValue) -> PyLoroResult<()> {
+        self.push(v)
+    }
+
+    /// Append all the values of an iterable to the end of the list.
+    pub fn extend(&self, values: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        for v in values.try_iter()? {
+            let v: LoroValue = v?.extract()?;
+            self.0.push(v.0)?;
+        }
+        Ok(())
+    }
+
+    pub fn __iadd__(&self, values: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        self.extend(values)
+    }
+
+    /// Remove the first occurrence of `value`.
+    ///
+    /// Raises `ValueError` if the value is not present.
+    pub fn remove(&self, value: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        let pos = self.index(value, 0, None)?;
+        self.delete(pos, 1)
+    }
+
+    /// Reverse the list in place.
+    ///
+    /// A `LoroList` can only be reversed by deleting and re-inserting its
+    /// values, so this raises `TypeError` if the list contains containers.
+    /// Use a `LoroMovableList` to reorder containers.
+    pub fn reverse(&self) -> PyLoroResult<()> {
+        let mut values = Vec::with_capacity(self.0.len());
+        for i in 0..self.0.len() {
+            match self.0.get(i) {
+                Some(loro::ValueOrContainer::Value(v)) => values.push(v),
+                Some(loro::ValueOrContainer::Container(_)) => {
+                    return Err(PyTypeError::new_err(
+                        "cannot reverse a LoroList that contains containers, use LoroMovableList instead",
+                    )
+                    .into())
+                }
+                None => break,
+            }
+        }
+        self.0.delete(0, values.len())?;
+        for v in values.into_iter().rev() {
+            self.0.push(v)?;
+        }
+        Ok(())
+    }
+
     /// Get the length of the list.
     #[inline]
     pub fn __len__(&self) -> usize {
@@ -343,7 +553,7 @@ impl LoroList {
     pub fn subscribe(&self, callback: Py<PyAny>) -> Option<Subscription> {
         let subscription = self.0.subscribe(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.map(|s| s.into())
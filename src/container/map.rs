@@ -1,22 +1,132 @@
 use std::sync::Arc;
 
 use loro::{ContainerTrait, LoroMap as LoroMapInner, PeerID};
-use pyo3::{exceptions::PyKeyError, prelude::*, PyErr};
+use pyo3::{
+    exceptions::{PyKeyError, PyTypeError},
+    prelude::*,
+    types::{PyDict, PyTuple},
+    IntoPyObjectExt, PyErr,
+};
 
 use crate::{
+    callback,
+    deep::{self, DeepPolicy},
     doc::LoroDoc,
     err::PyLoroResult,
     event::{DiffEvent, Subscription},
     value::{ContainerID, LoroValue, ValueOrContainer},
 };
 
-use super::Container;
+use super::{utils::Needle, Container};
 
 pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<LoroMap>()?;
+    m.add_class::<LoroMapIter>()?;
+    m.add_class::<LoroMapView>()?;
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy)]
+enum MapIterKind {
+    Keys,
+    Values,
+    Items,
+}
+
+/// Iterator over the keys, values or items of a `LoroMap`.
+///
+/// Only the keys are collected when the iterator is created; values are
+/// fetched lazily. Entries deleted during the iteration are skipped.
+#[pyclass]
+pub struct LoroMapIter {
+    map: LoroMapInner,
+    keys: std::vec::IntoIter<String>,
+    kind: MapIterKind,
+}
+
+impl LoroMapIter {
+    fn new(map: &LoroMapInner, kind: MapIterKind) -> Self {
+        let keys: Vec<String> = map.keys().map(|k| k.to_string()).collect();
+        Self {
+            map: map.clone(),
+            keys: keys.into_iter(),
+            kind,
+        }
+    }
+}
+
+#[pymethods]
+impl LoroMapIter {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        for key in self.keys.by_ref() {
+            let Some(value) = self.map.get(&key) else {
+                continue;
+            };
+            let value = ValueOrContainer::from(value);
+            let ans = match self.kind {
+                MapIterKind::Keys => key.into_bound_py_any(py)?,
+                MapIterKind::Values => value.into_bound_py_any(py)?,
+                MapIterKind::Items => (key, value).into_bound_py_any(py)?,
+            };
+            return Ok(Some(ans));
+        }
+        Ok(None)
+    }
+
+    pub fn __length_hint__(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+/// View of the keys, values or items of a `LoroMap`.
+///
+/// The view reflects the current entries of the map: it can be iterated over
+/// several times, and its length and membership follow the edits of the map.
+#[pyclass(frozen)]
+pub struct LoroMapView {
+    map: LoroMapInner,
+    kind: MapIterKind,
+}
+
+#[pymethods]
+impl LoroMapView {
+    pub fn __len__(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn __iter__(&self) -> LoroMapIter {
+        LoroMapIter::new(&self.map, self.kind)
+    }
+
+    /// Keys are looked up, values are compared like the items of a list,
+    /// and items are `(key, value)` pairs.
+    pub fn __contains__(&self, obj: &Bound<'_, PyAny>) -> bool {
+        let contains = |key: &str, value: &Bound<'_, PyAny>| {
+            let (Some(item), Some(needle)) = (self.map.get(key), Needle::extract(value)) else {
+                return false;
+            };
+            needle.matches(&item)
+        };
+        match self.kind {
+            MapIterKind::Keys => obj
+                .extract::<String>()
+                .is_ok_and(|key| self.map.get(&key).is_some()),
+            MapIterKind::Values => Needle::extract(obj).is_some_and(|needle| {
+                self.map
+                    .keys()
+                    .any(|key| self.map.get(&key).is_some_and(|item| needle.matches(&item)))
+            }),
+            MapIterKind::Items => obj
+                .extract::<(String, Bound<'_, PyAny>)>()
+                .is_ok_and(|(key, value)| contains(&key, &value)),
+        }
+    }
+}
+
 #[pyclass(frozen, mapping)]
 #[derive(Debug, Clone, Default)]
 pub struct LoroMap(pub LoroMapInner);
@@ -45,13 +155,16 @@ impl LoroMap {
     }
 
     /// Iterate over the key-value pairs of the map.
+    ///
+    /// If the callback raises, the remaining entries are skipped and the
+    /// exception is re-raised.
     // TODO: why valueOrHandler?
-    pub fn for_each(&self, f: Py<PyAny>) {
-        Python::attach(|py| {
-            self.0.for_each(move |key, value| {
-                f.call1(py, (key, ValueOrContainer::from(value))).unwrap();
-            })
+    pub fn for_each(&self, py: Python<'_>, f: Py<PyAny>) -> PyResult<()> {
+        let mut err = None;
+        self.0.for_each(|key, value| {
+            callback::call_capture(py, &f, (key, ValueOrContainer::from(value)), &mut err);
         });
+        err.map_or(Ok(()), Err)
     }
 
     /// Insert a key-value pair into the map.
@@ -69,8 +182,14 @@ impl LoroMap {
         self.0.get(key).is_some()
     }
 
+    pub fn __iter__(&self) -> LoroMapIter {
+        LoroMapIter::new(&self.0, MapIterKind::Keys)
+    }
+
     pub fn __getitem__(&self, key: &str) -> PyResult<ValueOrContainer> {
-        self.get(key)
+        self.0
+            .get(key)
+            .map(ValueOrContainer::from)
             .ok_or_else(|| PyKeyError::new_err(format!("Key {key} not found")))
     }
 
@@ -86,11 +205,12 @@ impl LoroMap {
         Ok(())
     }
 
-    pub fn items(&self) -> Vec<(String, ValueOrContainer)> {
-        self.0
-            .keys()
-            .filter_map(|k| self.get(&k).map(|v| (k.to_string(), v)))
-            .collect()
+    /// A view of the `(key, value)` pairs of the map.
+    pub fn items(&self) -> LoroMapView {
+        LoroMapView {
+            map: self.0.clone(),
+            kind: MapIterKind::Items,
+        }
     }
 
     /// Get the ID of the map.
@@ -104,9 +224,107 @@ impl LoroMap {
         self.0.is_empty()
     }
 
-    /// Get the value of the map with the given key.
-    pub fn get(&self, key: &str) -> Option<ValueOrContainer> {
-        self.0.get(key).map(|v| v.into())
+    /// Get the value of the map with the given key, or `default` if the key is absent.
+    #[pyo3(signature = (key, default=None))]
+    pub fn get<'py>(
+        &self,
+        py: Python<'py>,
+        key: &str,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        match self.0.get(key) {
+            Some(v) => Ok(Some(ValueOrContainer::from(v).into_bound_py_any(py)?)),
+            None => Ok(default),
+        }
+    }
+
+    /// Remove `key` and return its value.
+    ///
+    /// If the key is absent, `default` is returned if given, otherwise
+    /// `KeyError` is raised.
+    #[pyo3(signature = (key, *default))]
+    pub fn pop<'py>(
+        &self,
+        py: Python<'py>,
+        key: &str,
+        default: &Bound<'py, PyTuple>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        if default.len() > 1 {
+            return Err(PyTypeError::new_err(format!(
+                "pop expected at most 2 arguments, got {}",
+                default.len() + 1
+            )));
+        }
+        match self.0.get(key) {
+            Some(v) => {
+                self.delete(key)?;
+                ValueOrContainer::from(v).into_bound_py_any(py)
+            }
+            None => default
+                .get_item(0)
+                .map_err(|_| PyKeyError::new_err(format!("Key {key} not found"))),
+        }
+    }
+
+    /// Remove and return an arbitrary `(key, value)` pair.
+    ///
+    /// Raises `KeyError` if the map is empty.
+    pub fn popitem(&self) -> PyResult<(String, ValueOrContainer)> {
+        let key = self
+            .0
+            .keys()
+            .next()
+            .map(|k| k.to_string())
+            .ok_or_else(|| PyKeyError::new_err("popitem(): map is empty"))?;
+        let value = self.__getitem__(&key)?;
+        self.delete(&key)?;
+        Ok((key, value))
+    }
+
+    /// Return the value of `key`, inserting `default` first if the key is absent.
+    #[pyo3(signature = (key, default=None))]
+    pub fn setdefault(
+        &self,
+        key: &str,
+        default: Option<LoroValue>,
+    ) -> PyLoroResult<ValueOrContainer> {
+        if let Some(v) = self.0.get(key) {
+            return Ok(v.into());
+        }
+        let value = default.unwrap_or_else(|| LoroValue(loro::LoroValue::Null));
+        self.0.insert(key, value.0.clone())?;
+        Ok(ValueOrContainer::Value { value })
+    }
+
+    /// Update the map from a mapping or an iterable of `(key, value)` pairs,
+    /// then from keyword arguments, like `dict.update`.
+    #[pyo3(signature = (other=None, **kwargs))]
+    pub fn update(
+        &self,
+        other: Option<&Bound<'_, PyAny>>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyLoroResult<()> {
+        if let Some(other) = other {
+            if other.hasattr("keys")? {
+                for key in other.call_method0("keys")?.try_iter()? {
+                    let key = key?;
+                    let value: LoroValue = other.get_item(&key)?.extract()?;
+                    self.0.insert(&key.extract::<String>()?, value.0)?;
+                }
+            } else {
+                for item in other.try_iter()? {
+                    let (key, value): (String, LoroValue) = item?.extract()?;
+                    self.0.insert(&key, value.0)?;
+                }
+            }
+        }
+        if let Some(kwargs) = kwargs {
+            for (key, value) in kwargs {
+                let value: LoroValue = value.extract()?;
+                self.0.insert(&key.extract::<String>()?, value.0)?;
+            }
+        }
+        Ok(())
     }
 
     /// Insert a container with the given type at the given key.
@@ -128,6 +346,46 @@ impl LoroMap {
         Ok(container.into())
     }
 
+    /// Insert a value whose dicts, lists and strings are stored as containers,
+    /// according to `policy`, so that concurrent edits of its parts merge.
+    ///
+    /// An existing value of the key is replaced.
+    #[pyo3(signature = (key, value, policy=None))]
+    pub fn set_deep(
+        &self,
+        key: &str,
+        value: LoroValue,
+        policy: Option<&DeepPolicy>,
+    ) -> PyLoroResult<()> {
+        deep::set_deep(
+            &self.0,
+            key,
+            value.into(),
+            policy.unwrap_or(&DeepPolicy::default()),
+        )
+    }
+
+    /// Update the value of a key like `set_deep`, but only edit the parts of
+    /// the existing value that changed.
+    ///
+    /// Maps are updated entry by entry, lists item by item, and texts with
+    /// `LoroText.update`. A part stored in a container of another type than
+    /// the one `policy` gives is replaced.
+    #[pyo3(signature = (key, value, policy=None))]
+    pub fn update_deep(
+        &self,
+        key: &str,
+        value: LoroValue,
+        policy: Option<&DeepPolicy>,
+    ) -> PyLoroResult<()> {
+        deep::update_deep(
+            &self.0,
+            key,
+            value.into(),
+            policy.unwrap_or(&DeepPolicy::default()),
+        )
+    }
+
     /// Get the shallow value of the map.
     ///
     /// It will not convert the state of sub-containers, but represent them as [LoroValue::Container].
@@ -156,15 +414,20 @@ impl LoroMap {
         Ok(())
     }
 
-    // TODO: iter
-    /// Get the keys of the map.
-    pub fn keys(&self) -> Vec<String> {
-        self.0.keys().map(|k| k.to_string()).collect()
+    /// A view of the keys of the map.
+    pub fn keys(&self) -> LoroMapView {
+        LoroMapView {
+            map: self.0.clone(),
+            kind: MapIterKind::Keys,
+        }
     }
 
-    /// Get the values of the map.
-    pub fn values(&self) -> Vec<ValueOrContainer> {
-        self.0.values().map(ValueOrContainer::from).collect()
+    /// A view of the values of the map.
+    pub fn values(&self) -> LoroMapView {
+        LoroMapView {
+            map: self.0.clone(),
+            kind: MapIterKind::Values,
+        }
     }
 
     /// Get the peer id of the last editor on the given entry
@@ -190,7 +453,7 @@ impl LoroMap {
     pub fn subscribe(&self, callback: Py<PyAny>) -> Option<Subscription> {
         let subscription = self.0.subscribe(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.map(|s| s.into())
@@ -1,7 +1,14 @@
 use std::sync::Arc;
 
 use crate::{
-    container::utils::{py_any_to_loro_values, slice_indices_positions, SliceOrInt},
+    callback,
+    container::{
+        list::{ListHandler, LoroListIter},
+        utils::{
+            clamp_index, find_index, normalize_index, py_any_to_loro_values,
+            slice_indices_positions, Needle, SliceOrInt,
+        },
+    },
     doc::LoroDoc,
     err::{PyLoroError, PyLoroResult},
     event::{DiffEvent, Subscription},
@@ -46,9 +53,12 @@ impl LoroMovableList {
         self.0.is_attached()
     }
 
-    /// Insert a value at the given position.
-    pub fn insert(&self, pos: usize, v: LoroValue) -> PyLoroResult<()> {
-        self.0.insert(pos, &v)?;
+    /// Insert a value before the given position.
+    ///
+    /// Like `list.insert`, a negative position counts from the end, and a
+    /// position out of range inserts at the start or at the end.
+    pub fn insert(&self, pos: isize, v: LoroValue) -> PyLoroResult<()> {
+        self.0.insert(clamp_index(pos, self.0.len()), &v)?;
         Ok(())
     }
 
@@ -63,6 +73,92 @@ impl LoroMovableList {
         self.0.get(index).map(ValueOrContainer::from)
     }
 
+    pub fn __iter__(&self) -> LoroListIter {
+        LoroListIter::new(ListHandler::MovableList(self.0.clone()), false)
+    }
+
+    pub fn __reversed__(&self) -> LoroListIter {
+        LoroListIter::new(ListHandler::MovableList(self.0.clone()), true)
+    }
+
+    /// Whether the list contains the given value or container.
+    ///
+    /// Containers are compared by their id.
+    pub fn __contains__(&self, value: &Bound<'_, PyAny>) -> bool {
+        Needle::extract(value)
+            .is_some_and(|n| find_index(0, self.0.len(), |i| self.0.get(i), &n).is_some())
+    }
+
+    /// Return the index of the first occurrence of `value` in `list[start:stop]`.
+    ///
+    /// Raises `ValueError` if the value is not present.
+    #[pyo3(signature = (value, start=0, stop=None))]
+    pub fn index(
+        &self,
+        value: &Bound<'_, PyAny>,
+        start: isize,
+        stop: Option<isize>,
+    ) -> PyResult<usize> {
+        let len = self.0.len();
+        let start = clamp_index(start, len);
+        let stop = stop.map_or(len, |s| clamp_index(s, len));
+        Needle::extract(value)
+            .and_then(|n| find_index(start, stop, |i| self.0.get(i), &n))
+            .ok_or_else(|| PyValueError::new_err("value is not in list"))
+    }
+
+    /// Return the number of occurrences of `value`.
+    pub fn count(&self, value: &Bound<'_, PyAny>) -> usize {
+        let Some(needle) = Needle::extract(value) else {
+            return 0;
+        };
+        let mut ans = 0;
+        self.0.for_each(|v| {
+            if needle.matches(&v) {
+                ans += 1;
+            }
+        });
+        ans
+    }
+
+    /// Append a value to the end of the list. It is an alias of `push`.
+    pub fn append(&self, v: LoroValue) -> PyLoroResult<()> {
+        self.push(v)
+    }
+
+    /// Append all the values of an iterable to the end of the list.
+    pub fn extend(&self, values: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        for v in values.try_iter()? {
+            let v: LoroValue = v?.extract()?;
+            self.0.push(v.0)?;
+        }
+        Ok(())
+    }
+
+    pub fn __iadd__(&self, values: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        self.extend(values)
+    }
+
+    /// Remove the first occurrence of `value`.
+    ///
+    /// Raises `ValueError` if the value is not present.
+    pub fn remove(&self, value: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        let pos = self.index(value, 0, None)?;
+        self.delete(pos, 1)
+    }
+
+    /// Reverse the list in place by moving its elements.
+    ///
+    /// The elements keep their identity, so concurrent edits on them are
+    /// preserved.
+    pub fn reverse(&self) -> PyLoroResult<()> {
+        let len = self.0.len();
+        for i in 0..len.saturating_sub(1) {
+            self.0.mov(len - 1, i)?;
+        }
+        Ok(())
+    }
+
     /// Get the length of the list.
     pub fn __len__(&self) -> usize {
         self.0.len()
@@ -93,7 +189,7 @@ impl LoroMovableList {
             SliceOrInt::Int(idx) => {
                 let value: ValueOrContainer = self
                     .0
-                    .get(usize::try_from(idx)?)
+                    .get(normalize_index(idx, self.0.len())?)
                     .ok_or(PyIndexError::new_err("index out of range"))?
                     .into();
                 Ok(value.into_pyobject(py)?.into_any().into_bound())
@@ -108,6 +204,7 @@ impl LoroMovableList {
     ) -> PyLoroResult<()> {
         match index {
             SliceOrInt::Int(idx) => {
+                let idx = normalize_index(idx, self.0.len())?;
                 let extracted: LoroValue = value.extract().map_err(PyLoroError::from)?;
                 self.0.set(idx, extracted.0).map_err(PyLoroError::from)?;
                 Ok(())
@@ -123,10 +220,8 @@ impl LoroMovableList {
                         .delete(indices.start as usize, indices.slicelength)
                         .map_err(PyLoroError::from)?;
 
-                    let mut pos = indices.start as usize;
-                    for v in values {
+                    for (pos, v) in (indices.start as usize..).zip(values) {
                         self.0.insert(pos, v).map_err(PyLoroError::from)?;
-                        pos += 1;
                     }
                     Ok(())
                 } else {
@@ -152,7 +247,7 @@ impl LoroMovableList {
 
     pub fn __delitem__<'py>(&self, index: SliceOrInt<'py>) -> PyLoroResult<()> {
         match index {
-            SliceOrInt::Int(idx) => self.delete(idx, 1),
+            SliceOrInt::Int(idx) => self.delete(normalize_index(idx, self.0.len())?, 1),
             SliceOrInt::Slice(slice) => {
                 let len = self.__len__() as isize;
                 let indices = slice.indices(len).map_err(PyLoroError::from)?;
@@ -194,9 +289,26 @@ impl LoroMovableList {
         self.0.get_deep_value().into()
     }
 
-    /// Pop the last element of the list.
-    pub fn pop(&self) -> PyLoroResult<Option<ValueOrContainer>> {
-        let ans = self.0.pop()?.map(ValueOrContainer::from);
+    /// Remove and return the element at `index` (the last one by default).
+    ///
+    /// Raises `IndexError` if the list is empty or the index is out of
+    /// range.
+    #[pyo3(signature = (index=None))]
+    pub fn pop(&self, index: Option<isize>) -> PyLoroResult<ValueOrContainer> {
+        let len = self.0.len();
+        let index = match index {
+            Some(index) => normalize_index(index, len)?,
+            None if len == 0 => {
+                return Err(PyIndexError::new_err("pop from empty list").into());
+            }
+            None => len - 1,
+        };
+        let ans = self
+            .0
+            .get(index)
+            .map(ValueOrContainer::from)
+            .ok_or(PyIndexError::new_err("index out of range"))?;
+        self.0.delete(index, 1)?;
         Ok(ans)
     }
 
@@ -311,12 +423,15 @@ impl LoroMovableList {
     }
 
     /// Iterate over the elements of the list.
-    pub fn for_each(&self, f: Py<PyAny>) {
-        Python::attach(|py| {
-            self.0.for_each(&mut |v| {
-                f.call1(py, (ValueOrContainer::from(v),)).unwrap();
-            });
-        })
+    ///
+    /// If the callback raises, the remaining elements are skipped and the
+    /// exception is re-raised.
+    pub fn for_each(&self, py: Python<'_>, f: Py<PyAny>) -> PyResult<()> {
+        let mut err = None;
+        self.0.for_each(&mut |v| {
+            callback::call_capture(py, &f, (ValueOrContainer::from(v),), &mut err);
+        });
+        err.map_or(Ok(()), Err)
     }
 
     /// Get the creator of the list item at the given position.
@@ -352,7 +467,7 @@ impl LoroMovableList {
     pub fn subscribe(&self, callback: Py<PyAny>) -> Option<Subscription> {
         let subscription = self.0.subscribe(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.map(|s| s.into())
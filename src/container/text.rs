@@ -2,15 +2,17 @@ use loro::{ContainerTrait, LoroText as LoroTextInner, PeerID};
 use pyo3::{
     exceptions::{PyIndexError, PyTypeError, PyValueError},
     prelude::*,
-    types::{PyBytes, PySlice, PyString},
+    types::{PyBytes, PySlice, PyString, PyType},
     Bound, PyErr, PyRef,
 };
-use std::{fmt::Display, sync::Arc};
+use std::{borrow::Cow, collections::HashMap, fmt::Display, sync::Arc};
 
 use crate::{
+    callback,
     doc::LoroDoc,
-    err::{PyLoroError, PyLoroResult},
+    err::PyLoroResult,
     event::{DiffEvent, Subscription, TextDelta},
+    richtext::{self, Format, MarkStyle, Parsed},
     value::{ContainerID, LoroValue, ID},
 };
 
@@ -348,6 +350,94 @@ impl LoroText {
         self.0.to_delta().iter().map(|x| x.into()).collect()
     }
 
+    /// Render the text as Markdown.
+    ///
+    /// Marks are rendered with `MarkStyle.markdown_defaults()`, updated with
+    /// `marks`. Mapping a key to `None` ignores that mark, as are the marks
+    /// without a style. Overlapping marks are closed and reopened so that the
+    /// output is properly nested, and the text is escaped.
+    ///
+    /// Marks are closed at line breaks, and whitespace at the edges of a mark
+    /// is moved outside of it. Emphasis delimiters that Markdown would not read
+    /// as such, e.g. `**` between a letter and punctuation, are replaced by the
+    /// HTML tags `<strong>`, `<em>` and `<s>`, which `import_markdown()` reads.
+    #[pyo3(signature = (marks=None))]
+    pub fn to_markdown(&self, marks: Option<HashMap<String, Option<MarkStyle>>>) -> String {
+        let styles = Format::Markdown.styles(marks);
+        richtext::render(&self.0.to_delta(), Format::Markdown, &styles)
+    }
+
+    /// Render the text as HTML.
+    ///
+    /// Marks are rendered with `MarkStyle.html_defaults()`, updated with
+    /// `marks`. Mapping a key to `None` ignores that mark, as are the marks
+    /// without a style. Overlapping marks are closed and reopened so that the
+    /// output is properly nested, the text is escaped and line breaks are
+    /// rendered as `<br>`.
+    #[pyo3(signature = (marks=None))]
+    pub fn to_html(&self, marks: Option<HashMap<String, Option<MarkStyle>>>) -> String {
+        let styles = Format::Html.styles(marks);
+        richtext::render(&self.0.to_delta(), Format::Html, &styles)
+    }
+
+    /// Replace the content of the text with the given Markdown.
+    ///
+    /// Emphasis, `**bold**`, `~~strikethrough~~`, code spans, links and
+    /// `<u>underline</u>` become the marks `italic`, `bold`, `strikethrough`,
+    /// `code`, `link` and `underline`, as do the HTML tags `<em>`,
+    /// `<strong>` and `<s>`. `keys` maps these names to other mark
+    /// keys, or to `None` to drop the mark. Block syntax such as headings and
+    /// lists is kept as text.
+    ///
+    /// The marks are applied with the expand behavior configured with
+    /// `LoroDoc.config_text_style`, and each of their keys must be configured:
+    /// otherwise `InvalidArgumentError` is raised before the text is changed.
+    /// `StyleConfigMap.default_rich_text_config()` does not configure
+    /// `strikethrough`, which can be configured, or mapped with `keys`.
+    ///
+    /// If `diff` is true, the text is changed with `update()` and only the marks
+    /// that changed are applied, so that importing a document close to the
+    /// current content generates few ops. Marks with other keys, such as
+    /// comments, are kept.
+    #[pyo3(signature = (markdown, *, keys=None, diff=false))]
+    pub fn import_markdown(
+        &self,
+        markdown: &str,
+        keys: Option<HashMap<String, Option<String>>>,
+        diff: bool,
+    ) -> PyLoroResult<()> {
+        Parsed::markdown(markdown).apply(&self.0, keys, diff)
+    }
+
+    /// Replace the content of the text with the given HTML.
+    ///
+    /// `<b>`/`<strong>`, `<i>`/`<em>`, `<u>`, `<s>`/`<del>`, `<code>`,
+    /// `<a href>` and `<mark>` become the marks `bold`, `italic`, `underline`,
+    /// `strikethrough`, `code`, `link` and `highlight`. `keys` maps these names
+    /// to other mark keys, or to `None` to drop the mark. `<br>` and block
+    /// elements become line breaks, other tags are ignored and whitespace is
+    /// collapsed outside of `<pre>`.
+    ///
+    /// The marks are applied with the expand behavior configured with
+    /// `LoroDoc.config_text_style`, and each of their keys must be configured:
+    /// otherwise `InvalidArgumentError` is raised before the text is changed.
+    /// `StyleConfigMap.default_rich_text_config()` does not configure
+    /// `strikethrough`, which can be configured, or mapped with `keys`.
+    ///
+    /// If `diff` is true, the text is changed with `update()` and only the marks
+    /// that changed are applied, so that importing a document close to the
+    /// current content generates few ops. Marks with other keys, such as
+    /// comments, are kept.
+    #[pyo3(signature = (html, *, keys=None, diff=false))]
+    pub fn import_html(
+        &self,
+        html: &str,
+        keys: Option<HashMap<String, Option<String>>>,
+        diff: bool,
+    ) -> PyLoroResult<()> {
+        Parsed::html(html).apply(&self.0, keys, diff)
+    }
+
     /// Get the text content of the text container.
     #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
@@ -422,7 +512,7 @@ impl LoroText {
     pub fn subscribe(&self, callback: Py<PyAny>) -> Option<Subscription> {
         let subscription = self.0.subscribe(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.map(|s| s.into())
@@ -437,7 +527,7 @@ pub enum Side {
     Right = 1,
 }
 
-#[pyclass(str, frozen)]
+#[pyclass(str, eq, hash, frozen, module = "loro")]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Cursor(pub loro::cursor::Cursor);
 
@@ -471,14 +561,22 @@ impl Cursor {
         self.0.container.clone().into()
     }
 
-    pub fn encode(&self) -> Vec<u8> {
-        self.0.encode()
+    pub fn encode(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(self.0.encode())
     }
 
-    #[staticmethod]
-    pub fn decode(bytes: Bound<'_, PyBytes>) -> PyLoroResult<Self> {
+    #[classmethod]
+    pub fn decode(_cls: &Bound<'_, PyType>, bytes: Bound<'_, PyBytes>) -> PyLoroResult<Self> {
         let cursor = loro::cursor::Cursor::decode(bytes.as_bytes())
-            .map_err(|e| PyLoroError::Error(e.to_string()))?;
+            .map_err(|e| loro::LoroError::DecodeError(e.to_string().into()))?;
         Ok(Self(cursor))
     }
+
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let decode = py.get_type::<Self>().getattr("decode")?;
+        Ok((decode, (PyBytes::new(py, &self.0.encode()),)))
+    }
 }
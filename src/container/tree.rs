@@ -4,6 +4,7 @@ use loro::{ContainerTrait, LoroTree as LoroTreeInner};
 use pyo3::prelude::*;
 
 use crate::{
+    callback,
     convert::tree_parent_id_to_option_tree_id,
     doc::LoroDoc,
     err::PyLoroResult,
@@ -16,9 +17,36 @@ use super::LoroMap;
 pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<LoroTree>()?;
     m.add_class::<TreeNode>()?;
+    m.add_class::<LoroTreeWalk>()?;
     Ok(())
 }
 
+/// Depth-first, pre-order iterator over the nodes of a `LoroTree`.
+///
+/// The children of a node are only looked up once the node is reached, so
+/// the tree is not copied up front. Siblings are visited in their order in
+/// the tree.
+#[pyclass]
+pub struct LoroTreeWalk {
+    tree: LoroTreeInner,
+    stack: Vec<loro::TreeID>,
+}
+
+#[pymethods]
+impl LoroTreeWalk {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__(&mut self) -> Option<TreeID> {
+        let node = self.stack.pop()?;
+        if let Some(children) = self.tree.children(node) {
+            self.stack.extend(children.into_iter().rev());
+        }
+        Some(node.into())
+    }
+}
+
 #[pyclass(frozen)]
 #[derive(Debug, Clone, Default)]
 pub struct LoroTree(pub LoroTreeInner);
@@ -47,6 +75,27 @@ impl LoroTree {
         self.contains(target)
     }
 
+    /// Iterate over all the nodes of the forest in depth-first pre-order.
+    pub fn __iter__(&self) -> LoroTreeWalk {
+        self.walk(None)
+    }
+
+    /// Walk the nodes in depth-first pre-order.
+    ///
+    /// If `parent` is `None`, the whole forest is walked. Otherwise only the
+    /// subtree rooted at `parent` is walked, starting with `parent` itself.
+    #[pyo3(signature = (parent=None))]
+    pub fn walk(&self, parent: Option<TreeID>) -> LoroTreeWalk {
+        let stack = match parent {
+            Some(parent) => vec![parent.into()],
+            None => self.0.roots().into_iter().rev().collect(),
+        };
+        LoroTreeWalk {
+            tree: self.0.clone(),
+            stack,
+        }
+    }
+
     /// Create a new tree node and return the [`TreeID`].
     ///
     /// If the `parent` is `None`, the created node is the root of a tree.
@@ -365,7 +414,7 @@ impl LoroTree {
     pub fn subscribe(&self, callback: Py<PyAny>) -> Option<Subscription> {
         let subscription = self.0.subscribe(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.map(|s| s.into())
@@ -1,7 +1,11 @@
-use crate::value::LoroValue as PyLoroValue;
-use loro::LoroValue as CoreLoroValue;
+use crate::{
+    container::Container,
+    convert::pyobject_to_loro_value,
+    value::{LoroValue as PyLoroValue, ValueOrContainer},
+};
+use loro::{ContainerTrait, LoroValue as CoreLoroValue};
 use pyo3::{
-    exceptions::PyTypeError,
+    exceptions::{PyIndexError, PyTypeError},
     types::{PyAnyMethods, PySequence, PySequenceMethods, PySlice, PySliceIndices},
     Bound, FromPyObject, PyAny, PyResult,
 };
@@ -9,7 +13,55 @@ use pyo3::{
 #[derive(FromPyObject)]
 pub enum SliceOrInt<'py> {
     Slice(Bound<'py, PySlice>),
-    Int(usize),
+    Int(isize),
+}
+
+/// Resolve a possibly negative Python index against a sequence of length `len`.
+pub fn normalize_index(index: isize, len: usize) -> PyResult<usize> {
+    let resolved = if index < 0 {
+        index + len as isize
+    } else {
+        index
+    };
+    if resolved < 0 || resolved as usize >= len {
+        return Err(PyIndexError::new_err("index out of range"));
+    }
+    Ok(resolved as usize)
+}
+
+/// An element to look for in a list, as accepted by `__contains__`, `index`,
+/// `count` and `remove`.
+///
+/// Containers are compared by id, plain values by equality.
+pub enum Needle {
+    Value(CoreLoroValue),
+    Container(loro::ContainerID),
+}
+
+impl Needle {
+    /// Returns `None` if `obj` can never be an element of a Loro list.
+    pub fn extract(obj: &Bound<'_, PyAny>) -> Option<Self> {
+        if let Ok(v) = obj.extract::<ValueOrContainer>() {
+            return Some(match v {
+                ValueOrContainer::Value { value } => Self::Value(value.0),
+                ValueOrContainer::Container { container } => {
+                    Self::Container(loro::Container::from(container).id())
+                }
+            });
+        }
+        if let Ok(container) = obj.extract::<Container>() {
+            return Some(Self::Container(loro::Container::from(container).id()));
+        }
+        pyobject_to_loro_value(obj).ok().map(Self::Value)
+    }
+
+    pub fn matches(&self, item: &loro::ValueOrContainer) -> bool {
+        match (self, item) {
+            (Self::Value(a), loro::ValueOrContainer::Value(b)) => a == b,
+            (Self::Container(a), loro::ValueOrContainer::Container(b)) => *a == b.id(),
+            _ => false,
+        }
+    }
 }
 
 pub fn py_any_to_loro_values(obj: &Bound<'_, PyAny>) -> PyResult<Vec<CoreLoroValue>> {
@@ -36,3 +88,22 @@ pub fn slice_indices_positions(indices: &PySliceIndices) -> Vec<usize> {
     }
     positions
 }
+
+/// Clamp a possibly negative Python slice bound into `0..=len`.
+pub fn clamp_index(index: isize, len: usize) -> usize {
+    if index < 0 {
+        (index + len as isize).max(0) as usize
+    } else {
+        (index as usize).min(len)
+    }
+}
+
+/// Find the first position in `start..stop` whose element matches `needle`.
+pub fn find_index(
+    start: usize,
+    stop: usize,
+    get: impl Fn(usize) -> Option<loro::ValueOrContainer>,
+    needle: &Needle,
+) -> Option<usize> {
+    (start..stop).find(|&i| get(i).is_some_and(|item| needle.matches(&item)))
+}
@@ -2,9 +2,12 @@ use std::{borrow::Cow, collections::HashMap, sync::Mutex};
 
 use loro::FractionalIndex;
 use pyo3::{
-    exceptions::PyTypeError,
+    exceptions::{PyOverflowError, PyTypeError},
     prelude::*,
-    types::{PyBool, PyBytes, PyDict, PyList, PyMapping, PyString},
+    types::{
+        PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyList, PyMapping, PyMemoryView,
+        PySequence, PyString, PyTuple,
+    },
     BoundObject, PyResult,
 };
 use rustc_hash::FxHashMap;
@@ -23,8 +26,10 @@ use crate::{
         ContainerDiff, Diff, DiffEvent, EventTriggerKind, Index, ListDiffItem, MapDelta, PathItem,
         Subscription, TextDelta, TreeDiff, TreeDiffItem, TreeExternalDiff,
     },
+    hooks,
+    lazy::{is_lazy, LoroValueList, LoroValueMap},
     undo::{CursorWithPos, UndoItemMeta, UndoOrRedo},
-    value::{ContainerID, ContainerType, LoroValue, TreeID, ValueOrContainer, ID},
+    value::{ContainerID, ContainerType, IdLp, LoroValue, TreeID, ValueOrContainer, ID},
 };
 
 impl From<ID> for loro::ID {
@@ -45,6 +50,15 @@ impl From<loro::ID> for ID {
     }
 }
 
+impl From<loro::IdLp> for IdLp {
+    fn from(value: loro::IdLp) -> Self {
+        Self {
+            peer: value.peer,
+            lamport: value.lamport as i32,
+        }
+    }
+}
+
 pub fn pyobject_to_container_id(
     obj: &Bound<'_, PyAny>,
     ty: ContainerType,
@@ -59,10 +73,27 @@ pub fn pyobject_to_container_id(
     Err(PyTypeError::new_err("Invalid ContainerID"))
 }
 
+/// Whether `obj` is of an exact built-in type. The hooks are only looked up
+/// for the other objects, so that the common values are converted directly.
+pub fn is_builtin(obj: &Bound<'_, PyAny>) -> bool {
+    obj.is_exact_instance_of::<PyBool>()
+        || obj.is_exact_instance_of::<PyInt>()
+        || obj.is_exact_instance_of::<PyFloat>()
+        || obj.is_exact_instance_of::<PyString>()
+        || obj.is_exact_instance_of::<PyBytes>()
+        || obj.is_exact_instance_of::<PyList>()
+        || obj.is_exact_instance_of::<PyDict>()
+}
+
 pub fn pyobject_to_loro_value(obj: &Bound<'_, PyAny>) -> PyResult<loro::LoroValue> {
     if obj.is_none() {
         return Ok(loro::LoroValue::Null);
     }
+    if !is_builtin(obj) {
+        if let Some(converted) = hooks::convert(obj)? {
+            return pyobject_to_loro_value(&converted);
+        }
+    }
 
     if let Ok(value) = obj.extract::<bool>() {
         return Ok(loro::LoroValue::Bool(value));
@@ -70,6 +101,12 @@ pub fn pyobject_to_loro_value(obj: &Bound<'_, PyAny>) -> PyResult<loro::LoroValu
     if let Ok(value) = obj.extract::<i64>() {
         return Ok(loro::LoroValue::I64(value));
     }
+    if obj.downcast::<PyInt>().is_ok() {
+        return Err(PyOverflowError::new_err(
+            "int is out of the range of a 64-bit signed integer \
+             (-2**63 to 2**63 - 1); store it as a string or a float instead",
+        ));
+    }
     if let Ok(value) = obj.extract::<f64>() {
         return Ok(loro::LoroValue::Double(value));
     }
@@ -78,6 +115,15 @@ pub fn pyobject_to_loro_value(obj: &Bound<'_, PyAny>) -> PyResult<loro::LoroValu
             value.as_bytes().to_vec(),
         )));
     }
+    if let Ok(value) = obj.downcast::<PyByteArray>() {
+        return Ok(loro::LoroValue::Binary(loro::LoroBinaryValue::from(
+            value.to_vec(),
+        )));
+    }
+    if obj.downcast::<PyMemoryView>().is_ok() {
+        let bytes = obj.call_method0("tobytes")?;
+        return pyobject_to_loro_value(&bytes);
+    }
     if let Ok(value) = obj.downcast::<PyString>() {
         return Ok(loro::LoroValue::String(loro::LoroStringValue::from(
             value.to_string(),
@@ -90,6 +136,19 @@ pub fn pyobject_to_loro_value(obj: &Bound<'_, PyAny>) -> PyResult<loro::LoroValu
         }
         return Ok(loro::LoroValue::List(loro::LoroListValue::from(list)));
     }
+    if let Ok(value) = obj.downcast::<PyTuple>() {
+        let mut list = Vec::with_capacity(value.len());
+        for item in value.iter() {
+            list.push(pyobject_to_loro_value(&item)?);
+        }
+        return Ok(loro::LoroValue::List(loro::LoroListValue::from(list)));
+    }
+    if let Ok(value) = obj.downcast::<LoroValueList>() {
+        return Ok(loro::LoroValue::List(value.get().0.clone()));
+    }
+    if let Ok(value) = obj.downcast::<LoroValueMap>() {
+        return Ok(loro::LoroValue::Map(value.get().0.clone()));
+    }
     if let Ok(value) = obj.downcast::<PyDict>() {
         let mut map = FxHashMap::default();
         for (key, value) in value.iter() {
@@ -122,11 +181,37 @@ pub fn pyobject_to_loro_value(obj: &Bound<'_, PyAny>) -> PyResult<loro::LoroValu
     if let Ok(value) = obj.downcast::<ContainerID>() {
         return Ok(loro::LoroValue::Container(value.get().clone().into()));
     }
+    // Any other sequence, such as a `collections.deque`, but not a set, whose
+    // order is arbitrary, nor a list container.
+    if let (Ok(value), Err(_)) = (obj.downcast::<PySequence>(), obj.extract::<Container>()) {
+        let mut list = Vec::with_capacity(value.len()?);
+        for item in value.try_iter()? {
+            list.push(pyobject_to_loro_value(&item?)?);
+        }
+        return Ok(loro::LoroValue::List(loro::LoroListValue::from(list)));
+    }
     Err(PyTypeError::new_err("Invalid LoroValue"))
 }
 
 pub fn loro_value_to_pyobject(py: Python<'_>, value: LoroValue) -> PyResult<Bound<'_, PyAny>> {
-    match value.0 {
+    value_to_pyobject(py, value.0, true)
+}
+
+/// Convert `value` to lists and dicts whatever their size, without proxies.
+pub fn loro_value_to_pyobject_eager(
+    py: Python<'_>,
+    value: loro::LoroValue,
+) -> PyResult<Bound<'_, PyAny>> {
+    value_to_pyobject(py, value, false)
+}
+
+/// Convert `value`, returning the large lists and maps as proxies if `lazy`.
+fn value_to_pyobject(
+    py: Python<'_>,
+    value: loro::LoroValue,
+    lazy: bool,
+) -> PyResult<Bound<'_, PyAny>> {
+    match value {
         loro::LoroValue::Null => Ok(py.None().into_pyobject(py)?.into_any().into_bound()),
         loro::LoroValue::Bool(b) => Ok(PyBool::new(py, b)
             .into_pyobject(py)?
@@ -136,17 +221,33 @@ pub fn loro_value_to_pyobject(py: Python<'_>, value: LoroValue) -> PyResult<Boun
         loro::LoroValue::I64(i) => Ok(i.into_pyobject(py)?.into_any().into_bound()),
         loro::LoroValue::String(s) => Ok(s.to_string().into_pyobject(py)?.into_any().into_bound()),
         loro::LoroValue::Binary(b) => Ok(b.as_slice().into_pyobject(py)?.into_any().into_bound()),
+        loro::LoroValue::List(l) if lazy && is_lazy(l.len()) => {
+            Ok(Bound::new(py, LoroValueList(l))?.into_any())
+        }
         loro::LoroValue::List(l) => {
             let list = l
                 .iter()
-                .map(|v| LoroValue(v.clone()).into_pyobject(py))
+                .map(|v| value_to_pyobject(py, v.clone(), lazy))
                 .collect::<Result<Vec<_>, _>>()?;
             Ok(list.into_pyobject(py)?.into_any().into_bound())
         }
         loro::LoroValue::Map(m) => {
+            if let Some((decoder, value)) = hooks::decoder(py, &m) {
+                let value = value_to_pyobject(py, value, lazy)?;
+                // A failing decoder is reported, and the tagged map returned
+                // as if it had none, so that one value cannot make the whole
+                // document unreadable.
+                match decoder.bind(py).call1((value,)) {
+                    Ok(decoded) => return Ok(decoded),
+                    Err(e) => e.write_unraisable(py, Some(decoder.bind(py))),
+                }
+            }
+            if lazy && is_lazy(m.len()) {
+                return Ok(Bound::new(py, LoroValueMap(m))?.into_any());
+            }
             let dict = m
                 .iter()
-                .map(|(k, v)| Ok((k, LoroValue(v.clone()).into_pyobject(py)?)))
+                .map(|(k, v)| Ok((k, value_to_pyobject(py, v.clone(), lazy)?)))
                 .collect::<Result<FxHashMap<_, _>, PyErr>>()?;
             Ok(dict.into_pyobject(py)?.into_any().into_bound())
         }
@@ -159,7 +260,10 @@ pub fn loro_value_to_pyobject(py: Python<'_>, value: LoroValue) -> PyResult<Boun
 
 impl From<loro::LoroDoc> for LoroDoc {
     fn from(value: loro::LoroDoc) -> Self {
-        Self { doc: value }
+        Self {
+            doc: value,
+            staging: Default::default(),
+        }
     }
 }
 
@@ -796,6 +900,7 @@ impl From<loro::ChangeMeta> for ChangeMeta {
             message: value.message.map(|m| m.to_string()),
             deps: value.deps.into(),
             len: value.len,
+            ops: None,
         }
     }
 }
@@ -0,0 +1,352 @@
+//! Assignment of nested values that creates containers for their parts.
+//!
+//! `LoroMap.insert` stores a dict as a single value, which peers can only
+//! overwrite as a whole. The functions here store dicts and lists as maps and
+//! lists, and strings as texts if the [DeepPolicy] says so, so that
+//! concurrent edits of their parts merge. Updates compare the new value with
+//! the current one and only edit the parts that changed: lists are diffed
+//! item by item and texts with `LoroText.update`.
+use std::collections::HashSet;
+
+use loro::{
+    Container, ContainerTrait, ContainerType, LoroList, LoroMap, LoroMapValue, LoroMovableList,
+    LoroText, LoroValue, ToJson, ValueOrContainer,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::err::{InvalidArgumentError, PyLoroResult};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<DeepPolicy>()?;
+    Ok(())
+}
+
+/// Which containers `LoroMap.set_deep`, `LoroMap.update_deep` and
+/// `LoroDoc.from_json` create for the parts of a value.
+///
+/// Dicts become `LoroMap`s, and lists become `LoroList`s, or
+/// `LoroMovableList`s if `movable_lists` is true. Strings become `LoroText`s
+/// if `texts` is true or if they are the value of a key in `text_keys`, and
+/// are stored as plain values otherwise. The values of the keys in
+/// `value_keys` are always stored as plain values.
+#[pyclass(frozen)]
+#[derive(Debug, Clone, Default)]
+pub struct DeepPolicy {
+    texts: bool,
+    movable_lists: bool,
+    text_keys: HashSet<String>,
+    value_keys: HashSet<String>,
+}
+
+#[pymethods]
+impl DeepPolicy {
+    #[new]
+    #[pyo3(signature = (*, texts=false, movable_lists=false, text_keys=None, value_keys=None))]
+    pub fn new(
+        texts: bool,
+        movable_lists: bool,
+        text_keys: Option<Vec<String>>,
+        value_keys: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            texts,
+            movable_lists,
+            text_keys: text_keys.into_iter().flatten().collect(),
+            value_keys: value_keys.into_iter().flatten().collect(),
+        }
+    }
+}
+
+impl DeepPolicy {
+    /// The type of the container storing `value`, the value of `key` if it is
+    /// in a map, or `None` if it is stored as a plain value.
+    fn container_type(&self, key: Option<&str>, value: &LoroValue) -> Option<ContainerType> {
+        if key.is_some_and(|key| self.value_keys.contains(key)) {
+            return None;
+        }
+        match value {
+            LoroValue::Map(_) => Some(ContainerType::Map),
+            LoroValue::List(_) if self.movable_lists => Some(ContainerType::MovableList),
+            LoroValue::List(_) => Some(ContainerType::List),
+            LoroValue::String(_)
+                if self.texts || key.is_some_and(|key| self.text_keys.contains(key)) =>
+            {
+                Some(ContainerType::Text)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Where a value is stored: an entry of a map, or a position in a list.
+#[derive(Clone, Copy)]
+enum Slot<'a> {
+    Map(&'a LoroMap, &'a str),
+    List(&'a LoroList, usize),
+    MovableList(&'a LoroMovableList, usize),
+}
+
+impl Slot<'_> {
+    fn key(&self) -> Option<&str> {
+        match self {
+            Slot::Map(_, key) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Store `value`, replacing the value of a map entry, or inserting it
+    /// into a list.
+    fn insert(&self, value: LoroValue) -> PyLoroResult<()> {
+        match *self {
+            Slot::Map(map, key) => map.insert(key, value)?,
+            Slot::List(list, pos) => list.insert(pos, value)?,
+            Slot::MovableList(list, pos) => list.insert(pos, value)?,
+        }
+        Ok(())
+    }
+
+    fn insert_container<C: ContainerTrait>(&self, child: C) -> PyLoroResult<C> {
+        Ok(match *self {
+            Slot::Map(map, key) => map.insert_container(key, child)?,
+            Slot::List(list, pos) => list.insert_container(pos, child)?,
+            Slot::MovableList(list, pos) => list.insert_container(pos, child)?,
+        })
+    }
+
+    /// Delete the item of a list, before storing another one at its position.
+    /// A map entry is replaced by `insert`.
+    fn vacate(&self) -> PyLoroResult<()> {
+        match *self {
+            Slot::Map(..) => {}
+            Slot::List(list, pos) => list.delete(pos, 1)?,
+            Slot::MovableList(list, pos) => list.delete(pos, 1)?,
+        }
+        Ok(())
+    }
+}
+
+/// A list or a movable list.
+#[derive(Clone, Copy)]
+enum Seq<'a> {
+    List(&'a LoroList),
+    MovableList(&'a LoroMovableList),
+}
+
+impl<'a> Seq<'a> {
+    fn items(&self) -> Vec<ValueOrContainer> {
+        match self {
+            Seq::List(list) => (0..list.len()).filter_map(|i| list.get(i)).collect(),
+            Seq::MovableList(list) => (0..list.len()).filter_map(|i| list.get(i)).collect(),
+        }
+    }
+
+    fn slot(&self, pos: usize) -> Slot<'a> {
+        match *self {
+            Seq::List(list) => Slot::List(list, pos),
+            Seq::MovableList(list) => Slot::MovableList(list, pos),
+        }
+    }
+
+    fn delete(&self, pos: usize, len: usize) -> PyLoroResult<()> {
+        match self {
+            Seq::List(list) => list.delete(pos, len)?,
+            Seq::MovableList(list) => list.delete(pos, len)?,
+        }
+        Ok(())
+    }
+}
+
+/// Store `value` at `slot`, creating containers for its parts.
+fn set_value(slot: Slot<'_>, value: LoroValue, policy: &DeepPolicy) -> PyLoroResult<()> {
+    match (policy.container_type(slot.key(), &value), value) {
+        (Some(ContainerType::Map), LoroValue::Map(entries)) => {
+            update_map(&slot.insert_container(LoroMap::new())?, entries, policy)
+        }
+        (Some(ContainerType::List), LoroValue::List(items)) => {
+            let list = slot.insert_container(LoroList::new())?;
+            update_seq(Seq::List(&list), &items, policy)
+        }
+        (Some(ContainerType::MovableList), LoroValue::List(items)) => {
+            let list = slot.insert_container(LoroMovableList::new())?;
+            update_seq(Seq::MovableList(&list), &items, policy)
+        }
+        (Some(ContainerType::Text), LoroValue::String(s)) => {
+            slot.insert_container(LoroText::new())?.insert(0, &s)?;
+            Ok(())
+        }
+        (_, value) => slot.insert(value),
+    }
+}
+
+/// Update `existing`, the value at `slot`, to `value`. A part is edited in
+/// place if its container has the type the policy gives to its new value,
+/// and replaced otherwise.
+fn update_value(
+    slot: Slot<'_>,
+    existing: Option<ValueOrContainer>,
+    value: LoroValue,
+    policy: &DeepPolicy,
+) -> PyLoroResult<()> {
+    let Some(existing) = existing else {
+        return set_value(slot, value, policy);
+    };
+    if existing.get_deep_value() == value {
+        return Ok(());
+    }
+    match (existing, policy.container_type(slot.key(), &value), value) {
+        (
+            ValueOrContainer::Container(Container::Map(map)),
+            Some(ContainerType::Map),
+            LoroValue::Map(entries),
+        ) => update_map(&map, entries, policy),
+        (
+            ValueOrContainer::Container(Container::List(list)),
+            Some(ContainerType::List),
+            LoroValue::List(items),
+        ) => update_seq(Seq::List(&list), &items, policy),
+        (
+            ValueOrContainer::Container(Container::MovableList(list)),
+            Some(ContainerType::MovableList),
+            LoroValue::List(items),
+        ) => update_seq(Seq::MovableList(&list), &items, policy),
+        (
+            ValueOrContainer::Container(Container::Text(text)),
+            Some(ContainerType::Text),
+            LoroValue::String(s),
+        ) => text
+            .update(&s, Default::default())
+            .map_err(|e| PyValueError::new_err(e.to_string()).into()),
+        (_, _, value) => {
+            slot.vacate()?;
+            set_value(slot, value, policy)
+        }
+    }
+}
+
+/// Update `map` so that its entries are `entries`.
+pub fn update_map(map: &LoroMap, entries: LoroMapValue, policy: &DeepPolicy) -> PyLoroResult<()> {
+    let removed: Vec<_> = map
+        .keys()
+        .filter(|key| !entries.contains_key(key.as_str()))
+        .collect();
+    for key in removed {
+        map.delete(&key)?;
+    }
+    for (key, value) in entries.iter() {
+        update_value(Slot::Map(map, key), map.get(key), value.clone(), policy)?;
+    }
+    Ok(())
+}
+
+/// Update `seq` so that its items are `items`. The unchanged items are found
+/// by diffing the JSON of the items, and each changed item is updated from
+/// the item at its position, if the numbers of deleted and inserted items
+/// allow it.
+fn update_seq(seq: Seq<'_>, items: &[LoroValue], policy: &DeepPolicy) -> PyLoroResult<()> {
+    let existing = seq.items();
+    let old: Vec<_> = existing
+        .iter()
+        .map(|item| item.get_deep_value().to_json())
+        .collect();
+    let new: Vec<_> = items.iter().map(|item| item.to_json()).collect();
+    let ops = similar::capture_diff_slices(similar::Algorithm::Myers, &old, &new);
+    // The operations are applied from the end, so that the positions of the
+    // items before them are still the ones in `existing`.
+    for op in ops.iter().rev() {
+        let (old_index, old_len, new_index, new_len) = match *op {
+            similar::DiffOp::Equal { .. } => continue,
+            similar::DiffOp::Delete {
+                old_index, old_len, ..
+            } => (old_index, old_len, 0, 0),
+            similar::DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => (old_index, 0, new_index, new_len),
+            similar::DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => (old_index, old_len, new_index, new_len),
+        };
+        let paired = old_len.min(new_len);
+        if old_len > paired {
+            seq.delete(old_index + paired, old_len - paired)?;
+        }
+        for i in 0..paired {
+            let item = Some(existing[old_index + i].clone());
+            update_value(
+                seq.slot(old_index + i),
+                item,
+                items[new_index + i].clone(),
+                policy,
+            )?;
+        }
+        for i in paired..new_len {
+            set_value(
+                seq.slot(old_index + i),
+                items[new_index + i].clone(),
+                policy,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Store `value` as the entry `key` of `map`, creating containers for its parts.
+pub fn set_deep(
+    map: &LoroMap,
+    key: &str,
+    value: LoroValue,
+    policy: &DeepPolicy,
+) -> PyLoroResult<()> {
+    set_value(Slot::Map(map, key), value, policy)
+}
+
+/// Update the entry `key` of `map` to `value`, only editing the parts that changed.
+pub fn update_deep(
+    map: &LoroMap,
+    key: &str,
+    value: LoroValue,
+    policy: &DeepPolicy,
+) -> PyLoroResult<()> {
+    update_value(Slot::Map(map, key), map.get(key), value, policy)
+}
+
+/// Create the root containers of `doc` from the entries of `value`.
+pub fn from_json(doc: &loro::LoroDoc, value: LoroValue, policy: &DeepPolicy) -> PyLoroResult<()> {
+    let LoroValue::Map(roots) = value else {
+        return Err(InvalidArgumentError::new_err(format!(
+            "Expected a dict of root containers, got {}",
+            value.to_json()
+        ))
+        .into());
+    };
+    for (name, value) in roots.iter() {
+        match (policy.container_type(Some(name), value), value) {
+            (Some(ContainerType::Map), LoroValue::Map(entries)) => {
+                update_map(&doc.get_map(name.as_str()), entries.clone(), policy)?
+            }
+            (Some(ContainerType::List), LoroValue::List(items)) => {
+                update_seq(Seq::List(&doc.get_list(name.as_str())), items, policy)?
+            }
+            (Some(ContainerType::MovableList), LoroValue::List(items)) => {
+                let list = doc.get_movable_list(name.as_str());
+                update_seq(Seq::MovableList(&list), items, policy)?
+            }
+            (Some(ContainerType::Text), LoroValue::String(s)) => {
+                doc.get_text(name.as_str()).insert(0, s)?
+            }
+            (_, value) => {
+                return Err(InvalidArgumentError::new_err(format!(
+                    "The root container {name:?} cannot be created from {}",
+                    value.to_json()
+                ))
+                .into())
+            }
+        }
+    }
+    doc.commit();
+    Ok(())
+}
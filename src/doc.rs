@@ -7,14 +7,22 @@ use pyo3::{
 use std::{borrow::Cow, collections::HashSet, fmt::Display, ops::ControlFlow, sync::Arc};
 
 use crate::{
+    callback,
     container::{
         Cursor, LoroCounter, LoroList, LoroMap, LoroMovableList, LoroText, LoroTree, Side,
     },
     convert::pyobject_to_container_id,
-    err::{PyLoroError, PyLoroResult},
+    deep::{self, DeepPolicy},
+    err::{InvalidArgumentError, PyLoroError, PyLoroResult},
     event::{DiffBatch, DiffEvent, Index, Subscription},
+    history::{ChangeFilter, ChangeIter, Op, Version},
+    import_hook, json_patch, partial,
+    redact::{self, RedactPolicy},
+    schema::{DocSchema, SchemaViolation},
+    stream::{EventStream, OverflowPolicy, DEFAULT_MAXSIZE},
     value::{ContainerID, ContainerType, LoroValue, Ordering, ValueOrContainer, ID},
     version::{Frontiers, VersionRange, VersionVector, VersionVectorDiff},
+    view::DocView,
 };
 
 pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -22,6 +30,7 @@ pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<Configure>()?;
     m.add_class::<ImportStatus>()?;
     m.add_class::<PosQueryResult>()?;
+    m.add_class::<AbsolutePosition>()?;
     m.add_class::<EncodedBlobMode>()?;
     m.add_class::<ImportBlobMetadata>()?;
     m.add_class::<StyleConfigMap>()?;
@@ -40,15 +49,16 @@ pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
 /// **Important:** Loro is a pure library and does not handle network protocols.
 /// It is the responsibility of the user to manage the storage, loading, and synchronization
 /// of the bytes exported by Loro in a manner suitable for their specific environment.
-#[pyclass(frozen)]
+#[pyclass(frozen, module = "loro")]
 pub struct LoroDoc {
     pub(crate) doc: LoroDocInner,
+    /// The copy of the history `import_checked` imports blobs into.
+    pub(crate) staging: import_hook::Staging,
 }
 
 impl Default for LoroDoc {
     fn default() -> Self {
-        let doc = LoroDocInner::new();
-        Self { doc }
+        LoroDocInner::new().into()
     }
 }
 
@@ -66,18 +76,55 @@ impl LoroDoc {
     ///
     /// When called in detached mode, it will fork at the current state frontiers.
     /// It will have the same effect as `fork_at(&self.state_frontiers())`.
+    ///
+    /// The GIL is released while the document is copied.
     #[inline]
-    pub fn fork(&self) -> Self {
-        let doc = self.doc.fork();
-        Self { doc }
+    pub fn fork(&self, py: Python<'_>) -> Self {
+        py.detach(|| self.doc.fork()).into()
     }
 
     /// Fork the document at the given frontiers.
     ///
     /// The created doc will only contain the history before the specified frontiers.
-    pub fn fork_at(&self, frontiers: &Frontiers) -> Self {
-        let new_doc = self.doc.fork_at(&frontiers.into());
-        Self { doc: new_doc }
+    ///
+    /// The GIL is released while the document is copied.
+    pub fn fork_at(&self, py: Python<'_>, frontiers: &Frontiers) -> Self {
+        let frontiers = frontiers.into();
+        py.detach(|| self.doc.fork_at(&frontiers)).into()
+    }
+
+    /// Get a read-only view of the document at the given frontiers.
+    ///
+    /// Unlike `checkout`, the document stays attached, and unlike `fork_at`,
+    /// the returned containers are read-only. Several views can be held at the
+    /// same time.
+    ///
+    /// The GIL is released while the view is created.
+    pub fn view_at(&self, py: Python<'_>, frontiers: &Frontiers) -> PyLoroResult<DocView> {
+        let frontiers = frontiers.into();
+        py.detach(|| DocView::new(&self.doc, frontiers))
+    }
+
+    /// Shallow copy of the document, same as `fork`.
+    pub fn __copy__(&self, py: Python<'_>) -> Self {
+        self.fork(py)
+    }
+
+    /// Deep copy of the document, same as `fork`.
+    pub fn __deepcopy__(&self, py: Python<'_>, _memo: &Bound<'_, PyAny>) -> Self {
+        self.fork(py)
+    }
+
+    /// Pickle the document as a full snapshot.
+    ///
+    /// The unpickled document gets a new random PeerID, just like `fork`.
+    pub fn __getstate__(&self, py: Python<'_>) -> PyLoroResult<Cow<'_, [u8]>> {
+        self.export(py, ExportMode::Snapshot {}, None)
+    }
+
+    pub fn __setstate__(&self, py: Python<'_>, state: Bound<'_, PyBytes>) -> PyLoroResult<()> {
+        self.import(py, state)?;
+        Ok(())
     }
 
     /// Get the configurations of the document.
@@ -115,6 +162,27 @@ impl LoroDoc {
         Ok(meta.into())
     }
 
+    /// Create a document from a dict whose entries are its root containers.
+    ///
+    /// The values become `LoroMap`s, `LoroList`s or `LoroText`s, and so do
+    /// their nested dicts, lists and strings, according to `policy`. The
+    /// document is committed once all of them are created.
+    #[classmethod]
+    #[pyo3(signature = (obj, policy=None))]
+    pub fn from_json(
+        _cls: &Bound<'_, PyType>,
+        obj: LoroValue,
+        policy: Option<&DeepPolicy>,
+    ) -> PyLoroResult<Self> {
+        let ans = Self::default();
+        deep::from_json(
+            &ans.doc,
+            obj.into(),
+            policy.unwrap_or(&DeepPolicy::default()),
+        )?;
+        Ok(ans)
+    }
+
     /// Set whether to record the timestamp of each change. Default is `false`.
     ///
     /// If enabled, the Unix timestamp will be recorded for each change automatically.
@@ -217,9 +285,12 @@ impl LoroDoc {
     /// recorded in the `OpLog` without being applied to the `DocState`.
     ///
     /// You should call `attach` to attach the `DocState` to the latest version of `OpLog`.
+    ///
+    /// The GIL is released during the checkout. It is re-acquired only to dispatch events.
     #[inline]
-    pub fn checkout(&self, frontiers: &Frontiers) -> PyLoroResult<()> {
-        self.doc.checkout(&frontiers.into())?;
+    pub fn checkout(&self, py: Python<'_>, frontiers: &Frontiers) -> PyLoroResult<()> {
+        let frontiers = frontiers.into();
+        py.detach(|| self.doc.checkout(&frontiers))?;
         Ok(())
     }
 
@@ -267,13 +338,19 @@ impl LoroDoc {
         self.doc.detach()
     }
 
-    // /// Import a batch of updates/snapshot.
-    // ///
-    // /// The data can be in arbitrary order. The import result will be the same.
+    /// Import a batch of updates/snapshot.
+    ///
+    /// The data can be in arbitrary order. The import result will be the same.
+    ///
+    /// The GIL is released during the import. It is re-acquired only to dispatch events.
     #[inline]
-    pub fn import_batch(&self, bytes: Vec<Bound<'_, PyBytes>>) -> PyLoroResult<ImportStatus> {
+    pub fn import_batch(
+        &self,
+        py: Python<'_>,
+        bytes: Vec<Bound<'_, PyBytes>>,
+    ) -> PyLoroResult<ImportStatus> {
         let vec_bytes: Vec<Vec<u8>> = bytes.into_iter().map(|b| b.as_bytes().to_vec()).collect();
-        let status = self.doc.import_batch(&vec_bytes)?;
+        let status = py.detach(|| self.doc.import_batch(&vec_bytes))?;
         Ok(ImportStatus::from(status))
     }
 
@@ -421,10 +498,13 @@ impl LoroDoc {
     }
 
     /// Import updates/snapshot exported by [`LoroDoc::export_snapshot`] or [`LoroDoc::export_from`].
+    ///
+    /// The GIL is released during the import. It is re-acquired only to dispatch events.
     #[pyo3(name = "import_")]
     #[inline]
-    pub fn import(&self, bytes: Bound<'_, PyBytes>) -> PyLoroResult<ImportStatus> {
-        let status = self.doc.import(bytes.as_bytes())?;
+    pub fn import(&self, py: Python<'_>, bytes: Bound<'_, PyBytes>) -> PyLoroResult<ImportStatus> {
+        let bytes = bytes.as_bytes().to_vec();
+        let status = py.detach(|| self.doc.import(&bytes))?;
         Ok(ImportStatus::from(status))
     }
 
@@ -432,16 +512,60 @@ impl LoroDoc {
     ///
     /// It marks the import with a custom `origin` string. It can be used to track the import source
     /// in the generated events.
+    ///
+    /// The GIL is released during the import. It is re-acquired only to dispatch events.
     #[inline]
     pub fn import_with(
         &self,
+        py: Python<'_>,
         bytes: Bound<'_, PyBytes>,
         origin: &str,
     ) -> PyLoroResult<ImportStatus> {
-        let status = self.doc.import_with(bytes.as_bytes(), origin)?;
+        let bytes = bytes.as_bytes().to_vec();
+        let status = py.detach(|| self.doc.import_with(&bytes, origin))?;
         Ok(ImportStatus::from(status))
     }
 
+    /// Import updates or a snapshot after checking them with `check`.
+    ///
+    /// `check(meta, changes)` receives the `ImportBlobMetadata` of the blob and
+    /// the list of the changes it would add to the document, as `ChangeMeta`
+    /// with their ops decoded, in causal order. It is called before the
+    /// document is modified, and returns:
+    ///
+    /// - `None` or `True` to import every change,
+    /// - `False` to reject the blob, which raises `ImportRejectedError`,
+    /// - an iterable of peer ids to drop the changes of those peers, together
+    ///   with the changes that depend on them. The rest is imported, and the
+    ///   dropped changes are reported in `ImportStatus.rejected`.
+    ///
+    /// Exceptions raised by `check` are propagated and nothing is imported.
+    /// Changes whose dependencies are missing are never imported and are
+    /// reported in `ImportStatus.pending`, so that they cannot be applied
+    /// later without being checked.
+    ///
+    /// The blob is decoded by importing it into a copy of the history of the
+    /// document. The copy is made by the first call, and is then kept up to
+    /// date with the changes of the document, unless changes were rejected or
+    /// pending. The GIL is released while the blob is imported.
+    #[pyo3(signature = (bytes, check, *, origin=None))]
+    pub fn import_checked(
+        &self,
+        py: Python<'_>,
+        bytes: Bound<'_, PyBytes>,
+        check: &Bound<'_, PyAny>,
+        origin: Option<&str>,
+    ) -> PyLoroResult<ImportStatus> {
+        import_hook::import_checked(
+            py,
+            &self.doc,
+            &self.staging,
+            bytes.as_bytes(),
+            check,
+            origin,
+        )
+    }
+
     /// Import the json schema updates.
     ///
     /// only supports backward compatibility but not forward compatibility.
@@ -626,6 +750,9 @@ impl LoroDoc {
     /// - `doc.import(data)` is called.
     /// - `doc.checkout(version)` is called.
     ///
+    /// Exceptions raised by the callback cannot interrupt the transaction. They are
+    /// reported through `sys.unraisablehook` and the subscription stays alive.
+    ///
     /// # Example
     ///
     /// ```
@@ -664,7 +791,7 @@ impl LoroDoc {
             &container_id.into(),
             Arc::new(move |e| {
                 Python::attach(|py| {
-                    callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                    callback::call(py, &callback, (DiffEvent::from(e),));
                 });
             }),
         );
@@ -682,34 +809,84 @@ impl LoroDoc {
     /// - `doc.export(mode)` is called.
     /// - `doc.import(data)` is called.
     /// - `doc.checkout(version)` is called.
+    ///
+    /// Exceptions raised by the callback cannot interrupt the transaction. They are
+    /// reported through `sys.unraisablehook` and the subscription stays alive.
     #[inline]
     pub fn subscribe_root(&self, callback: Py<PyAny>) -> Subscription {
         let subscription = self.doc.subscribe_root(Arc::new(move |e| {
             Python::attach(|py| {
-                callback.call1(py, (DiffEvent::from(e),)).unwrap();
+                callback::call(py, &callback, (DiffEvent::from(e),));
             });
         }));
         subscription.into()
     }
 
+    /// Return an async iterator over the `DiffEvent`s of the document.
+    ///
+    /// If `container_id` is given, only the events of that container are yielded.
+    /// The events are buffered until they are consumed, in a buffer of at most
+    /// `maxsize` events; `overflow` decides what happens when it is full.
+    ///
+    /// ```python
+    /// async for event in doc.events():
+    ///     ...
+    /// ```
+    #[pyo3(signature = (container_id=None, *, maxsize=DEFAULT_MAXSIZE, overflow=OverflowPolicy::Error))]
+    pub fn events(
+        &self,
+        container_id: Option<ContainerID>,
+        maxsize: usize,
+        overflow: OverflowPolicy,
+    ) -> PyResult<EventStream> {
+        EventStream::new(maxsize, overflow, |sender| {
+            let callback: loro::event::Subscriber = Arc::new(move |e| {
+                Python::attach(|py| {
+                    if let Ok(event) = Py::new(py, DiffEvent::from(e)) {
+                        sender.send(py, event.into_any());
+                    }
+                });
+            });
+            Some(match container_id {
+                Some(id) => self.doc.subscribe(&id.into(), callback),
+                None => self.doc.subscribe_root(callback),
+            })
+        })
+    }
+
+    /// Return an async iterator over the local updates of the document.
+    ///
+    /// It yields the same encoded updates as `subscribe_local_update`, buffered in
+    /// a buffer of at most `maxsize` updates; `overflow` decides what happens when
+    /// it is full. Dropping updates breaks synchronization, so the default policy
+    /// closes the stream instead.
+    #[pyo3(signature = (*, maxsize=DEFAULT_MAXSIZE, overflow=OverflowPolicy::Error))]
+    pub fn local_updates(&self, maxsize: usize, overflow: OverflowPolicy) -> PyResult<EventStream> {
+        EventStream::new(maxsize, overflow, |sender| {
+            Some(self.doc.subscribe_local_update(Box::new(move |updates| {
+                Python::attach(|py| sender.send(py, PyBytes::new(py, updates).into_any().unbind()))
+            })))
+        })
+    }
+
     /// Subscribe the local update of the document.
+    ///
+    /// The callback receives the encoded update as `bytes`. Returning `False` removes the
+    /// subscription; any other return value keeps it alive.
     pub fn subscribe_local_update(&self, callback: Py<PyAny>) -> Subscription {
         let subscription = self.doc.subscribe_local_update(Box::new(move |updates| {
-            Python::attach(|py| {
-                let b = callback.call1(py, (updates,)).unwrap();
-                b.extract::<bool>(py).unwrap()
-            })
+            Python::attach(|py| callback::call_keep_alive(py, &callback, (updates,)))
         }));
         subscription.into()
     }
 
     /// Subscribe the peer id change of the document.
+    ///
+    /// Returning `False` from the callback removes the subscription; any other return value
+    /// keeps it alive.
     pub fn subscribe_peer_id_change(&self, callback: Py<PyAny>) -> Subscription {
         let subscription = self.doc.subscribe_peer_id_change(Box::new(move |id| {
-            Python::attach(|py| {
-                let b = callback.call1(py, (ID::from(*id),)).unwrap();
-                b.extract::<bool>(py).unwrap()
-            })
+            Python::attach(|py| callback::call_keep_alive(py, &callback, (ID::from(*id),)))
         }));
         subscription.into()
     }
@@ -802,8 +979,51 @@ impl LoroDoc {
     }
 
     /// Export the document in the given mode.
-    pub fn export(&self, mode: ExportMode) -> PyLoroResult<Cow<'_, [u8]>> {
-        let ans = self.doc.export(mode.into())?;
+    ///
+    /// If `containers` is given, only these containers and their descendants
+    /// are exported, along with the entries leading to them from the root, so
+    /// that the receiving document can resolve their path. The output is then
+    /// a snapshot of the history up to the version of `mode`, rewritten so
+    /// that it never contains the other containers: the modes `Snapshot`,
+    /// `ShallowSnapshot`, `StateOnly` and `SnapshotAt` are accepted. It is at
+    /// the same version as the document, so that the two documents can
+    /// exchange updates afterwards. The other items of the lists on the path
+    /// to the containers are exported as `None`. Shallow documents are not
+    /// accepted.
+    ///
+    /// The GIL is released while the document is encoded.
+    #[pyo3(signature = (mode, *, containers=None))]
+    pub fn export(
+        &self,
+        py: Python<'_>,
+        mode: ExportMode,
+        containers: Option<Vec<ContainerID>>,
+    ) -> PyLoroResult<Cow<'_, [u8]>> {
+        if let Some(containers) = containers {
+            let frontiers = match mode {
+                ExportMode::Snapshot {} | ExportMode::StateOnly { frontiers: None } => None,
+                ExportMode::ShallowSnapshot { frontiers }
+                | ExportMode::StateOnly {
+                    frontiers: Some(frontiers),
+                }
+                | ExportMode::SnapshotAt { version: frontiers } => Some(frontiers.into()),
+                ExportMode::Updates { .. } | ExportMode::UpdatesInRange { .. } => {
+                    return Err(InvalidArgumentError::new_err(
+                        "updates cannot be restricted to some containers",
+                    )
+                    .into())
+                }
+            };
+            let ids: Vec<_> = containers
+                .into_iter()
+                .map(loro::ContainerID::from)
+                .collect();
+            let ans =
+                py.detach(|| partial::export_containers(&self.doc, &ids, frontiers.as_ref()))?;
+            return Ok(Cow::Owned(ans));
+        }
+        let mode: loro::ExportMode = mode.into();
+        let ans = py.detach(|| self.doc.export(mode))?;
         Ok(Cow::Owned(ans))
     }
 
@@ -824,6 +1044,27 @@ impl LoroDoc {
         })
     }
 
+    /// Check the document against `schema`, and return the places where it
+    /// does not match.
+    ///
+    /// This is typically called after importing updates from an untrusted
+    /// peer. Every violation is addressed by the path returned by
+    /// `get_path_to_container`.
+    pub fn validate(&self, schema: &DocSchema) -> Vec<SchemaViolation> {
+        schema.validate(&self.doc)
+    }
+
+    /// Upgrade the document to the version of `schema` by running its pending
+    /// migrations, and return the version the document was at.
+    ///
+    /// All the migrations are applied in a single commit. They run on a fork
+    /// of the document, so if one of them raises, or if the result does not
+    /// match the schema, the document is left untouched and the error (a
+    /// `SchemaError` for violations) is raised.
+    pub fn migrate(&self, py: Python<'_>, schema: &DocSchema) -> PyLoroResult<i64> {
+        schema.migrate(py, &self.doc)
+    }
+
     /// Evaluate a JSONPath expression on the document and return matching values or handlers.
     ///
     /// This method allows querying the document structure using JSONPath syntax.
@@ -879,24 +1120,60 @@ impl LoroDoc {
     ///
     /// * `ids` - The IDs of the Change to start the traversal from.
     /// * `cb` - A callback function that is called for each ancestor. It can return `True` to stop the traversal.
-    pub fn travel_change_ancestors(&self, ids: Vec<ID>, cb: Py<PyAny>) -> PyLoroResult<()> {
+    ///
+    /// If the callback raises, the traversal stops and the exception is re-raised.
+    pub fn travel_change_ancestors(
+        &self,
+        py: Python<'_>,
+        ids: Vec<ID>,
+        cb: Py<PyAny>,
+    ) -> PyLoroResult<()> {
+        let mut err = None;
         self.doc.travel_change_ancestors(
             &ids.into_iter().map(|id| id.into()).collect::<Vec<_>>(),
             &mut |meta| {
-                let b = Python::attach(|py| {
-                    cb.call1(py, (ChangeMeta::from(meta),))
-                        .unwrap()
-                        .extract::<bool>(py)
-                        .unwrap()
-                });
-                if b {
+                let stop = callback::call_capture(py, &cb, (ChangeMeta::from(meta),), &mut err)
+                    .is_none_or(|ret| ret.extract::<bool>().unwrap_or(false));
+                if stop {
                     ControlFlow::Break(())
                 } else {
                     ControlFlow::Continue(())
                 }
             },
         )?;
-        Ok(())
+        err.map_or(Ok(()), |e| Err(e.into()))
+    }
+
+    /// Iterate over the changes between two versions with their ops decoded.
+    ///
+    /// `from_` and `to` can be `Frontiers` or a `VersionVector`. They default to
+    /// the empty version and the latest version of the oplog. Changes are
+    /// yielded in causal order as `ChangeMeta` whose `ops` are filled. A change
+    /// that is only partially included in the range is trimmed to it.
+    ///
+    /// The results can be filtered by the authors of the changes (`peers`),
+    /// their timestamp (`start_time <= timestamp < end_time`) and the
+    /// containers they modified. When `containers` is given, only the ops on
+    /// those containers are kept.
+    ///
+    /// **NOTE:** This method will implicitly commit if `containers` is given.
+    #[pyo3(signature = (from_=None, to=None, *, peers=None, start_time=None, end_time=None, containers=None))]
+    pub fn changes(
+        &self,
+        from_: Option<Version>,
+        to: Option<Version>,
+        peers: Option<Vec<PeerID>>,
+        start_time: Option<Timestamp>,
+        end_time: Option<Timestamp>,
+        containers: Option<Vec<ContainerID>>,
+    ) -> PyResult<ChangeIter> {
+        let filter = ChangeFilter {
+            peers: peers.map(|p| p.into_iter().collect()),
+            start_time,
+            end_time,
+            containers: containers.map(|c| c.into_iter().map(loro::ContainerID::from).collect()),
+        };
+        ChangeIter::new(&self.doc, from_, to, filter)
     }
 
     /// Check if the doc contains the full history.
@@ -928,9 +1205,12 @@ impl LoroDoc {
     /// Internally, it will generate a series of local operations that can revert the
     /// current doc to the target version. It will calculate the diff between the current
     /// state and the target state, and apply the diff to the current state.
+    ///
+    /// The GIL is released while the diff is calculated and applied.
     #[inline]
-    pub fn revert_to(&self, version: &Frontiers) -> PyLoroResult<()> {
-        self.doc.revert_to(&version.into())?;
+    pub fn revert_to(&self, py: Python<'_>, version: &Frontiers) -> PyLoroResult<()> {
+        let version = version.into();
+        py.detach(|| self.doc.revert_to(&version))?;
         Ok(())
     }
 
@@ -943,11 +1223,28 @@ impl LoroDoc {
         Ok(())
     }
 
+    /// Apply a JSON patch (RFC 6902), given as a list of operations, and commit it.
+    ///
+    /// The paths are JSON pointers from the root of the document, whose first
+    /// token is the name of a root container. Objects and arrays added by the
+    /// patch become `LoroMap`s and `LoroList`s, a string replacing a `LoroText`
+    /// updates it, and moves inside a `LoroMovableList` use `mov`.
+    ///
+    /// The patch is checked before it is applied, so either all of it is
+    /// applied or, if an operation fails, including a `test`, a
+    /// `JsonPatchError` is raised and the document is left unchanged.
+    pub fn apply_json_patch(&self, patch: LoroValue) -> PyLoroResult<()> {
+        json_patch::apply_json_patch(&self.doc, &patch.into())
+    }
+
     /// Calculate the diff between two versions
+    ///
+    /// The GIL is released while the diff is calculated.
     #[inline]
-    pub fn diff(&self, a: &Frontiers, b: &Frontiers) -> PyLoroResult<DiffBatch> {
-        let ans = self.doc.diff(&a.into(), &b.into())?;
-        Ok(ans.into())
+    pub fn diff(&self, py: Python<'_>, a: &Frontiers, b: &Frontiers) -> PyLoroResult<DiffBatch> {
+        let (a, b): (loro::Frontiers, loro::Frontiers) = (a.into(), b.into());
+        let ans = py.detach(|| self.doc.diff(&a, &b))?;
+        Ok(DiffBatch::with_source(ans, self.doc.clone(), a, b))
     }
 
     /// Check if the doc contains the target container.
@@ -975,10 +1272,11 @@ impl LoroDoc {
             .doc
             .subscribe_first_commit_from_peer(Box::new(move |payload| {
                 Python::attach(|py| {
-                    let b = callback
-                        .call1(py, (FirstCommitFromPeerPayload { peer: payload.peer },))
-                        .unwrap();
-                    b.extract::<bool>(py).unwrap()
+                    callback::call_keep_alive(
+                        py,
+                        &callback,
+                        (FirstCommitFromPeerPayload { peer: payload.peer },),
+                    )
                 })
             }));
         subscription.into()
@@ -991,17 +1289,15 @@ impl LoroDoc {
     pub fn subscribe_pre_commit(&self, callback: Py<PyAny>) -> Subscription {
         let subscription = self.doc.subscribe_pre_commit(Box::new(move |payload| {
             Python::attach(|py| {
-                let b = callback
-                    .call1(
-                        py,
-                        (PreCommitCallbackPayload {
-                            change_meta: payload.change_meta.clone().into(),
-                            origin: payload.origin.clone(),
-                            modifier: ChangeModifier(payload.modifier.clone()),
-                        },),
-                    )
-                    .unwrap();
-                b.extract::<bool>(py).unwrap()
+                callback::call_keep_alive(
+                    py,
+                    &callback,
+                    (PreCommitCallbackPayload {
+                        change_meta: payload.change_meta.clone().into(),
+                        origin: payload.origin.clone(),
+                        modifier: ChangeModifier(payload.modifier.clone()),
+                    },),
+                )
             })
         }));
         subscription.into()
@@ -1057,6 +1353,27 @@ impl LoroDoc {
             .map_err(|e| PyLoroError::Error(e.to_string()))?;
         Ok(serde_json::to_string(&schema).unwrap())
     }
+
+    /// Redacts the operations within the specified version range according to
+    /// `policy`, and exports the whole redacted history.
+    ///
+    /// Unlike `redact_json_updates`, the content can be kept, nulled, hashed
+    /// or dropped per container and per map key, and the values of some text
+    /// marks can be preserved. The operations keep their ids, so the result
+    /// can be imported by peers that already have the unredacted history.
+    ///
+    /// Returns a snapshot, or all the updates if `snapshot` is false.
+    #[pyo3(signature = (version_range, policy, *, snapshot=true))]
+    pub fn redact(
+        &self,
+        py: Python,
+        version_range: VersionRange,
+        policy: &RedactPolicy,
+        snapshot: bool,
+    ) -> PyLoroResult<Cow<'_, [u8]>> {
+        let bytes = py.detach(|| redact::redact(&self.doc, &version_range, policy, snapshot))?;
+        Ok(Cow::Owned(bytes))
+    }
 }
 
 #[derive(Debug, IntoPyObject)]
@@ -1136,6 +1453,8 @@ impl Configure {
 pub struct ImportStatus {
     pub success: VersionRange,
     pub pending: Option<VersionRange>,
+    /// The changes rejected by the hook of `import_checked`.
+    pub rejected: Option<VersionRange>,
 }
 
 impl From<loro::ImportStatus> for ImportStatus {
@@ -1144,6 +1463,7 @@ impl From<loro::ImportStatus> for ImportStatus {
         Self {
             success: a.into(),
             pending: value.pending.map(|x| x.into()),
+            rejected: None,
         }
     }
 }
@@ -1175,6 +1495,15 @@ pub struct AbsolutePosition {
     pub side: Side,
 }
 
+#[pymethods]
+impl AbsolutePosition {
+    #[new]
+    #[pyo3(signature = (pos, side=Side::Middle))]
+    pub fn new(pos: usize, side: Side) -> Self {
+        Self { pos, side }
+    }
+}
+
 impl Display for AbsolutePosition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -1257,6 +1586,11 @@ pub struct ChangeMeta {
     pub deps: Frontiers,
     /// The total op num inside this change
     pub len: usize,
+    /// The decoded ops of the change.
+    ///
+    /// It is only filled by `LoroDoc.changes()` and for the hook of
+    /// `LoroDoc.import_checked()`, and is `None` otherwise.
+    pub ops: Option<Vec<Op>>,
 }
 
 impl Display for ChangeMeta {
@@ -1,5 +1,169 @@
-use loro::{CannotFindRelativePosition, ChangeTravelError, LoroEncodeError};
-use pyo3::{exceptions::PyBaseException, PyErr};
+use loro::{CannotFindRelativePosition, ChangeTravelError, LoroEncodeError, LoroTreeError};
+use pyo3::{
+    create_exception,
+    exceptions::{PyBaseException, PyException},
+    prelude::*,
+    PyErr,
+};
+
+use crate::{
+    schema::SchemaViolation,
+    value::{ContainerID, TreeID, ID},
+};
+
+create_exception!(
+    loro,
+    LoroError,
+    PyException,
+    "Base class of every error raised by Loro."
+);
+create_exception!(
+    loro,
+    DecodeError,
+    LoroError,
+    "The imported data is corrupted, incompatible or cannot be decoded."
+);
+create_exception!(
+    loro,
+    EncodeError,
+    LoroError,
+    "The document cannot be exported in the requested mode."
+);
+create_exception!(
+    loro,
+    OutOfBoundError,
+    LoroError,
+    "The given index or range is out of the bounds of the container."
+);
+create_exception!(
+    loro,
+    ContainerNotFoundError,
+    LoroError,
+    "The target container does not exist in the document."
+);
+create_exception!(
+    loro,
+    ContainerDeletedError,
+    ContainerNotFoundError,
+    "The target container has been deleted."
+);
+create_exception!(
+    loro,
+    DetachedEditError,
+    LoroError,
+    "The document is detached and detached editing is not enabled."
+);
+create_exception!(
+    loro,
+    DetachedContainerError,
+    LoroError,
+    "The method is not allowed on a container that is not attached to a document."
+);
+create_exception!(
+    loro,
+    CursorNotFoundError,
+    LoroError,
+    "The position of the cursor cannot be resolved."
+);
+create_exception!(
+    loro,
+    VersionNotFoundError,
+    LoroError,
+    "The given version is not included in the document."
+);
+create_exception!(
+    loro,
+    PeerIdError,
+    LoroError,
+    "The PeerID is invalid or conflicts with existing operations."
+);
+create_exception!(
+    loro,
+    TransactionError,
+    LoroError,
+    "The operation conflicts with the current transaction."
+);
+create_exception!(
+    loro,
+    TreeError,
+    LoroError,
+    "The operation on a LoroTree is invalid."
+);
+create_exception!(
+    loro,
+    UndoError,
+    LoroError,
+    "The operation on an UndoManager is invalid."
+);
+create_exception!(
+    loro,
+    StreamOverflowError,
+    LoroError,
+    "The buffer of an event stream is full because its consumer is too slow."
+);
+create_exception!(
+    loro,
+    InvalidArgumentError,
+    LoroError,
+    "An argument passed to Loro is invalid."
+);
+create_exception!(
+    loro,
+    ImportRejectedError,
+    LoroError,
+    "The import hook rejected the imported updates."
+);
+create_exception!(
+    loro,
+    SchemaError,
+    LoroError,
+    "The document does not match its schema, or cannot be migrated."
+);
+create_exception!(
+    loro,
+    JsonPatchError,
+    LoroError,
+    "The JSON patch is invalid, or one of its operations cannot be applied."
+);
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("LoroError", py.get_type::<LoroError>())?;
+    m.add("DecodeError", py.get_type::<DecodeError>())?;
+    m.add("EncodeError", py.get_type::<EncodeError>())?;
+    m.add("OutOfBoundError", py.get_type::<OutOfBoundError>())?;
+    m.add(
+        "ContainerNotFoundError",
+        py.get_type::<ContainerNotFoundError>(),
+    )?;
+    m.add(
+        "ContainerDeletedError",
+        py.get_type::<ContainerDeletedError>(),
+    )?;
+    m.add("DetachedEditError", py.get_type::<DetachedEditError>())?;
+    m.add(
+        "DetachedContainerError",
+        py.get_type::<DetachedContainerError>(),
+    )?;
+    m.add("CursorNotFoundError", py.get_type::<CursorNotFoundError>())?;
+    m.add(
+        "VersionNotFoundError",
+        py.get_type::<VersionNotFoundError>(),
+    )?;
+    m.add("PeerIdError", py.get_type::<PeerIdError>())?;
+    m.add("TransactionError", py.get_type::<TransactionError>())?;
+    m.add("TreeError", py.get_type::<TreeError>())?;
+    m.add("UndoError", py.get_type::<UndoError>())?;
+    m.add("StreamOverflowError", py.get_type::<StreamOverflowError>())?;
+    m.add(
+        "InvalidArgumentError",
+        py.get_type::<InvalidArgumentError>(),
+    )?;
+    m.add("ImportRejectedError", py.get_type::<ImportRejectedError>())?;
+    m.add("SchemaError", py.get_type::<SchemaError>())?;
+    m.add("JsonPatchError", py.get_type::<JsonPatchError>())?;
+    Ok(())
+}
 
 pub enum PyLoroError {
     LoroError(loro::LoroError),
@@ -36,15 +200,154 @@ impl From<ChangeTravelError> for PyLoroError {
     }
 }
 
+/// Attach structured attributes to a freshly created exception.
+///
+/// If setting an attribute fails, the error of `setattr` is returned instead so
+/// that it does not get lost silently.
+fn with_attrs(err: PyErr, f: impl FnOnce(&Bound<'_, PyBaseException>) -> PyResult<()>) -> PyErr {
+    Python::attach(|py| match f(err.value(py)) {
+        Ok(()) => err,
+        Err(e) => e,
+    })
+}
+
+/// A `SchemaError` carrying the violations in its `violations` attribute.
+pub(crate) fn schema_error(msg: String, violations: Vec<SchemaViolation>) -> PyErr {
+    let msg = match violations.first() {
+        Some(first) => format!("{msg}: {first}"),
+        None => msg,
+    };
+    with_attrs(SchemaError::new_err(msg), |v| {
+        v.setattr("violations", violations)
+    })
+}
+
+fn tree_error_to_pyerr(e: LoroTreeError) -> PyErr {
+    let msg = e.to_string();
+    match e {
+        LoroTreeError::TreeNodeParentNotFound(id)
+        | LoroTreeError::TreeNodeNotExist(id)
+        | LoroTreeError::TreeNodeDeletedOrNotExist(id) => {
+            with_attrs(TreeError::new_err(msg), |v| {
+                v.setattr("tree_id", TreeID::from(id))
+            })
+        }
+        LoroTreeError::IndexOutOfBound { len, index } => {
+            with_attrs(OutOfBoundError::new_err(msg), |v| {
+                v.setattr("pos", index)?;
+                v.setattr("len", len)
+            })
+        }
+        LoroTreeError::CyclicMoveError
+        | LoroTreeError::InvalidParent
+        | LoroTreeError::FractionalIndexNotEnabled => TreeError::new_err(msg),
+    }
+}
+
+fn loro_error_to_pyerr(e: loro::LoroError) -> PyErr {
+    use loro::LoroError as E;
+    let msg = e.to_string();
+    match e {
+        E::DecodeVersionVectorError
+        | E::DecodeError(_)
+        | E::DecodeDataCorruptionError
+        | E::DecodeChecksumMismatchError
+        | E::IncompatibleFutureEncodingError(_)
+        | E::ImportUpdatesThatDependsOnOutdatedVersion
+        | E::InvalidJsonSchema => DecodeError::new_err(msg),
+        E::OutOfBound { pos, len, info } => with_attrs(OutOfBoundError::new_err(msg), |v| {
+            v.setattr("pos", pos)?;
+            v.setattr("len", len)?;
+            v.setattr("info", info.to_string())
+        }),
+        E::UTF8InUnicodeCodePoint { pos } | E::UTF16InUnicodeCodePoint { pos } => {
+            with_attrs(OutOfBoundError::new_err(msg), |v| v.setattr("pos", pos))
+        }
+        E::EndIndexLessThanStartIndex { start, end } => {
+            with_attrs(OutOfBoundError::new_err(msg), |v| {
+                v.setattr("start", start)?;
+                v.setattr("end", end)
+            })
+        }
+        E::ContainersNotFound { containers } => {
+            with_attrs(ContainerNotFoundError::new_err(msg), |v| {
+                let ids: Vec<ContainerID> = containers.into_iter().map(Into::into).collect();
+                v.setattr("container_ids", ids)
+            })
+        }
+        E::ContainerDeleted { container } => with_attrs(ContainerDeletedError::new_err(msg), |v| {
+            v.setattr("container_id", ContainerID::from(*container))
+        }),
+        E::EditWhenDetached | E::AutoCommitNotStarted => DetachedEditError::new_err(msg),
+        E::MisuseDetachedContainer { method } => {
+            with_attrs(DetachedContainerError::new_err(msg), |v| {
+                v.setattr("method", method)
+            })
+        }
+        E::FrontiersNotFound(id) | E::UndoInvalidIdSpan(id) => {
+            with_attrs(VersionNotFoundError::new_err(msg), |v| {
+                v.setattr("id", ID::from(id))
+            })
+        }
+        E::NotFoundError(_) | E::SwitchToVersionBeforeShallowRoot => {
+            VersionNotFoundError::new_err(msg)
+        }
+        E::UnmatchedContext { expected, found } => with_attrs(PeerIdError::new_err(msg), |v| {
+            v.setattr("expected", expected)?;
+            v.setattr("found", found)
+        }),
+        E::UndoWithDifferentPeerId { expected, actual } => {
+            with_attrs(PeerIdError::new_err(msg), |v| {
+                v.setattr("expected", expected)?;
+                v.setattr("found", actual)
+            })
+        }
+        E::UsedOpID { id } => {
+            with_attrs(PeerIdError::new_err(msg), |v| v.setattr("id", ID::from(id)))
+        }
+        E::ConcurrentOpsWithSamePeerID { peer, .. } => {
+            with_attrs(PeerIdError::new_err(msg), |v| v.setattr("peer", peer))
+        }
+        E::InvalidPeerID => PeerIdError::new_err(msg),
+        E::DuplicatedTransactionError
+        | E::TransactionError(_)
+        | E::ImportWhenInTxn
+        | E::LockError => TransactionError::new_err(msg),
+        E::TreeError(e) => tree_error_to_pyerr(e),
+        E::UndoGroupAlreadyStarted => UndoError::new_err(msg),
+        E::ArgErr(_)
+        | E::InvalidRootContainerName
+        | E::StyleConfigMissing(_)
+        | E::ReattachAttachedContainer => InvalidArgumentError::new_err(msg),
+        _ => LoroError::new_err(msg),
+    }
+}
+
 impl From<PyLoroError> for PyErr {
     fn from(value: PyLoroError) -> Self {
         match value {
-            PyLoroError::LoroError(e) => PyBaseException::new_err(e.to_string()),
-            PyLoroError::CannotFindRelativePosition(e) => PyBaseException::new_err(e.to_string()),
-            PyLoroError::LoroEncodeError(e) => PyBaseException::new_err(e.to_string()),
-            PyLoroError::ChangeTravelError(e) => PyBaseException::new_err(e.to_string()),
+            PyLoroError::LoroError(e) => loro_error_to_pyerr(e),
+            PyLoroError::CannotFindRelativePosition(e) => {
+                CursorNotFoundError::new_err(e.to_string())
+            }
+            PyLoroError::LoroEncodeError(e) => match e {
+                LoroEncodeError::FrontiersNotFound(_) => {
+                    VersionNotFoundError::new_err(e.to_string())
+                }
+                _ => EncodeError::new_err(e.to_string()),
+            },
+            PyLoroError::ChangeTravelError(e) => match e {
+                ChangeTravelError::TargetIdNotFound(id) => {
+                    with_attrs(VersionNotFoundError::new_err(e.to_string()), |v| {
+                        v.setattr("id", ID::from(id))
+                    })
+                }
+                ChangeTravelError::TargetVersionNotIncluded => {
+                    VersionNotFoundError::new_err(e.to_string())
+                }
+            },
             PyLoroError::PyError(e) => e,
-            PyLoroError::Error(e) => PyBaseException::new_err(e),
+            PyLoroError::Error(e) => LoroError::new_err(e),
         }
     }
 }
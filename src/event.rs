@@ -1,3 +1,5 @@
+use crate::err::PyLoroResult;
+use crate::report::{self, DiffReport, DiffSource};
 use crate::value::{ContainerID, LoroValue, TreeID, TreeParentId, ValueOrContainer};
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyTuple};
@@ -451,7 +453,20 @@ impl Subscription {
 
 #[pyclass(str)]
 #[derive(Debug, Clone, Default)]
-pub struct DiffBatch(loro::event::DiffBatch);
+pub struct DiffBatch(loro::event::DiffBatch, Option<DiffSource>);
+
+impl DiffBatch {
+    /// A batch calculated by `LoroDoc.diff` from the version `from` to the
+    /// version `to` of `doc`.
+    pub fn with_source(
+        batch: loro::event::DiffBatch,
+        doc: loro::LoroDoc,
+        from: loro::Frontiers,
+        to: loro::Frontiers,
+    ) -> Self {
+        Self(batch, Some(DiffSource { doc, from, to }))
+    }
+}
 
 impl fmt::Display for DiffBatch {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -491,6 +506,30 @@ impl DiffBatch {
             .map(|(cid, diff)| (cid.into(), diff.into()))
             .collect()
     }
+
+    /// Create a human-readable report of the changes, addressed by the paths
+    /// of the containers in the document.
+    ///
+    /// Text changes are also rendered as unified diffs, and map and list changes
+    /// as JSON patches (RFC 6902). The paths and the unified diffs are only
+    /// available if the batch was calculated by `LoroDoc.diff`; otherwise the
+    /// containers are addressed by their ids.
+    pub fn to_report(&self, py: Python<'_>) -> PyLoroResult<DiffReport> {
+        py.detach(|| report::report(&self.0, self.1.as_ref()))
+    }
+
+    /// Convert the changes to a JSON patch (RFC 6902), as a list of operations
+    /// that `LoroDoc.apply_json_patch` can apply.
+    ///
+    /// Maps and lists are patched, while texts, counters and trees are replaced
+    /// by their new values. The root containers that do not exist before the
+    /// changes are added empty first, so that the patch applies to a document
+    /// at that version. Only a batch calculated by `LoroDoc.diff` can be
+    /// converted, since the paths are resolved in the document.
+    pub fn to_json_patch(&self, py: Python<'_>) -> PyLoroResult<LoroValue> {
+        let patch = py.detach(|| report::json_patch(&self.0, self.1.as_ref()))?;
+        Ok(loro::LoroValue::from(patch).into())
+    }
 }
 
 impl From<DiffBatch> for loro::event::DiffBatch {
@@ -501,6 +540,6 @@ impl From<DiffBatch> for loro::event::DiffBatch {
 
 impl From<loro::event::DiffBatch> for DiffBatch {
     fn from(value: loro::event::DiffBatch) -> Self {
-        Self(value)
+        Self(value, None)
     }
 }
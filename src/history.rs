@@ -0,0 +1,409 @@
+//! Browsing the change history of a document with decoded ops.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use loro::{
+    IdSpan, JsonFutureOp, JsonListOp, JsonMapOp, JsonMovableListOp, JsonOpContent, JsonTextOp,
+    JsonTreeOp, Lamport, LoroDoc as LoroDocInner, PeerID, Timestamp,
+};
+use pyo3::prelude::*;
+
+use crate::{
+    doc::ChangeMeta,
+    err::VersionNotFoundError,
+    value::{ContainerID, IdLp, LoroValue, TreeID, ID},
+    version::{Frontiers, VersionVector},
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Op>()?;
+    m.add_class::<OpContent>()?;
+    m.add_class::<ChangeIter>()?;
+    Ok(())
+}
+
+/// A version given either as `Frontiers` or as a `VersionVector`.
+#[derive(FromPyObject)]
+pub enum Version {
+    Frontiers(Frontiers),
+    VersionVector(VersionVector),
+}
+
+impl Version {
+    fn into_frontiers(self, doc: &LoroDocInner) -> PyResult<loro::Frontiers> {
+        match self {
+            Version::Frontiers(f) => {
+                let f = loro::Frontiers::from(f);
+                if doc.frontiers_to_vv(&f).is_none() {
+                    return Err(VersionNotFoundError::new_err(format!(
+                        "Frontiers {f:?} are not included in the document"
+                    )));
+                }
+                Ok(f)
+            }
+            Version::VersionVector(vv) => {
+                let vv = loro::VersionVector::from(vv);
+                if !doc.oplog_vv().includes_vv(&vv) {
+                    return Err(VersionNotFoundError::new_err(format!(
+                        "VersionVector {vv:?} is not included in the document"
+                    )));
+                }
+                Ok(doc.vv_to_frontiers(&vv))
+            }
+        }
+    }
+}
+
+/// The filters accepted by `LoroDoc.changes()`.
+#[derive(Default)]
+pub struct ChangeFilter {
+    pub peers: Option<HashSet<PeerID>>,
+    pub start_time: Option<Timestamp>,
+    pub end_time: Option<Timestamp>,
+    pub containers: Option<HashSet<loro::ContainerID>>,
+}
+
+impl ChangeFilter {
+    fn accepts(&self, meta: &loro::ChangeMeta) -> bool {
+        self.peers
+            .as_ref()
+            .is_none_or(|peers| peers.contains(&meta.id.peer))
+            && self.start_time.is_none_or(|t| meta.timestamp >= t)
+            && self.end_time.is_none_or(|t| meta.timestamp < t)
+    }
+}
+
+/// Iterator over the changes between two versions, yielding `ChangeMeta`
+/// with their ops decoded.
+///
+/// The changes are sorted in causal order (by lamport, then by peer). The
+/// ops of a change are only decoded when the change is reached.
+#[pyclass]
+pub struct ChangeIter {
+    doc: LoroDocInner,
+    pending: std::vec::IntoIter<(loro::ChangeMeta, IdSpan)>,
+    containers: Option<HashSet<loro::ContainerID>>,
+}
+
+impl ChangeIter {
+    pub fn new(
+        doc: &LoroDocInner,
+        from: Option<Version>,
+        to: Option<Version>,
+        filter: ChangeFilter,
+    ) -> PyResult<Self> {
+        let from = match from {
+            Some(v) => v.into_frontiers(doc)?,
+            None => loro::Frontiers::default(),
+        };
+        let to = match to {
+            Some(v) => v.into_frontiers(doc)?,
+            None => doc.oplog_frontiers(),
+        };
+
+        let mut changes = Vec::new();
+        for (&peer, span) in doc.find_id_spans_between(&from, &to).forward.iter() {
+            let mut counter = span.start;
+            while counter < span.end {
+                let Some(meta) = doc.get_change(loro::ID::new(peer, counter)) else {
+                    break;
+                };
+                let start = counter;
+                counter = meta.id.counter + meta.len as i32;
+                let end = counter.min(span.end);
+                if !filter.accepts(&meta) {
+                    continue;
+                }
+                if let Some(containers) = &filter.containers {
+                    let changed = doc.get_changed_containers_in(
+                        loro::ID::new(peer, start),
+                        (end - start) as usize,
+                    );
+                    if !changed.iter().any(|c| containers.contains(c)) {
+                        continue;
+                    }
+                }
+                changes.push((meta, IdSpan::new(peer, start, end)));
+            }
+        }
+        changes.sort_by_key(|(meta, span)| {
+            (
+                meta.lamport + (span.counter.start - meta.id.counter) as Lamport,
+                span.peer,
+            )
+        });
+
+        Ok(Self {
+            doc: doc.clone(),
+            pending: changes.into_iter(),
+            containers: filter.containers,
+        })
+    }
+}
+
+#[pymethods]
+impl ChangeIter {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__(&mut self) -> Option<ChangeMeta> {
+        let (meta, span) = self.pending.next()?;
+        let offset = span.counter.start - meta.id.counter;
+        let mut ops = Vec::new();
+        for change in self.doc.export_json_in_id_span(span) {
+            for op in change.ops {
+                if self
+                    .containers
+                    .as_ref()
+                    .is_some_and(|c| !c.contains(&op.container))
+                {
+                    continue;
+                }
+                ops.push(Op {
+                    id: loro::ID::new(change.id.peer, op.counter).into(),
+                    lamport: change.lamport + (op.counter - change.id.counter) as Lamport,
+                    container: op.container.into(),
+                    content: op.content.into(),
+                });
+            }
+        }
+
+        let mut ans = ChangeMeta::from(meta);
+        ans.id = loro::ID::new(span.peer, span.counter.start).into();
+        ans.lamport += offset as Lamport;
+        ans.len = (span.counter.end - span.counter.start) as usize;
+        ans.ops = Some(ops);
+        Some(ans)
+    }
+
+    pub fn __length_hint__(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// A single operation of a change.
+#[pyclass(get_all, str)]
+#[derive(Debug, Clone)]
+pub struct Op {
+    /// The id of the op. Its peer is the author of the op.
+    pub id: ID,
+    /// The lamport timestamp of the op.
+    pub lamport: Lamport,
+    /// The container the op was applied to.
+    pub container: ContainerID,
+    /// What the op does.
+    pub content: OpContent,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The decoded content of an op.
+///
+/// `LoroList` and `LoroMovableList` share the `ListInsert` and `ListDelete`
+/// variants; the container id of the op tells them apart.
+#[pyclass(get_all, str)]
+#[derive(Debug, Clone)]
+pub enum OpContent {
+    ListInsert {
+        pos: u32,
+        values: Vec<LoroValue>,
+    },
+    ListDelete {
+        pos: i32,
+        len: i32,
+        start_id: ID,
+    },
+    MovableListMove {
+        from_: u32,
+        to: u32,
+        elem_id: IdLp,
+    },
+    MovableListSet {
+        elem_id: IdLp,
+        value: LoroValue,
+    },
+    MapInsert {
+        key: String,
+        value: LoroValue,
+    },
+    MapDelete {
+        key: String,
+    },
+    TextInsert {
+        pos: u32,
+        text: String,
+    },
+    TextDelete {
+        pos: i32,
+        len: i32,
+        start_id: ID,
+    },
+    TextMark {
+        start: u32,
+        end: u32,
+        key: String,
+        value: LoroValue,
+        info: u8,
+    },
+    TextMarkEnd {},
+    TreeCreate {
+        target: TreeID,
+        parent: Option<TreeID>,
+        fractional_index: String,
+    },
+    TreeMove {
+        target: TreeID,
+        parent: Option<TreeID>,
+        fractional_index: String,
+    },
+    TreeDelete {
+        target: TreeID,
+    },
+    CounterIncrement {
+        value: f64,
+    },
+    /// An op of a container type unknown to this version of Loro.
+    Unknown {
+        prop: i32,
+    },
+}
+
+impl Display for OpContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<JsonOpContent> for OpContent {
+    fn from(value: JsonOpContent) -> Self {
+        match value {
+            JsonOpContent::List(JsonListOp::Insert { pos, value })
+            | JsonOpContent::MovableList(JsonMovableListOp::Insert { pos, value }) => {
+                OpContent::ListInsert {
+                    pos,
+                    values: value.into_iter().map(LoroValue::from).collect(),
+                }
+            }
+            JsonOpContent::List(JsonListOp::Delete { pos, len, start_id })
+            | JsonOpContent::MovableList(JsonMovableListOp::Delete { pos, len, start_id }) => {
+                OpContent::ListDelete {
+                    pos,
+                    len,
+                    start_id: start_id.into(),
+                }
+            }
+            JsonOpContent::MovableList(JsonMovableListOp::Move { from, to, elem_id }) => {
+                OpContent::MovableListMove {
+                    from_: from,
+                    to,
+                    elem_id: elem_id.into(),
+                }
+            }
+            JsonOpContent::MovableList(JsonMovableListOp::Set { elem_id, value }) => {
+                OpContent::MovableListSet {
+                    elem_id: elem_id.into(),
+                    value: value.into(),
+                }
+            }
+            JsonOpContent::Map(JsonMapOp::Insert { key, value }) => OpContent::MapInsert {
+                key,
+                value: value.into(),
+            },
+            JsonOpContent::Map(JsonMapOp::Delete { key }) => OpContent::MapDelete { key },
+            JsonOpContent::Text(JsonTextOp::Insert { pos, text }) => {
+                OpContent::TextInsert { pos, text }
+            }
+            JsonOpContent::Text(JsonTextOp::Delete { pos, len, start_id }) => {
+                OpContent::TextDelete {
+                    pos,
+                    len,
+                    start_id: start_id.into(),
+                }
+            }
+            JsonOpContent::Text(JsonTextOp::Mark {
+                start,
+                end,
+                style_key,
+                style_value,
+                info,
+            }) => OpContent::TextMark {
+                start,
+                end,
+                key: style_key,
+                value: style_value.into(),
+                info,
+            },
+            JsonOpContent::Text(JsonTextOp::MarkEnd) => OpContent::TextMarkEnd {},
+            JsonOpContent::Tree(JsonTreeOp::Create {
+                target,
+                parent,
+                fractional_index,
+            }) => OpContent::TreeCreate {
+                target: target.into(),
+                parent: parent.map(TreeID::from),
+                fractional_index: fractional_index.to_string(),
+            },
+            JsonOpContent::Tree(JsonTreeOp::Move {
+                target,
+                parent,
+                fractional_index,
+            }) => OpContent::TreeMove {
+                target: target.into(),
+                parent: parent.map(TreeID::from),
+                fractional_index: fractional_index.to_string(),
+            },
+            JsonOpContent::Tree(JsonTreeOp::Delete { target }) => OpContent::TreeDelete {
+                target: target.into(),
+            },
+            JsonOpContent::Future(op) => match op.value {
+                // The counter value is only reachable through its serialized
+                // form: `{"type": "counter", "value_type": ..., "value": ...}`.
+                JsonFutureOp::Counter(_) => {
+                    let value = serde_json::to_value(&op.value)
+                        .ok()
+                        .and_then(|v| v.get("value").and_then(|v| v.as_f64()))
+                        .unwrap_or_default();
+                    OpContent::CounterIncrement { value }
+                }
+                JsonFutureOp::Unknown(_) => OpContent::Unknown { prop: op.prop },
+            },
+        }
+    }
+}
+
+/// The parent of every container created by `changes`: the container of the
+/// op that inserted it, or the tree of the node a metadata map belongs to.
+///
+/// Unlike `LoroDoc::get_path_to_container`, this also resolves the parents
+/// of the containers that have since been deleted.
+pub fn container_parents(
+    changes: &[loro::JsonChange],
+) -> HashMap<loro::ContainerID, loro::ContainerID> {
+    let mut parents = HashMap::new();
+    for op in changes.iter().flat_map(|change| &change.ops) {
+        let children: Vec<loro::ContainerID> = match &op.content {
+            JsonOpContent::Map(JsonMapOp::Insert { value, .. })
+            | JsonOpContent::MovableList(JsonMovableListOp::Set { value, .. }) => {
+                value.as_container().cloned().into_iter().collect()
+            }
+            JsonOpContent::List(JsonListOp::Insert { value, .. })
+            | JsonOpContent::MovableList(JsonMovableListOp::Insert { value, .. }) => value
+                .iter()
+                .filter_map(|value| value.as_container().cloned())
+                .collect(),
+            JsonOpContent::Tree(JsonTreeOp::Create { target, .. }) => {
+                vec![target.associated_meta_container()]
+            }
+            _ => continue,
+        };
+        for child in children {
+            parents.insert(child, op.container.clone());
+        }
+    }
+    parents
+}
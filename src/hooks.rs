@@ -0,0 +1,188 @@
+//! Hooks converting Python objects of other types to values, and back.
+//!
+//! `pyobject_to_loro_value` converts the built-in types itself. For an object
+//! of another type, the hook of the nearest class in its MRO is called, and
+//! the object it returns is converted instead. By default, `decimal.Decimal`
+//! objects are converted to strings, so that no digit is lost, and
+//! `datetime.datetime`, `datetime.date` and `datetime.time` objects to their
+//! ISO 8601 strings.
+//!
+//! The hook of an encoder wraps what it returns in a tagged map,
+//! `{"$type": tag, "$value": value}`, which `loro_value_to_pyobject` passes to
+//! the decoder registered for the tag, so that domain types round-trip
+//! through documents. A tagged map whose tag has no decoder is returned as
+//! a dict, so that peers without the decoder can still read it, and so is a
+//! tagged map whose decoder raises, after the exception is reported with
+//! `sys.unraisablehook`.
+use std::{collections::HashMap, sync::Mutex};
+
+use loro::LoroMapValue;
+use pyo3::{
+    exceptions::PyTypeError,
+    prelude::*,
+    types::{PyDict, PyString, PyType},
+};
+
+use crate::convert::is_builtin;
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(set_conversion_hook, m)?)?;
+    m.add_function(wrap_pyfunction!(register_encoder, m)?)?;
+    m.add_function(wrap_pyfunction!(register_decoder, m)?)?;
+
+    let py = m.py();
+    let decimal = py.import("decimal")?.getattr("Decimal")?;
+    let str = py.get_type::<PyString>();
+    set_conversion_hook(decimal.downcast_into()?, Some(str.into_any().unbind()));
+    let datetime = py.import("datetime")?;
+    for name in ["datetime", "date", "time"] {
+        let ty = datetime.getattr(name)?;
+        let isoformat = ty.getattr("isoformat")?.unbind();
+        set_conversion_hook(ty.downcast_into()?, Some(isoformat));
+    }
+    Ok(())
+}
+
+/// The key of the tag in a tagged map.
+pub const TYPE_KEY: &str = "$type";
+/// The key of the encoded value in a tagged map.
+pub const VALUE_KEY: &str = "$value";
+
+enum Hook {
+    /// Convert the object to another object.
+    Convert(Py<PyAny>),
+    /// Encode the object to a tagged map with the tag.
+    Encode(String, Py<PyAny>),
+}
+
+/// The hooks, by type.
+static HOOKS: Mutex<Vec<(Py<PyType>, Hook)>> = Mutex::new(Vec::new());
+
+/// The decoders of the tagged maps, by tag.
+static DECODERS: Mutex<Option<HashMap<String, Py<PyAny>>>> = Mutex::new(None);
+
+fn set_hook(ty: Bound<'_, PyType>, hook: Option<Hook>) {
+    let mut hooks = HOOKS.lock().unwrap();
+    hooks.retain(|(t, _)| !t.is(&ty));
+    if let Some(hook) = hook {
+        hooks.push((ty.unbind(), hook));
+    }
+}
+
+/// Convert the objects of type `ty`, and of its subclasses, with `hook` when
+/// they are stored in a document. `hook` takes the object and returns an
+/// object that can be stored, such as a string or a dict. If `hook` is `None`,
+/// the hook of `ty` is removed.
+#[pyfunction]
+#[pyo3(signature = (ty, hook))]
+pub fn set_conversion_hook(ty: Bound<'_, PyType>, hook: Option<Py<PyAny>>) {
+    set_hook(ty, hook.map(Hook::Convert));
+}
+
+/// Store the objects of type `ty`, and of its subclasses, as tagged maps
+/// `{"$type": tag, "$value": encoder(obj)}`, which the decoder registered for
+/// `tag` turns back into objects. The tag defaults to `module.qualname` of
+/// `ty`. The encoder replaces the conversion hook of `ty`, if any. If
+/// `encoder` is `None`, the encoder of `ty` is removed.
+#[pyfunction]
+#[pyo3(signature = (ty, encoder, tag=None))]
+pub fn register_encoder(
+    ty: Bound<'_, PyType>,
+    encoder: Option<Py<PyAny>>,
+    tag: Option<String>,
+) -> PyResult<()> {
+    let tag = match tag {
+        Some(tag) => tag,
+        None => format!("{}.{}", ty.module()?, ty.qualname()?),
+    };
+    set_hook(ty, encoder.map(|encoder| Hook::Encode(tag, encoder)));
+    Ok(())
+}
+
+/// Return `decoder(value)` instead of the tagged maps
+/// `{"$type": tag, "$value": value}` read from documents. If `decoder` raises,
+/// the exception is passed to `sys.unraisablehook` and the tagged map is
+/// returned as a dict. If `decoder` is `None`, the decoder of `tag` is removed.
+#[pyfunction]
+#[pyo3(signature = (tag, decoder))]
+pub fn register_decoder(tag: String, decoder: Option<Py<PyAny>>) {
+    let mut decoders = DECODERS.lock().unwrap();
+    let decoders = decoders.get_or_insert_with(HashMap::new);
+    match decoder {
+        Some(decoder) => decoders.insert(tag, decoder),
+        None => decoders.remove(&tag),
+    };
+}
+
+/// How many times in a row the hooks are applied to the objects they return,
+/// e.g. to a `Decimal` returned by a hook, before giving up.
+const MAX_DEPTH: usize = 32;
+
+/// Convert `obj` with the hook of the nearest class in its MRO, and the object
+/// it returns in turn, until it returns an object without a hook. A hook that
+/// keeps returning objects with hooks, such as `lambda o: o`, raises a
+/// `TypeError` instead of recursing forever.
+pub fn convert<'py>(obj: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let mut converted: Option<Bound<'py, PyAny>> = None;
+    for _ in 0..MAX_DEPTH {
+        let current = converted.as_ref().unwrap_or(obj);
+        if is_builtin(current) {
+            return Ok(converted);
+        }
+        match convert_once(current)? {
+            Some(next) => converted = Some(next),
+            None => return Ok(converted),
+        }
+    }
+    Err(PyTypeError::new_err(format!(
+        "the conversion hooks of {} returned objects with hooks {MAX_DEPTH} times in a row",
+        obj.get_type().name()?
+    )))
+}
+
+/// Convert `obj` with the hook of the nearest class in its MRO, if any.
+fn convert_once<'py>(obj: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let py = obj.py();
+    let hook = {
+        let hooks = HOOKS.lock().unwrap();
+        if hooks.is_empty() {
+            return Ok(None);
+        }
+        let mro = obj.get_type().mro();
+        mro.iter().find_map(|class| {
+            hooks
+                .iter()
+                .find(|(ty, _)| ty.is(&class))
+                .map(|(_, hook)| match hook {
+                    Hook::Convert(hook) => (None, hook.clone_ref(py)),
+                    Hook::Encode(tag, encoder) => (Some(tag.clone()), encoder.clone_ref(py)),
+                })
+        })
+    };
+    let Some((tag, hook)) = hook else {
+        return Ok(None);
+    };
+    let converted = hook.bind(py).call1((obj,))?;
+    let Some(tag) = tag else {
+        return Ok(Some(converted));
+    };
+    let tagged = PyDict::new(py);
+    tagged.set_item(TYPE_KEY, tag)?;
+    tagged.set_item(VALUE_KEY, converted)?;
+    Ok(Some(tagged.into_any()))
+}
+
+/// The decoder and the encoded value of `map`, if it is a tagged map whose
+/// tag has a decoder.
+pub fn decoder(py: Python<'_>, map: &LoroMapValue) -> Option<(Py<PyAny>, loro::LoroValue)> {
+    if map.len() != 2 {
+        return None;
+    }
+    let loro::LoroValue::String(tag) = map.get(TYPE_KEY)? else {
+        return None;
+    };
+    let value = map.get(VALUE_KEY)?;
+    let decoders = DECODERS.lock().unwrap();
+    let decoder = decoders.as_ref()?.get(tag.as_str())?.clone_ref(py);
+    Some((decoder, value.clone()))
+}
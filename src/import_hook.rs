@@ -0,0 +1,167 @@
+//! Importing updates through a hook that can reject them.
+//!
+//! The blob is first imported into a [Staging] copy of the history of the
+//! document, so that its changes can be decoded and handed to the hook before
+//! the document itself is mutated. Only the changes the hook accepts are then
+//! exported from the copy and imported into the document.
+use std::{collections::HashSet, sync::Mutex};
+
+use loro::{Counter, ExportMode, IdSpan, LoroDoc as LoroDocInner, PeerID};
+use pyo3::{prelude::*, types::PyBool};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    doc::{ChangeMeta, ImportBlobMetadata, ImportStatus},
+    err::{ImportRejectedError, PyLoroResult},
+    history::{ChangeFilter, ChangeIter, Version},
+    version::VersionRange,
+};
+
+/// What the hook decided for a blob.
+enum Verdict {
+    AcceptAll,
+    RejectAll,
+    RejectPeers(HashSet<PeerID>),
+}
+
+impl Verdict {
+    fn extract(ans: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if ans.is_none() {
+            return Ok(Verdict::AcceptAll);
+        }
+        if let Ok(accept) = ans.downcast::<PyBool>() {
+            return Ok(if accept.is_true() {
+                Verdict::AcceptAll
+            } else {
+                Verdict::RejectAll
+            });
+        }
+        let peers = ans
+            .try_iter()?
+            .map(|peer| peer?.extract())
+            .collect::<PyResult<HashSet<PeerID>>>()?;
+        Ok(Verdict::RejectPeers(peers))
+    }
+}
+
+/// A detached copy of the history of a document, which blobs are imported
+/// into before they are checked.
+///
+/// Copying the history is O(n) in the size of the document, so the copy is
+/// kept between imports, and only the changes the document got since then
+/// are imported into it. It is dropped when it gets changes the document does
+/// not have, that is when changes are rejected or pending.
+#[derive(Default)]
+pub struct Staging(Mutex<Option<LoroDocInner>>);
+
+impl Staging {
+    /// Take the copy, up to date with `doc`, or make a new one.
+    fn take(&self, doc: &LoroDocInner) -> PyLoroResult<LoroDocInner> {
+        let cached = self.0.lock().unwrap().take();
+        let vv = doc.oplog_vv();
+        if let Some(staging) = cached {
+            let staged = staging.oplog_vv();
+            if vv.includes_vv(&staged) {
+                if staged != vv {
+                    staging.import(&doc.export(ExportMode::updates(&staged))?)?;
+                }
+                return Ok(staging);
+            }
+        }
+        let staging = doc.fork();
+        // Only the history is needed: imports do not update the state of a
+        // detached document.
+        staging.detach();
+        Ok(staging)
+    }
+
+    fn keep(&self, staging: LoroDocInner) {
+        *self.0.lock().unwrap() = Some(staging);
+    }
+}
+
+pub fn import_checked(
+    py: Python<'_>,
+    doc: &LoroDocInner,
+    cache: &Staging,
+    bytes: &[u8],
+    check: &Bound<'_, PyAny>,
+    origin: Option<&str>,
+) -> PyLoroResult<ImportStatus> {
+    let meta = LoroDocInner::decode_import_blob_meta(bytes, true)?;
+    let base = doc.oplog_vv();
+    let (staging, staged) = py.detach(|| -> PyLoroResult<_> {
+        let staging = cache.take(doc)?;
+        let status = staging.import(bytes)?;
+        Ok((staging, status))
+    })?;
+
+    let mut iter = ChangeIter::new(
+        &staging,
+        Some(Version::VersionVector(base.clone().into())),
+        None,
+        ChangeFilter::default(),
+    )?;
+    let mut changes: Vec<ChangeMeta> = Vec::new();
+    while let Some(change) = iter.__next__() {
+        changes.push(change);
+    }
+
+    let verdict = check.call1((ImportBlobMetadata::from(meta), changes.clone()))?;
+    let rejected_peers = match Verdict::extract(&verdict)? {
+        Verdict::AcceptAll => HashSet::new(),
+        Verdict::RejectAll => {
+            return Err(ImportRejectedError::new_err("the import was rejected by the hook").into())
+        }
+        Verdict::RejectPeers(peers) => peers,
+    };
+
+    // The first rejected counter of every peer. The changes of a peer depend
+    // on its previous changes, so everything after it is rejected too, as are
+    // the changes depending on a rejected change.
+    let mut cut: FxHashMap<PeerID, Counter> = FxHashMap::default();
+    for change in &changes {
+        let id = loro::ID::from(change.id);
+        let deps = loro::Frontiers::from(&change.deps);
+        let is_cut = |id: loro::ID| cut.get(&id.peer).is_some_and(|&c| id.counter >= c);
+        if rejected_peers.contains(&id.peer) || is_cut(id) || deps.iter().any(is_cut) {
+            cut.entry(id.peer).or_insert(id.counter);
+        }
+    }
+
+    let mut spans = Vec::new();
+    let mut rejected = None;
+    for (&peer, &end) in staging.oplog_vv().iter() {
+        let start = base.get(&peer).copied().unwrap_or(0);
+        let accepted_end = cut.get(&peer).copied().unwrap_or(end);
+        if accepted_end > start {
+            spans.push(IdSpan::new(peer, start, accepted_end));
+        }
+        if end > accepted_end {
+            rejected
+                .get_or_insert_with(loro::VersionRange::new)
+                .insert(peer, accepted_end, end);
+        }
+    }
+
+    let success = if spans.is_empty() {
+        loro::VersionRange::new()
+    } else {
+        py.detach(|| -> PyLoroResult<_> {
+            let updates = staging.export(ExportMode::updates_in_range(spans))?;
+            let status = match origin {
+                Some(origin) => doc.import_with(&updates, origin)?,
+                None => doc.import(&updates)?,
+            };
+            Ok(status.success)
+        })?
+    };
+    if rejected.is_none() && staged.pending.is_none() {
+        cache.keep(staging);
+    }
+    Ok(ImportStatus {
+        success: success.into(),
+        pending: staged.pending.map(VersionRange::from),
+        rejected: rejected.map(VersionRange::from),
+    })
+}
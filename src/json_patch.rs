@@ -0,0 +1,782 @@
+//! JSON Patch (RFC 6902) support for `LoroDoc.apply_json_patch`.
+//!
+//! A patch is first applied to the structure of the document, which checks
+//! every operation, including the `test` ones, without touching the document.
+//! It is then applied to the containers in a single commit, so that a patch is
+//! either applied entirely or not at all.
+//!
+//! Objects and arrays added by a patch become maps and lists, so that later
+//! patches can address their content. A string replacing a text updates it in
+//! place, and a move inside a movable list uses `mov`. A pointer with a single
+//! token addresses a root container, which can be replaced by a value of the
+//! same kind but not removed.
+use loro::{
+    Container, ContainerID, ContainerType, LoroDoc, LoroList, LoroListValue, LoroMap, LoroMapValue,
+    LoroResult, LoroValue, ToJson, ValueOrContainer,
+};
+use rustc_hash::FxHashMap;
+
+use crate::err::{JsonPatchError, PyLoroError, PyLoroResult};
+
+type PatchResult<T> = Result<T, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Add,
+    Remove,
+    Replace,
+    Move,
+    Copy,
+    Test,
+}
+
+struct Operation {
+    kind: Kind,
+    path: Vec<String>,
+    from: Vec<String>,
+    value: LoroValue,
+}
+
+/// Apply the JSON patch `patch` to `doc`, and commit it.
+pub fn apply_json_patch(doc: &LoroDoc, patch: &LoroValue) -> PyLoroResult<()> {
+    let ops = parse(patch).map_err(patch_error)?;
+    apply(&mut DryRun::new(doc), &ops)?;
+    let ans = apply(&mut DocTarget { doc }, &ops);
+    doc.commit();
+    ans
+}
+
+fn patch_error(msg: String) -> PyLoroError {
+    JsonPatchError::new_err(msg).into()
+}
+
+fn apply<T: Target>(target: &mut T, ops: &[Operation]) -> PyLoroResult<()> {
+    for (i, op) in ops.iter().enumerate() {
+        apply_op(target, op).map_err(|msg| patch_error(format!("Operation {i}: {msg}")))?;
+    }
+    Ok(())
+}
+
+fn parse_pointer(pointer: &str) -> PatchResult<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(tokens) = pointer.strip_prefix('/') else {
+        return Err(format!("Invalid JSON pointer {pointer:?}"));
+    };
+    Ok(tokens
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn parse(patch: &LoroValue) -> PatchResult<Vec<Operation>> {
+    let LoroValue::List(ops) = patch else {
+        return Err("A JSON patch must be a list of operations".to_string());
+    };
+    let mut ans = Vec::with_capacity(ops.len());
+    for (i, op) in ops.iter().enumerate() {
+        let LoroValue::Map(op) = op else {
+            return Err(format!("Operation {i}: an operation must be an object"));
+        };
+        let string = |name: &str| match op.get(name) {
+            Some(LoroValue::String(s)) => Ok(s.to_string()),
+            _ => Err(format!("Operation {i}: missing string member {name:?}")),
+        };
+        let kind = match string("op")?.as_str() {
+            "add" => Kind::Add,
+            "remove" => Kind::Remove,
+            "replace" => Kind::Replace,
+            "move" => Kind::Move,
+            "copy" => Kind::Copy,
+            "test" => Kind::Test,
+            other => return Err(format!("Operation {i}: unknown operation {other:?}")),
+        };
+        let path = parse_pointer(&string("path")?).map_err(|e| format!("Operation {i}: {e}"))?;
+        let from = match kind {
+            Kind::Move | Kind::Copy => {
+                parse_pointer(&string("from")?).map_err(|e| format!("Operation {i}: {e}"))?
+            }
+            _ => Vec::new(),
+        };
+        let value = match kind {
+            Kind::Add | Kind::Replace | Kind::Test => op
+                .get("value")
+                .cloned()
+                .ok_or_else(|| format!("Operation {i}: missing member \"value\""))?,
+            _ => LoroValue::Null,
+        };
+        if kind != Kind::Test && contains_container(&value) {
+            return Err(format!(
+                "Operation {i}: a container id cannot be added, only JSON values"
+            ));
+        }
+        ans.push(Operation {
+            kind,
+            path,
+            from,
+            value,
+        });
+    }
+    Ok(ans)
+}
+
+/// Whether `value` is or includes a container id, which the document would
+/// reject once the patch is partly applied.
+fn contains_container(value: &LoroValue) -> bool {
+    match value {
+        LoroValue::Container(_) => true,
+        LoroValue::List(items) => items.iter().any(contains_container),
+        LoroValue::Map(entries) => entries.values().any(contains_container),
+        _ => false,
+    }
+}
+
+/// The index of `token` in a list of `len` items. When adding, the index can
+/// be `len`, which `-` stands for.
+fn index(token: &str, len: usize, adding: bool) -> PatchResult<usize> {
+    if token == "-" && adding {
+        return Ok(len);
+    }
+    let canonical = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(i) if canonical && (i < len || adding && i == len) => Ok(i),
+        _ => Err(format!("invalid index {token:?} in a list of {len} items")),
+    }
+}
+
+fn pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Whether two JSON values are equal, with integers equal to the same floats.
+pub fn json_eq(a: &LoroValue, b: &LoroValue) -> bool {
+    match (a, b) {
+        (LoroValue::I64(a), LoroValue::Double(b)) | (LoroValue::Double(b), LoroValue::I64(a)) => {
+            *a as f64 == *b
+        }
+        (LoroValue::List(a), LoroValue::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| json_eq(a, b))
+        }
+        (LoroValue::Map(a), LoroValue::Map(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| json_eq(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// Where a patch is applied.
+trait Target {
+    /// The value at `path`.
+    fn value(&self, path: &[String]) -> PatchResult<LoroValue>;
+    fn add(&mut self, path: &[String], value: LoroValue) -> PatchResult<()>;
+    fn remove(&mut self, path: &[String]) -> PatchResult<()>;
+    fn replace(&mut self, path: &[String], value: LoroValue) -> PatchResult<()>;
+    /// Move an item inside a movable list. Returns false if `from` and `path`
+    /// are not in the same movable list.
+    fn mov(&mut self, from: &[String], path: &[String]) -> PatchResult<bool>;
+}
+
+fn apply_op<T: Target>(target: &mut T, op: &Operation) -> PatchResult<()> {
+    if op.kind != Kind::Test && op.path.is_empty() {
+        return Err("the document itself cannot be replaced".to_string());
+    }
+    match op.kind {
+        Kind::Add => target.add(&op.path, op.value.clone()),
+        Kind::Remove if op.path.len() == 1 => Err("a root container cannot be removed".to_string()),
+        Kind::Remove => target.remove(&op.path),
+        Kind::Replace => target.replace(&op.path, op.value.clone()),
+        Kind::Move if op.from.len() <= 1 => Err("a root container cannot be moved".to_string()),
+        Kind::Move if op.from == op.path => target.value(&op.from).map(drop),
+        Kind::Move if op.path.starts_with(&op.from) => {
+            Err("a value cannot be moved into itself".to_string())
+        }
+        Kind::Move => {
+            if !target.mov(&op.from, &op.path)? {
+                let value = target.value(&op.from)?;
+                target.remove(&op.from)?;
+                target.add(&op.path, value)?;
+            }
+            Ok(())
+        }
+        Kind::Copy => {
+            let value = target.value(&op.from)?;
+            target.add(&op.path, value)
+        }
+        Kind::Test => {
+            let value = target.value(&op.path)?;
+            if json_eq(&value, &op.value) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "test failed, the value at {:?} is {}",
+                    pointer(&op.path),
+                    value.to_json()
+                ))
+            }
+        }
+    }
+}
+
+/// The structure of a document, to check a patch before applying it.
+#[derive(Debug, Clone)]
+enum Node {
+    Value(LoroValue),
+    Text(String),
+    Map(FxHashMap<String, Node>),
+    List {
+        items: Vec<Node>,
+        movable: bool,
+    },
+    /// A tree or a counter, which a patch cannot address the content of.
+    Opaque(LoroValue),
+}
+
+impl Node {
+    fn from_container(container: Container) -> Self {
+        match container {
+            Container::Map(map) => {
+                let mut entries = FxHashMap::default();
+                map.for_each(|key, value| {
+                    entries.insert(key.to_string(), Node::from_item(value));
+                });
+                Node::Map(entries)
+            }
+            Container::List(list) => {
+                let mut items = Vec::new();
+                list.for_each(|value| items.push(Node::from_item(value)));
+                Node::List {
+                    items,
+                    movable: false,
+                }
+            }
+            Container::MovableList(list) => {
+                let mut items = Vec::new();
+                list.for_each(|value| items.push(Node::from_item(value)));
+                Node::List {
+                    items,
+                    movable: true,
+                }
+            }
+            Container::Text(text) => Node::Text(text.to_string()),
+            container => Node::Opaque(ValueOrContainer::Container(container).get_deep_value()),
+        }
+    }
+
+    fn from_item(item: ValueOrContainer) -> Self {
+        match item {
+            ValueOrContainer::Value(value) => Node::Value(value),
+            ValueOrContainer::Container(container) => Node::from_container(container),
+        }
+    }
+
+    /// The node of a value added by a patch.
+    fn from_value(value: LoroValue) -> Self {
+        match value {
+            LoroValue::Map(entries) => Node::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Node::from_value(value.clone())))
+                    .collect(),
+            ),
+            LoroValue::List(items) => Node::List {
+                items: items.iter().cloned().map(Node::from_value).collect(),
+                movable: false,
+            },
+            value => Node::Value(value),
+        }
+    }
+
+    /// The node of `value` replacing `self`.
+    fn replaced_by(&self, value: LoroValue) -> Self {
+        match (self, value) {
+            (Node::Text(_), LoroValue::String(s)) => Node::Text(s.to_string()),
+            (_, value) => Node::from_value(value),
+        }
+    }
+
+    fn to_value(&self) -> LoroValue {
+        match self {
+            Node::Value(value) | Node::Opaque(value) => value.clone(),
+            Node::Text(text) => text.as_str().into(),
+            Node::Map(entries) => LoroValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, node)| (key.clone(), node.to_value()))
+                    .collect::<FxHashMap<_, _>>()
+                    .into(),
+            ),
+            Node::List { items, .. } => {
+                LoroValue::List(items.iter().map(Node::to_value).collect::<Vec<_>>().into())
+            }
+        }
+    }
+
+    fn child(&self, token: &str) -> PatchResult<&Node> {
+        match self {
+            Node::Map(entries) => entries
+                .get(token)
+                .ok_or_else(|| format!("no key {token:?}")),
+            Node::List { items, .. } => Ok(&items[index(token, items.len(), false)?]),
+            _ => Err(format!(
+                "cannot address {token:?} inside of a {}",
+                self.kind()
+            )),
+        }
+    }
+
+    fn child_mut(&mut self, token: &str) -> PatchResult<&mut Node> {
+        match self {
+            Node::Map(entries) => entries
+                .get_mut(token)
+                .ok_or_else(|| format!("no key {token:?}")),
+            Node::List { items, .. } => {
+                let i = index(token, items.len(), false)?;
+                Ok(&mut items[i])
+            }
+            _ => Err(format!(
+                "cannot address {token:?} inside of a {}",
+                self.kind()
+            )),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Node::Value(_) => "value",
+            Node::Text(_) => "text",
+            Node::Map(_) => "map",
+            Node::List { .. } => "list",
+            Node::Opaque(_) => "tree or counter",
+        }
+    }
+}
+
+struct DryRun {
+    roots: FxHashMap<String, Node>,
+}
+
+impl DryRun {
+    fn new(doc: &LoroDoc) -> Self {
+        let roots = root_containers(doc)
+            .into_iter()
+            .map(|(name, container)| (name, Node::from_container(container)))
+            .collect();
+        Self { roots }
+    }
+
+    fn node(&self, path: &[String]) -> PatchResult<&Node> {
+        let mut node = self
+            .roots
+            .get(&path[0])
+            .ok_or_else(|| format!("no root container {:?}", path[0]))?;
+        for token in &path[1..] {
+            node = node.child(token)?;
+        }
+        Ok(node)
+    }
+
+    fn parent_mut(&mut self, path: &[String]) -> PatchResult<&mut Node> {
+        let mut node = self
+            .roots
+            .get_mut(&path[0])
+            .ok_or_else(|| format!("no root container {:?}", path[0]))?;
+        for token in &path[1..path.len() - 1] {
+            node = node.child_mut(token)?;
+        }
+        Ok(node)
+    }
+
+    fn assign_root(&mut self, name: &str, value: LoroValue) -> PatchResult<()> {
+        let node = match (self.roots.get(name), value) {
+            (None | Some(Node::Map(_)), value @ LoroValue::Map(_)) => Node::from_value(value),
+            (None, value @ LoroValue::List(_)) => Node::from_value(value),
+            (Some(Node::List { movable, .. }), LoroValue::List(items)) => Node::List {
+                items: items.iter().cloned().map(Node::from_value).collect(),
+                movable: *movable,
+            },
+            (None | Some(Node::Text(_)), LoroValue::String(s)) => Node::Text(s.to_string()),
+            (root, value) => {
+                return Err(format!(
+                    "the root {} {name:?} cannot be replaced by {}",
+                    root.map_or("container", Node::kind),
+                    value.to_json()
+                ))
+            }
+        };
+        self.roots.insert(name.to_string(), node);
+        Ok(())
+    }
+}
+
+impl Target for DryRun {
+    fn value(&self, path: &[String]) -> PatchResult<LoroValue> {
+        if path.is_empty() {
+            return Ok(LoroValue::Map(
+                self.roots
+                    .iter()
+                    .map(|(name, node)| (name.clone(), node.to_value()))
+                    .collect::<FxHashMap<_, _>>()
+                    .into(),
+            ));
+        }
+        Ok(self.node(path)?.to_value())
+    }
+
+    fn add(&mut self, path: &[String], value: LoroValue) -> PatchResult<()> {
+        if path.len() == 1 {
+            return self.assign_root(&path[0], value);
+        }
+        let token = path.last().unwrap();
+        match self.parent_mut(path)? {
+            Node::Map(entries) => {
+                entries.insert(token.clone(), Node::from_value(value));
+            }
+            Node::List { items, .. } => {
+                let i = index(token, items.len(), true)?;
+                items.insert(i, Node::from_value(value));
+            }
+            node => return Err(format!("cannot add {token:?} to a {}", node.kind())),
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &[String]) -> PatchResult<()> {
+        let token = path.last().unwrap();
+        match self.parent_mut(path)? {
+            Node::Map(entries) => {
+                entries
+                    .remove(token)
+                    .ok_or_else(|| format!("no key {token:?}"))?;
+            }
+            Node::List { items, .. } => {
+                let i = index(token, items.len(), false)?;
+                items.remove(i);
+            }
+            node => return Err(format!("cannot remove {token:?} from a {}", node.kind())),
+        }
+        Ok(())
+    }
+
+    fn replace(&mut self, path: &[String], value: LoroValue) -> PatchResult<()> {
+        if path.len() == 1 {
+            if !self.roots.contains_key(&path[0]) {
+                return Err(format!("no root container {:?}", path[0]));
+            }
+            return self.assign_root(&path[0], value);
+        }
+        let node = self.parent_mut(path)?.child_mut(path.last().unwrap())?;
+        *node = node.replaced_by(value);
+        Ok(())
+    }
+
+    fn mov(&mut self, from: &[String], path: &[String]) -> PatchResult<bool> {
+        if from.len() != path.len() || from[..from.len() - 1] != path[..path.len() - 1] {
+            return Ok(false);
+        }
+        let Node::List {
+            items,
+            movable: true,
+        } = self.parent_mut(path)?
+        else {
+            return Ok(false);
+        };
+        let from = index(from.last().unwrap(), items.len(), false)?;
+        let to = index(path.last().unwrap(), items.len() - 1, true)?;
+        let item = items.remove(from);
+        items.insert(to, item);
+        Ok(true)
+    }
+}
+
+/// The root containers of `doc`, by name.
+fn root_containers(doc: &LoroDoc) -> Vec<(String, Container)> {
+    let LoroValue::Map(roots) = doc.get_value() else {
+        unreachable!("the value of a document is a map")
+    };
+    roots
+        .iter()
+        .filter_map(|(name, value)| match value {
+            LoroValue::Container(id) => Some((name.clone(), container(doc, id.clone())?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The handler of the container `id` of `doc`.
+pub fn container(doc: &LoroDoc, id: ContainerID) -> Option<Container> {
+    Some(match id.container_type() {
+        ContainerType::Map => Container::Map(doc.get_map(id)),
+        ContainerType::List => Container::List(doc.get_list(id)),
+        ContainerType::MovableList => Container::MovableList(doc.get_movable_list(id)),
+        ContainerType::Text => Container::Text(doc.get_text(id)),
+        ContainerType::Tree => Container::Tree(doc.get_tree(id)),
+        ContainerType::Counter => Container::Counter(doc.get_counter(id)),
+        ContainerType::Unknown(_) => return None,
+    })
+}
+
+/// Insert `value` with one of the functions, turning objects and arrays into
+/// maps and lists.
+fn insert_value(
+    value: LoroValue,
+    insert: impl FnOnce(LoroValue) -> LoroResult<()>,
+    insert_map: impl FnOnce(LoroMap) -> LoroResult<LoroMap>,
+    insert_list: impl FnOnce(LoroList) -> LoroResult<LoroList>,
+) -> LoroResult<()> {
+    match value {
+        LoroValue::Map(entries) => fill_map(&insert_map(LoroMap::new())?, entries),
+        LoroValue::List(items) => fill_list(&insert_list(LoroList::new())?, items),
+        value => insert(value),
+    }
+}
+
+fn fill_map(map: &LoroMap, entries: LoroMapValue) -> LoroResult<()> {
+    for (key, value) in entries.iter() {
+        insert_value(
+            value.clone(),
+            |v| map.insert(key, v),
+            |c| map.insert_container(key, c),
+            |c| map.insert_container(key, c),
+        )?;
+    }
+    Ok(())
+}
+
+fn fill_list(list: &LoroList, items: LoroListValue) -> LoroResult<()> {
+    for value in items.iter() {
+        insert_value(
+            value.clone(),
+            |v| list.push(v),
+            |c| list.push_container(c),
+            |c| list.push_container(c),
+        )?;
+    }
+    Ok(())
+}
+
+struct DocTarget<'a> {
+    doc: &'a LoroDoc,
+}
+
+impl DocTarget<'_> {
+    /// Resolve `path` with `get_by_str_path`. It reads tokens made of digits as
+    /// list indexes and cannot express keys containing '/', so the containers
+    /// are walked one by one when it fails.
+    fn resolve(&self, path: &[String]) -> PatchResult<ValueOrContainer> {
+        if !path.iter().any(|token| token.contains('/')) {
+            if let Some(found) = self.doc.get_by_str_path(&path.join("/")) {
+                return Ok(found);
+            }
+        }
+        let not_found = || format!("no value at {:?}", pointer(path));
+        let mut current = root_containers(self.doc)
+            .into_iter()
+            .find(|(name, _)| *name == path[0])
+            .map(|(_, root)| ValueOrContainer::Container(root))
+            .ok_or_else(not_found)?;
+        for token in &path[1..] {
+            current = match current {
+                ValueOrContainer::Container(Container::Map(map)) => map.get(token),
+                ValueOrContainer::Container(Container::List(list)) => {
+                    token.parse().ok().and_then(|i| list.get(i))
+                }
+                ValueOrContainer::Container(Container::MovableList(list)) => {
+                    token.parse().ok().and_then(|i| list.get(i))
+                }
+                _ => None,
+            }
+            .ok_or_else(not_found)?;
+        }
+        Ok(current)
+    }
+
+    fn parent(&self, path: &[String]) -> PatchResult<Container> {
+        match self.resolve(&path[..path.len() - 1])? {
+            ValueOrContainer::Container(container) => Ok(container),
+            ValueOrContainer::Value(_) => Err(format!(
+                "cannot address inside of the value at {:?}",
+                pointer(&path[..path.len() - 1])
+            )),
+        }
+    }
+
+    fn assign_root(&mut self, name: &str, value: LoroValue) -> PatchResult<()> {
+        let root = root_containers(self.doc)
+            .into_iter()
+            .find(|(root, _)| root == name)
+            .map(|(_, root)| root);
+        let ans = match (root, value) {
+            (Some(Container::MovableList(list)), LoroValue::List(items)) => {
+                list.clear().and_then(|_| {
+                    items.iter().try_for_each(|value| {
+                        insert_value(
+                            value.clone(),
+                            |v| list.push(v),
+                            |c| list.push_container(c),
+                            |c| list.push_container(c),
+                        )
+                    })
+                })
+            }
+            (_, LoroValue::Map(entries)) => {
+                let map = self.doc.get_map(name);
+                map.clear().and_then(|_| fill_map(&map, entries))
+            }
+            (_, LoroValue::List(items)) => {
+                let list = self.doc.get_list(name);
+                list.clear().and_then(|_| fill_list(&list, items))
+            }
+            (_, LoroValue::String(s)) => {
+                return update_text(&self.doc.get_text(name), &s);
+            }
+            (_, value) => {
+                return Err(format!(
+                    "the root container {name:?} cannot be replaced by {}",
+                    value.to_json()
+                ))
+            }
+        };
+        ans.map_err(|e| e.to_string())
+    }
+}
+
+fn update_text(text: &loro::LoroText, s: &str) -> PatchResult<()> {
+    text.update(s, Default::default())
+        .map_err(|e| format!("{e:?}"))
+}
+
+/// The text at `item`, if `value` replaces it.
+fn replaced_text(
+    item: Option<ValueOrContainer>,
+    value: &LoroValue,
+) -> Option<(loro::LoroText, String)> {
+    match (item, value) {
+        (Some(ValueOrContainer::Container(Container::Text(text))), LoroValue::String(s)) => {
+            Some((text, s.to_string()))
+        }
+        _ => None,
+    }
+}
+
+impl Target for DocTarget<'_> {
+    fn value(&self, path: &[String]) -> PatchResult<LoroValue> {
+        if path.is_empty() {
+            return Ok(self.doc.get_deep_value());
+        }
+        Ok(self.resolve(path)?.get_deep_value())
+    }
+
+    fn add(&mut self, path: &[String], value: LoroValue) -> PatchResult<()> {
+        if path.len() == 1 {
+            return self.assign_root(&path[0], value);
+        }
+        let token = path.last().unwrap().as_str();
+        match self.parent(path)? {
+            Container::Map(map) => insert_value(
+                value,
+                |v| map.insert(token, v),
+                |c| map.insert_container(token, c),
+                |c| map.insert_container(token, c),
+            ),
+            Container::List(list) => {
+                let i = index(token, list.len(), true)?;
+                insert_value(
+                    value,
+                    |v| list.insert(i, v),
+                    |c| list.insert_container(i, c),
+                    |c| list.insert_container(i, c),
+                )
+            }
+            Container::MovableList(list) => {
+                let i = index(token, list.len(), true)?;
+                insert_value(
+                    value,
+                    |v| list.insert(i, v),
+                    |c| list.insert_container(i, c),
+                    |c| list.insert_container(i, c),
+                )
+            }
+            _ => return Err(format!("cannot add {token:?} to this container")),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn remove(&mut self, path: &[String]) -> PatchResult<()> {
+        let token = path.last().unwrap().as_str();
+        match self.parent(path)? {
+            Container::Map(map) => map.delete(token),
+            Container::List(list) => list.delete(index(token, list.len(), false)?, 1),
+            Container::MovableList(list) => list.delete(index(token, list.len(), false)?, 1),
+            _ => return Err(format!("cannot remove {token:?} from this container")),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn replace(&mut self, path: &[String], value: LoroValue) -> PatchResult<()> {
+        if path.len() == 1 {
+            return self.assign_root(&path[0], value);
+        }
+        let token = path.last().unwrap().as_str();
+        match self.parent(path)? {
+            Container::Map(map) => {
+                if let Some((text, s)) = replaced_text(map.get(token), &value) {
+                    return update_text(&text, &s);
+                }
+                insert_value(
+                    value,
+                    |v| map.insert(token, v),
+                    |c| map.insert_container(token, c),
+                    |c| map.insert_container(token, c),
+                )
+            }
+            Container::List(list) => {
+                let i = index(token, list.len(), false)?;
+                if let Some((text, s)) = replaced_text(list.get(i), &value) {
+                    return update_text(&text, &s);
+                }
+                list.delete(i, 1).and_then(|_| {
+                    insert_value(
+                        value,
+                        |v| list.insert(i, v),
+                        |c| list.insert_container(i, c),
+                        |c| list.insert_container(i, c),
+                    )
+                })
+            }
+            Container::MovableList(list) => {
+                let i = index(token, list.len(), false)?;
+                if let Some((text, s)) = replaced_text(list.get(i), &value) {
+                    return update_text(&text, &s);
+                }
+                insert_value(
+                    value,
+                    |v| list.set(i, v),
+                    |c| list.set_container(i, c),
+                    |c| list.set_container(i, c),
+                )
+            }
+            _ => return Err(format!("cannot replace {token:?} in this container")),
+        }
+        .map_err(|e| e.to_string())
+    }
+
+    fn mov(&mut self, from: &[String], path: &[String]) -> PatchResult<bool> {
+        if from.len() != path.len() || from[..from.len() - 1] != path[..path.len() - 1] {
+            return Ok(false);
+        }
+        let Container::MovableList(list) = self.parent(path)? else {
+            return Ok(false);
+        };
+        let from = index(from.last().unwrap(), list.len(), false)?;
+        let to = index(path.last().unwrap(), list.len() - 1, true)?;
+        list.mov(from, to).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,383 @@
+//! Lazy proxies for large list and map values.
+//!
+//! Values are immutable and share their content, so a proxy holds the value
+//! itself and converts an item only when it is accessed. Lists and maps are
+//! returned as proxies when they have at least [set_lazy_threshold] items,
+//! and the items they return follow the same rule. Proxies compare equal to
+//! the lists and dicts with the same content, are hashable, and convert back
+//! to values without copying.
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use loro::{LoroListValue, LoroMapValue, LoroValue, ToJson};
+use pyo3::{
+    exceptions::PyKeyError,
+    prelude::*,
+    types::{PyDict, PyList},
+    IntoPyObjectExt,
+};
+
+use crate::{
+    container::utils::{normalize_index, SliceOrInt},
+    convert::{loro_value_to_pyobject, loro_value_to_pyobject_eager, pyobject_to_loro_value},
+    json_patch::json_eq,
+    value::LoroValue as PyLoroValue,
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<LoroValueList>()?;
+    m.add_class::<LoroValueListIter>()?;
+    m.add_class::<LoroValueMap>()?;
+    m.add_class::<LoroValueMapView>()?;
+    m.add_class::<LoroValueMapIter>()?;
+    m.add_function(wrap_pyfunction!(set_lazy_threshold, m)?)?;
+
+    let abc = m.py().import("collections.abc")?;
+    abc.getattr("Sequence")?
+        .call_method1("register", (m.py().get_type::<LoroValueList>(),))?;
+    abc.getattr("Mapping")?
+        .call_method1("register", (m.py().get_type::<LoroValueMap>(),))?;
+    Ok(())
+}
+
+/// The number of items from which lists and maps are returned as proxies.
+static LAZY_THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Return the lists and maps with at least `threshold` items as
+/// `LoroValueList` and `LoroValueMap` proxies, or always convert them to
+/// lists and dicts if `threshold` is `None`, which is the default.
+#[pyfunction]
+#[pyo3(signature = (threshold=None))]
+pub fn set_lazy_threshold(threshold: Option<usize>) {
+    LAZY_THRESHOLD.store(threshold.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// Whether a list or a map of `len` items is returned as a proxy.
+pub fn is_lazy(len: usize) -> bool {
+    len >= LAZY_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Hash `value` consistently with [json_eq]: integral floats hash like
+/// integers, and maps hash regardless of the order of their entries.
+fn hash_value<H: Hasher>(value: &LoroValue, state: &mut H) {
+    match value {
+        LoroValue::Double(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => {
+            hash_value(&LoroValue::I64(*f as i64), state)
+        }
+        LoroValue::List(items) => {
+            std::mem::discriminant(value).hash(state);
+            state.write_usize(items.len());
+            for item in items.iter() {
+                hash_value(item, state);
+            }
+        }
+        LoroValue::Map(entries) => {
+            std::mem::discriminant(value).hash(state);
+            state.write_usize(entries.len());
+            let mut sum = 0u64;
+            for (key, item) in entries.iter() {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+                hash_value(item, &mut hasher);
+                sum = sum.wrapping_add(hasher.finish());
+            }
+            state.write_u64(sum);
+        }
+        value => value.hash(state),
+    }
+}
+
+fn hash(value: &LoroValue) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_value(value, &mut hasher);
+    hasher.finish()
+}
+
+/// Compare `value` with `other`, or return `NotImplemented` if `other` is not
+/// a list, a dict or a proxy. Tuples and other sequences and mappings are not
+/// equal to lists and dicts, and hash differently.
+fn eq(py: Python<'_>, value: &LoroValue, other: &Bound<'_, PyAny>) -> Py<PyAny> {
+    let comparable = other.downcast::<PyList>().is_ok()
+        || other.downcast::<PyDict>().is_ok()
+        || other.downcast::<LoroValueList>().is_ok()
+        || other.downcast::<LoroValueMap>().is_ok();
+    if !comparable {
+        return py.NotImplemented();
+    }
+    match pyobject_to_loro_value(other) {
+        Ok(other) => json_eq(value, &other).into_py_any(py).unwrap(),
+        Err(_) => py.NotImplemented(),
+    }
+}
+
+/// A read-only proxy of a list value, whose items are converted when they
+/// are accessed.
+#[pyclass(frozen, sequence)]
+#[derive(Debug, Clone)]
+pub struct LoroValueList(pub LoroListValue);
+
+#[pymethods]
+impl LoroValueList {
+    pub fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn __getitem__<'py>(
+        &self,
+        py: Python<'py>,
+        index: SliceOrInt<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match index {
+            SliceOrInt::Slice(slice) => {
+                let indices = slice.indices(self.0.len() as isize)?;
+                let items: Vec<_> = (0..indices.slicelength)
+                    .map(|i| self.0[(indices.start + i as isize * indices.step) as usize].clone())
+                    .collect();
+                loro_value_to_pyobject(py, PyLoroValue(LoroValue::List(items.into())))
+            }
+            SliceOrInt::Int(idx) => {
+                let item = self.0[normalize_index(idx, self.0.len())?].clone();
+                loro_value_to_pyobject(py, PyLoroValue(item))
+            }
+        }
+    }
+
+    pub fn __iter__(&self) -> LoroValueListIter {
+        LoroValueListIter {
+            items: self.0.clone(),
+            next: 0,
+        }
+    }
+
+    pub fn __contains__(&self, value: &Bound<'_, PyAny>) -> bool {
+        pyobject_to_loro_value(value).is_ok_and(|v| self.0.iter().any(|item| json_eq(item, &v)))
+    }
+
+    pub fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> Py<PyAny> {
+        eq(py, &LoroValue::List(self.0.clone()), other)
+    }
+
+    pub fn __hash__(&self) -> u64 {
+        hash(&LoroValue::List(self.0.clone()))
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "LoroValueList({})",
+            LoroValue::List(self.0.clone()).to_json()
+        )
+    }
+
+    /// Convert the whole list to a Python list, without proxies.
+    pub fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        loro_value_to_pyobject_eager(py, LoroValue::List(self.0.clone()))
+    }
+}
+
+/// Iterator over the items of a `LoroValueList`.
+#[pyclass]
+pub struct LoroValueListIter {
+    items: LoroListValue,
+    next: usize,
+}
+
+#[pymethods]
+impl LoroValueListIter {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let Some(item) = self.items.get(self.next) else {
+            return Ok(None);
+        };
+        self.next += 1;
+        loro_value_to_pyobject(py, PyLoroValue(item.clone())).map(Some)
+    }
+
+    pub fn __length_hint__(&self) -> usize {
+        self.items.len().saturating_sub(self.next)
+    }
+}
+
+/// A read-only proxy of a map value, whose values are converted when they
+/// are accessed.
+#[pyclass(frozen, mapping)]
+#[derive(Debug, Clone)]
+pub struct LoroValueMap(pub LoroMapValue);
+
+#[pymethods]
+impl LoroValueMap {
+    pub fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        let value = self
+            .0
+            .get(key)
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))?;
+        loro_value_to_pyobject(py, PyLoroValue(value.clone()))
+    }
+
+    #[pyo3(signature = (key, default=None))]
+    pub fn get<'py>(
+        &self,
+        py: Python<'py>,
+        key: &str,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        match self.0.get(key) {
+            Some(value) => loro_value_to_pyobject(py, PyLoroValue(value.clone())).map(Some),
+            None => Ok(default),
+        }
+    }
+
+    pub fn __contains__(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn __iter__(&self) -> LoroValueMapIter {
+        LoroValueMapIter::new(&self.0, MapIterKind::Keys)
+    }
+
+    pub fn keys(&self) -> LoroValueMapView {
+        LoroValueMapView::new(&self.0, MapIterKind::Keys)
+    }
+
+    pub fn values(&self) -> LoroValueMapView {
+        LoroValueMapView::new(&self.0, MapIterKind::Values)
+    }
+
+    pub fn items(&self) -> LoroValueMapView {
+        LoroValueMapView::new(&self.0, MapIterKind::Items)
+    }
+
+    pub fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> Py<PyAny> {
+        eq(py, &LoroValue::Map(self.0.clone()), other)
+    }
+
+    pub fn __hash__(&self) -> u64 {
+        hash(&LoroValue::Map(self.0.clone()))
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("LoroValueMap({})", LoroValue::Map(self.0.clone()).to_json())
+    }
+
+    /// Convert the whole map to a Python dict, without proxies.
+    pub fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        loro_value_to_pyobject_eager(py, LoroValue::Map(self.0.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MapIterKind {
+    Keys,
+    Values,
+    Items,
+}
+
+/// The keys, values or items of a `LoroValueMap`, which can be iterated over
+/// several times, like the views of a dict.
+#[pyclass(frozen)]
+pub struct LoroValueMapView {
+    map: LoroMapValue,
+    kind: MapIterKind,
+}
+
+impl LoroValueMapView {
+    fn new(map: &LoroMapValue, kind: MapIterKind) -> Self {
+        Self {
+            map: map.clone(),
+            kind,
+        }
+    }
+}
+
+#[pymethods]
+impl LoroValueMapView {
+    pub fn __len__(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn __iter__(&self) -> LoroValueMapIter {
+        LoroValueMapIter::new(&self.map, self.kind)
+    }
+
+    pub fn __contains__(&self, item: &Bound<'_, PyAny>) -> bool {
+        match self.kind {
+            MapIterKind::Keys => item
+                .extract::<String>()
+                .is_ok_and(|key| self.map.contains_key(&key)),
+            MapIterKind::Values => pyobject_to_loro_value(item)
+                .is_ok_and(|v| self.map.values().any(|value| json_eq(value, &v))),
+            MapIterKind::Items => {
+                item.extract::<(String, Bound<'_, PyAny>)>()
+                    .is_ok_and(|(key, value)| {
+                        self.map.get(&key).is_some_and(|existing| {
+                            pyobject_to_loro_value(&value).is_ok_and(|v| json_eq(existing, &v))
+                        })
+                    })
+            }
+        }
+    }
+
+    pub fn __repr__(&self) -> String {
+        let name = match self.kind {
+            MapIterKind::Keys => "keys",
+            MapIterKind::Values => "values",
+            MapIterKind::Items => "items",
+        };
+        format!(
+            "LoroValueMap.{name}({})",
+            LoroValue::Map(self.map.clone()).to_json()
+        )
+    }
+}
+
+/// Iterator over the keys, values or items of a `LoroValueMap`.
+#[pyclass]
+pub struct LoroValueMapIter {
+    map: LoroMapValue,
+    keys: std::vec::IntoIter<String>,
+    kind: MapIterKind,
+}
+
+impl LoroValueMapIter {
+    fn new(map: &LoroMapValue, kind: MapIterKind) -> Self {
+        let keys: Vec<String> = map.keys().cloned().collect();
+        Self {
+            map: map.clone(),
+            keys: keys.into_iter(),
+            kind,
+        }
+    }
+}
+
+#[pymethods]
+impl LoroValueMapIter {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let Some(key) = self.keys.next() else {
+            return Ok(None);
+        };
+        let value = || loro_value_to_pyobject(py, PyLoroValue(self.map[&key].clone()));
+        let ans = match self.kind {
+            MapIterKind::Keys => key.into_bound_py_any(py)?,
+            MapIterKind::Values => value()?,
+            MapIterKind::Items => (key.clone(), value()?).into_bound_py_any(py)?,
+        };
+        Ok(Some(ans))
+    }
+
+    pub fn __length_hint__(&self) -> usize {
+        self.keys.len()
+    }
+}
@@ -3,23 +3,54 @@ use loro::LORO_VERSION;
 use pyo3::prelude::*;
 
 mod awareness;
+mod callback;
 mod container;
 mod convert;
+mod deep;
 mod doc;
 mod err;
 mod event;
+mod history;
+mod hooks;
+mod import_hook;
+mod json_patch;
+mod lazy;
+mod model;
+mod partial;
+mod redact;
+mod report;
+mod richtext;
+mod schema;
+mod store;
+mod stream;
+mod sync;
 mod undo;
 mod value;
 mod version;
+mod view;
 
 /// Python bindings for Loro
 #[pymodule(name = "loro")]
 fn loro_py(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    err::register_class(m)?;
     doc::register_class(m)?;
     container::register_class(m)?;
+    deep::register_class(m)?;
     event::register_class(m)?;
+    history::register_class(m)?;
+    hooks::register_class(m)?;
+    lazy::register_class(m)?;
+    model::register_class(m)?;
+    redact::register_class(m)?;
+    report::register_class(m)?;
+    richtext::register_class(m)?;
+    schema::register_class(m)?;
+    store::register_class(m)?;
+    stream::register_class(m)?;
+    sync::register_class(m)?;
     value::register_class(m)?;
     version::register_class(m)?;
+    view::register_class(m)?;
     undo::register_class(m)?;
     awareness::register_class(m)?;
     m.add("LORO_VERSION", LORO_VERSION)?;
@@ -0,0 +1,540 @@
+//! Typed Python classes bound to a `LoroMap`.
+//!
+//! A subclass of [LoroModel] declares its fields with annotations. When the
+//! subclass is created, every annotated field is replaced by a [ModelField]
+//! descriptor that reads and writes the entry of the bound map with the same
+//! name. The annotations are only resolved on first access, so that they can
+//! refer to models declared later.
+use std::sync::OnceLock;
+
+use loro::{ContainerTrait, LoroMap as LoroMapInner};
+use pyo3::{
+    exceptions::{PyAttributeError, PyNameError, PyTypeError},
+    prelude::*,
+    types::{PyDict, PyString, PyType},
+    IntoPyObjectExt,
+};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    container::{Container, LoroCounter, LoroList, LoroMap, LoroMovableList, LoroText, LoroTree},
+    convert::{loro_value_to_pyobject, pyobject_to_loro_value},
+    err::PyLoroResult,
+    value::LoroValue,
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<LoroModel>()?;
+    m.add_class::<ModelField>()?;
+    Ok(())
+}
+
+/// The type of the values a field accepts.
+#[derive(Debug, Clone)]
+pub(crate) enum ValueType {
+    Any,
+    Null,
+    Bool,
+    Int,
+    Float,
+    Str,
+    Bytes,
+    List(Box<ValueType>),
+    Map(Box<ValueType>),
+    Union(Vec<ValueType>),
+}
+
+impl ValueType {
+    /// Check `value` against the type, converting ints to floats where a
+    /// float is expected.
+    pub(crate) fn coerce(
+        &self,
+        value: loro::LoroValue,
+    ) -> Result<loro::LoroValue, loro::LoroValue> {
+        use loro::LoroValue as V;
+        match (self, value) {
+            (ValueType::Any, v) => Ok(v),
+            (ValueType::Null, v @ V::Null)
+            | (ValueType::Bool, v @ V::Bool(_))
+            | (ValueType::Int, v @ V::I64(_))
+            | (ValueType::Float, v @ V::Double(_))
+            | (ValueType::Str, v @ V::String(_))
+            | (ValueType::Bytes, v @ V::Binary(_)) => Ok(v),
+            (ValueType::Float, V::I64(i)) => Ok(V::Double(i as f64)),
+            (ValueType::List(item), V::List(list)) => {
+                let items = list
+                    .iter()
+                    .map(|v| item.coerce(v.clone()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(V::List(items.into()))
+            }
+            (ValueType::Map(item), V::Map(map)) => {
+                let entries = map
+                    .iter()
+                    .map(|(k, v)| Ok::<_, loro::LoroValue>((k.clone(), item.coerce(v.clone())?)))
+                    .collect::<Result<FxHashMap<_, _>, _>>()?;
+                Ok(V::Map(entries.into()))
+            }
+            (ValueType::Union(types), v) => {
+                for ty in types {
+                    if let Ok(v) = ty.coerce(v.clone()) {
+                        return Ok(v);
+                    }
+                }
+                Err(v)
+            }
+            (_, v) => Err(v),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum FieldKind {
+    Value(ValueType),
+    /// A child container. The value is an empty detached container of the
+    /// right type.
+    Container(Container),
+    /// A child `LoroMap` bound to a nested model.
+    Model(Py<PyType>),
+}
+
+/// Remove `None` from an `Optional[...]` annotation.
+fn strip_optional<'py>(annotation: &Bound<'py, PyAny>) -> PyResult<(Bound<'py, PyAny>, bool)> {
+    let py = annotation.py();
+    let typing = py.import("typing")?;
+    let origin = typing.call_method1("get_origin", (annotation,))?;
+    let is_union = origin.is(&typing.getattr("Union")?)
+        || py
+            .import("types")?
+            .getattr("UnionType")
+            .is_ok_and(|t| origin.is(&t));
+    if !is_union {
+        return Ok((annotation.clone(), false));
+    }
+    let none = py.None().into_bound(py).get_type();
+    let args = typing.call_method1("get_args", (annotation,))?;
+    let args: Vec<Bound<'py, PyAny>> = args.extract()?;
+    let rest: Vec<_> = args.iter().filter(|a| !a.is(&none)).cloned().collect();
+    match rest.as_slice() {
+        [single] if rest.len() < args.len() => Ok((single.clone(), true)),
+        _ => Ok((annotation.clone(), false)),
+    }
+}
+
+pub(crate) fn value_type(annotation: &Bound<'_, PyAny>) -> PyResult<ValueType> {
+    let py = annotation.py();
+    let builtins = py.import("builtins")?;
+    let typing = py.import("typing")?;
+    let is = |name: &str| -> PyResult<bool> { Ok(annotation.is(&builtins.getattr(name)?)) };
+    if annotation.is(&typing.getattr("Any")?) || is("object")? {
+        return Ok(ValueType::Any);
+    }
+    if annotation.is_none() || annotation.is(py.None().into_bound(py).get_type()) {
+        return Ok(ValueType::Null);
+    }
+    for (name, ty) in [
+        ("bool", ValueType::Bool),
+        ("int", ValueType::Int),
+        ("float", ValueType::Float),
+        ("str", ValueType::Str),
+        ("bytes", ValueType::Bytes),
+        ("list", ValueType::List(Box::new(ValueType::Any))),
+        ("dict", ValueType::Map(Box::new(ValueType::Any))),
+    ] {
+        if is(name)? {
+            return Ok(ty);
+        }
+    }
+
+    let origin = typing.call_method1("get_origin", (annotation,))?;
+    let args: Vec<Bound<'_, PyAny>> = typing.call_method1("get_args", (annotation,))?.extract()?;
+    let is_union = origin.is(&typing.getattr("Union")?)
+        || py
+            .import("types")?
+            .getattr("UnionType")
+            .is_ok_and(|t| origin.is(&t));
+    if is_union {
+        return Ok(ValueType::Union(
+            args.iter().map(value_type).collect::<PyResult<_>>()?,
+        ));
+    }
+    if origin.is(&builtins.getattr("list")?) {
+        let item = args.first().map(value_type).transpose()?;
+        return Ok(ValueType::List(Box::new(item.unwrap_or(ValueType::Any))));
+    }
+    if origin.is(&builtins.getattr("dict")?) {
+        let item = args.get(1).map(value_type).transpose()?;
+        return Ok(ValueType::Map(Box::new(item.unwrap_or(ValueType::Any))));
+    }
+    Err(PyTypeError::new_err(format!(
+        "unsupported field annotation {}",
+        annotation.repr()?
+    )))
+}
+
+fn field_kind(annotation: &Bound<'_, PyAny>) -> PyResult<FieldKind> {
+    let py = annotation.py();
+    let (inner, _) = strip_optional(annotation)?;
+    if let Ok(ty) = inner.downcast::<PyType>() {
+        let container = if ty.is(py.get_type::<LoroText>()) {
+            Some(Container::Text(LoroText::default()))
+        } else if ty.is(py.get_type::<LoroList>()) {
+            Some(Container::List(LoroList::default()))
+        } else if ty.is(py.get_type::<LoroMovableList>()) {
+            Some(Container::MovableList(LoroMovableList::default()))
+        } else if ty.is(py.get_type::<LoroMap>()) {
+            Some(Container::Map(LoroMap::default()))
+        } else if ty.is(py.get_type::<LoroCounter>()) {
+            Some(Container::Counter(LoroCounter::default()))
+        } else if ty.is(py.get_type::<LoroTree>()) {
+            Some(Container::Tree(LoroTree::default()))
+        } else {
+            None
+        };
+        if let Some(container) = container {
+            return Ok(FieldKind::Container(container));
+        }
+        if ty.is_subclass_of::<LoroModel>()? {
+            return Ok(FieldKind::Model(ty.clone().unbind()));
+        }
+    }
+    Ok(FieldKind::Value(value_type(annotation)?))
+}
+
+/// A field of a `LoroModel`, stored in the entry of the bound map with the
+/// same name.
+///
+/// Fields are created from the annotations of the model class; they do not
+/// need to be declared explicitly.
+#[pyclass(frozen)]
+pub struct ModelField {
+    name: String,
+    owner: Py<PyType>,
+    /// The class default, converted when the class is created so that every
+    /// read returns a new object, like the values of the map.
+    default: Option<loro::LoroValue>,
+    kind: OnceLock<FieldKind>,
+}
+
+impl ModelField {
+    fn kind(&self, py: Python<'_>) -> PyResult<&FieldKind> {
+        if let Some(kind) = self.kind.get() {
+            return Ok(kind);
+        }
+        let hints = py
+            .import("typing")?
+            .call_method1("get_type_hints", (self.owner.bind(py),))?;
+        let kind = field_kind(&hints.get_item(&self.name)?)?;
+        self.check_default(py, &kind)?;
+        Ok(self.kind.get_or_init(|| kind))
+    }
+
+    /// Check the class default against the annotation of the field.
+    fn check_default(&self, py: Python<'_>, kind: &FieldKind) -> PyResult<()> {
+        let Some(default) = &self.default else {
+            return Ok(());
+        };
+        let valid = match kind {
+            FieldKind::Value(ty) => ty.coerce(default.clone()).is_ok(),
+            FieldKind::Container(_) | FieldKind::Model(_) => false,
+        };
+        if valid {
+            return Ok(());
+        }
+        let owner = self.owner.bind(py).qualname()?;
+        let default = loro_value_to_pyobject(py, LoroValue(default.clone()))?;
+        Err(PyTypeError::new_err(format!(
+            "invalid default for {owner}.{}: {}",
+            self.name,
+            default.repr()?
+        )))
+    }
+
+    fn map(&self, instance: &Bound<'_, PyAny>) -> PyResult<LoroMapInner> {
+        Ok(instance.downcast::<LoroModel>()?.get().map.clone())
+    }
+
+    fn type_error(&self, py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<PyErr> {
+        let owner = self.owner.bind(py).qualname()?;
+        Ok(PyTypeError::new_err(format!(
+            "invalid value for {owner}.{}: {}",
+            self.name,
+            value.repr()?
+        )))
+    }
+}
+
+#[pymethods]
+impl ModelField {
+    /// The name of the field and of its map entry.
+    #[getter]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn __get__<'py>(
+        slf: &Bound<'py, Self>,
+        instance: Option<&Bound<'py, PyAny>>,
+        _owner: Option<&Bound<'py, PyAny>>,
+    ) -> PyLoroResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let Some(instance) = instance.filter(|i| !i.is_none()) else {
+            return Ok(slf.clone().into_any());
+        };
+        let this = slf.get();
+        let map = this.map(instance)?;
+        match this.kind(py)? {
+            FieldKind::Value(ty) => match map.get(&this.name) {
+                Some(loro::ValueOrContainer::Value(value)) => {
+                    Ok(loro_value_to_pyobject(py, LoroValue(value))?)
+                }
+                Some(loro::ValueOrContainer::Container(c)) => {
+                    Ok(Container::from(c).into_bound_py_any(py)?)
+                }
+                None => match &this.default {
+                    Some(default) => {
+                        let default = ty.coerce(default.clone()).unwrap_or_else(|v| v);
+                        Ok(loro_value_to_pyobject(py, LoroValue(default))?)
+                    }
+                    None if ty.coerce(loro::LoroValue::Null).is_ok() => {
+                        Ok(py.None().into_bound(py))
+                    }
+                    None => Err(PyAttributeError::new_err(format!(
+                        "{}.{} is not set",
+                        this.owner.bind(py).qualname()?,
+                        this.name
+                    ))
+                    .into()),
+                },
+            },
+            FieldKind::Container(child) => {
+                let child =
+                    map.get_or_create_container(&this.name, loro::Container::from(child.clone()))?;
+                Ok(Container::from(child).into_bound_py_any(py)?)
+            }
+            FieldKind::Model(model) => {
+                let child = map.get_or_create_container(&this.name, loro::LoroMap::new())?;
+                Ok(model.bind(py).call1((LoroMap(child),))?)
+            }
+        }
+    }
+
+    pub fn __set__(
+        &self,
+        py: Python<'_>,
+        instance: &Bound<'_, PyAny>,
+        value: &Bound<'_, PyAny>,
+    ) -> PyLoroResult<()> {
+        self.set(py, &self.map(instance)?, value)
+    }
+
+    pub fn __delete__(&self, instance: &Bound<'_, PyAny>) -> PyLoroResult<()> {
+        self.map(instance)?.delete(&self.name)?;
+        Ok(())
+    }
+}
+
+impl ModelField {
+    fn set(
+        &self,
+        py: Python<'_>,
+        map: &LoroMapInner,
+        value: &Bound<'_, PyAny>,
+    ) -> PyLoroResult<()> {
+        match self.kind(py)? {
+            FieldKind::Value(ty) => {
+                let converted = pyobject_to_loro_value(value)?;
+                let converted = ty
+                    .coerce(converted)
+                    .map_err(|_| self.type_error(py, value).unwrap_or_else(|e| e))?;
+                map.insert(&self.name, converted)?;
+            }
+            FieldKind::Container(Container::Text(_)) => {
+                // Assigning a string to a text field replaces its content.
+                let Ok(s) = value.downcast::<PyString>() else {
+                    return Err(self.type_error(py, value)?.into());
+                };
+                let text = map.get_or_create_container(&self.name, loro::LoroText::new())?;
+                text.update(s.to_str()?, Default::default())
+                    .map_err(|e| PyTypeError::new_err(e.to_string()))?;
+            }
+            FieldKind::Container(_) | FieldKind::Model(_) => {
+                return Err(PyAttributeError::new_err(format!(
+                    "{}.{} is a container field, it is edited in place",
+                    self.owner.bind(py).qualname()?,
+                    self.name
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Base class of typed views over a `LoroMap`.
+///
+/// Subclasses declare their fields with annotations:
+///
+/// ```python
+/// class Task(LoroModel):
+///     title: str
+///     done: bool = False
+///     notes: LoroText
+///     assignee: Optional[Person]
+/// ```
+///
+/// Every field reads and writes the map entry with the same name. Values are
+/// converted to `LoroValue` and checked against the annotation on write,
+/// which raises `TypeError` on mismatch; `int` is accepted for `float`, and
+/// `list[...]`, `dict[str, ...]` and `Optional`/`Union` are checked
+/// recursively. A missing value reads as a copy of the class default, or
+/// `None` for an optional field: like the values read from the map, it is
+/// not written back when mutated, and must be assigned instead. The defaults
+/// are checked against the annotations when the class is created, or when
+/// the annotations are first resolved if they refer to a class declared
+/// later.
+///
+/// Fields annotated with a container type (`LoroText`, `LoroList`,
+/// `LoroMovableList`, `LoroMap`, `LoroCounter`, `LoroTree`) or with another
+/// model are child containers, created with `get_or_create_container` on first
+/// access. They are edited in place, except text fields which accept a `str`
+/// and update their content to it.
+#[pyclass(subclass, frozen)]
+pub struct LoroModel {
+    map: LoroMapInner,
+}
+
+#[pymethods]
+impl LoroModel {
+    /// Bind the model to `map`, then assign the given fields.
+    #[new]
+    #[classmethod]
+    #[pyo3(signature = (map, **fields))]
+    pub fn new(
+        cls: &Bound<'_, PyType>,
+        map: &LoroMap,
+        fields: Option<&Bound<'_, PyDict>>,
+    ) -> PyLoroResult<Self> {
+        for (name, value) in fields.into_iter().flatten() {
+            let field = cls
+                .getattr(name.downcast::<PyString>().map_err(PyErr::from)?)
+                .ok();
+            let Some(field) = field.as_ref().and_then(|f| f.downcast::<ModelField>().ok()) else {
+                return Err(PyTypeError::new_err(format!(
+                    "{}() got an unexpected field {}",
+                    cls.qualname()?,
+                    name.repr()?
+                ))
+                .into());
+            };
+            field.get().set(cls.py(), &map.0, &value)?;
+        }
+        Ok(Self { map: map.0.clone() })
+    }
+
+    /// Replace the annotated attributes of the subclass with `ModelField`s.
+    #[classmethod]
+    #[pyo3(signature = (**_kwargs))]
+    pub fn __init_subclass__(
+        cls: &Bound<'_, PyType>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        let py = cls.py();
+        let inspect = py.import("inspect")?;
+        let annotations = if inspect.hasattr("get_annotations")? {
+            inspect.call_method1("get_annotations", (cls,))?
+        } else {
+            cls.getattr("__dict__")?
+                .call_method1("get", ("__annotations__", PyDict::new(py)))?
+        };
+        let class_var = py.import("typing")?.getattr("ClassVar")?;
+        let origin_of = py.import("typing")?.getattr("get_origin")?;
+        let dict = cls.getattr("__dict__")?;
+        let mut fields = Vec::new();
+        for name in annotations.try_iter()? {
+            let name = name?;
+            let name_str: String = name.extract()?;
+            let annotation = annotations.get_item(&name)?;
+            if name_str.starts_with('_')
+                || annotation.is(&class_var)
+                || origin_of.call1((&annotation,))?.is(&class_var)
+            {
+                continue;
+            }
+            let default = dict.call_method1("get", (&name,))?.into_any();
+            let default = match pyobject_to_loro_value(&default) {
+                _ if default.is_none() => None,
+                Ok(value) => Some(value),
+                Err(_) => {
+                    return Err(PyTypeError::new_err(format!(
+                        "invalid default for {}.{name_str}: {}",
+                        cls.qualname()?,
+                        default.repr()?
+                    )))
+                }
+            };
+            let has_default = default.is_some();
+            let field = Bound::new(
+                py,
+                ModelField {
+                    name: name_str.clone(),
+                    owner: cls.clone().unbind(),
+                    default,
+                    kind: OnceLock::new(),
+                },
+            )?;
+            cls.setattr(name_str.as_str(), &field)?;
+            if has_default {
+                fields.push(field);
+            }
+        }
+        // The annotations can refer to classes declared later, in which case
+        // the defaults are checked when they are first resolved.
+        let name_error = py.get_type::<PyNameError>();
+        for field in fields {
+            match field.get().kind(py) {
+                Err(e) if e.is_instance(py, &name_error) => {}
+                result => {
+                    result?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The map the model is bound to.
+    #[getter]
+    pub fn map(&self) -> LoroMap {
+        LoroMap(self.map.clone())
+    }
+
+    pub fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let ty = slf.get_type();
+        let map = &slf.get().map;
+        let mut fields = Vec::new();
+        for name in ty.dir()? {
+            let Ok(field) = ty.getattr(name.downcast::<PyString>()?) else {
+                continue;
+            };
+            let Ok(field) = field.downcast::<ModelField>() else {
+                continue;
+            };
+            let field = field.get();
+            // Do not create the missing child containers.
+            if map.get(&field.name).is_none() && field.default.is_none() {
+                continue;
+            }
+            let value = slf.getattr(field.name.as_str())?;
+            fields.push(format!("{}={}", field.name, value.repr()?));
+        }
+        Ok(format!("{}({})", ty.qualname()?, fields.join(", ")))
+    }
+
+    /// Models are equal if they have the same class and are bound to the same
+    /// map.
+    pub fn __eq__(slf: &Bound<'_, Self>, other: &Bound<'_, PyAny>) -> bool {
+        other.downcast::<LoroModel>().is_ok_and(|o| {
+            o.get_type().is(slf.get_type()) && o.get().map.id() == slf.get().map.id()
+        })
+    }
+}
@@ -0,0 +1,245 @@
+//! Exports restricted to some containers of a document.
+//!
+//! The history up to the exported version is exported in the JSON format and
+//! rewritten, so that it never contains anything outside of the requested
+//! containers but the entries leading to them from the root. The rewritten
+//! history is imported into a new document, which is exported as a snapshot.
+//! It is not exported as a shallow snapshot, which Loro exports without any
+//! state when a map holds a list of maps. The changes keep their ids, dependencies and
+//! lamports, so the receiving document is at the same version as the source
+//! document, and the two can exchange updates afterwards.
+//!
+//! An op in a requested container, or in one of its descendants, is kept. An
+//! op in a container on the path to a requested one is kept if it creates the
+//! next container of the path: the other items of a list are replaced with
+//! null, and the other map entries and tree nodes are dropped. Other ops are
+//! dropped. A dropped op is replaced with ops of the same length that delete
+//! the empty key of a map that is never attached to the document, so that
+//! the following ops keep their ids.
+use std::collections::{HashMap, HashSet};
+
+use loro::{
+    ContainerID, ContainerType, ExportMode, Frontiers, JsonListOp, JsonMapOp, JsonMovableListOp,
+    JsonOp, JsonOpContent, JsonTreeOp, LoroDoc, LoroValue, PeerID, TreeID, ID,
+};
+
+use crate::{
+    err::{ContainerNotFoundError, InvalidArgumentError, PyLoroResult, VersionNotFoundError},
+    history::container_parents,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// A requested container or one of its descendants.
+    Included,
+    /// A container on the path from a root to a requested container.
+    Ancestor,
+    Excluded,
+}
+
+struct Pruner {
+    parents: HashMap<ContainerID, ContainerID>,
+    included: HashSet<ContainerID>,
+    ancestors: HashSet<ContainerID>,
+    scopes: HashMap<ContainerID, Scope>,
+    /// The tree nodes on a path to a requested container, and all the nodes
+    /// they have been moved under.
+    nodes: HashSet<TreeID>,
+    /// The map the dropped ops are replaced with ops on.
+    sink: Option<ContainerID>,
+}
+
+impl Pruner {
+    fn new(
+        doc: &LoroDoc,
+        changes: &[loro::JsonChange],
+        ids: &[ContainerID],
+        latest: bool,
+    ) -> PyLoroResult<Self> {
+        let parents = container_parents(changes);
+        let mut ancestors = HashSet::new();
+        for id in ids {
+            let not_found = || {
+                ContainerNotFoundError::new_err(format!(
+                    "Container {id} is not attached to the document"
+                ))
+            };
+            if latest && doc.get_path_to_container(id).is_none() {
+                return Err(not_found().into());
+            }
+            let mut current = id;
+            while let Some(parent) = parents.get(current) {
+                ancestors.insert(parent.clone());
+                current = parent;
+            }
+            if !current.is_root() {
+                return Err(not_found().into());
+            }
+        }
+
+        // The nodes whose metadata is on a path, and their parents.
+        let mut nodes: HashSet<TreeID> = ids
+            .iter()
+            .chain(&ancestors)
+            .filter(|id| {
+                parents
+                    .get(*id)
+                    .is_some_and(|parent| parent.container_type() == ContainerType::Tree)
+            })
+            .filter_map(|id| match id {
+                ContainerID::Normal { peer, counter, .. } => Some(TreeID::new(*peer, *counter)),
+                ContainerID::Root { .. } => None,
+            })
+            .collect();
+        let mut moves: HashMap<TreeID, Vec<TreeID>> = HashMap::new();
+        for op in changes.iter().flat_map(|change| &change.ops) {
+            if let JsonOpContent::Tree(
+                JsonTreeOp::Create {
+                    target,
+                    parent: Some(parent),
+                    ..
+                }
+                | JsonTreeOp::Move {
+                    target,
+                    parent: Some(parent),
+                    ..
+                },
+            ) = &op.content
+            {
+                moves.entry(*target).or_default().push(*parent);
+            }
+        }
+        let mut stack: Vec<TreeID> = nodes.iter().copied().collect();
+        while let Some(node) = stack.pop() {
+            for &parent in moves.get(&node).into_iter().flatten() {
+                if nodes.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+
+        Ok(Pruner {
+            parents,
+            included: ids.iter().cloned().collect(),
+            ancestors,
+            scopes: HashMap::new(),
+            nodes,
+            sink: None,
+        })
+    }
+
+    fn keeps(&self, id: &ContainerID) -> bool {
+        self.included.contains(id) || self.ancestors.contains(id)
+    }
+
+    fn scope(&mut self, id: &ContainerID) -> Scope {
+        if let Some(&scope) = self.scopes.get(id) {
+            return scope;
+        }
+        let scope = if self.included.contains(id) {
+            Scope::Included
+        } else if self.ancestors.contains(id) {
+            Scope::Ancestor
+        } else {
+            match self.parents.get(id).cloned() {
+                Some(parent) if self.scope(&parent) == Scope::Included => Scope::Included,
+                _ => Scope::Excluded,
+            }
+        };
+        self.scopes.insert(id.clone(), scope);
+        scope
+    }
+
+    /// Replace the values that are not kept with null.
+    fn prune_values(&self, values: &mut [LoroValue]) {
+        for value in values {
+            if !value.as_container().is_some_and(|id| self.keeps(id)) {
+                *value = LoroValue::Null;
+            }
+        }
+    }
+
+    /// Push the rewritten `op` of a change of `peer` to `out`.
+    fn rewrite(&mut self, peer: PeerID, mut op: JsonOp, out: &mut Vec<JsonOp>) {
+        let keep = match self.scope(&op.container) {
+            Scope::Included => true,
+            Scope::Excluded => false,
+            Scope::Ancestor => match &mut op.content {
+                JsonOpContent::Map(JsonMapOp::Insert { value, .. }) => {
+                    value.as_container().is_some_and(|id| self.keeps(id))
+                }
+                JsonOpContent::List(JsonListOp::Insert { value, .. })
+                | JsonOpContent::MovableList(JsonMovableListOp::Insert { value, .. }) => {
+                    self.prune_values(value);
+                    true
+                }
+                JsonOpContent::MovableList(JsonMovableListOp::Set { value, .. }) => {
+                    self.prune_values(std::slice::from_mut(value));
+                    true
+                }
+                JsonOpContent::List(JsonListOp::Delete { .. })
+                | JsonOpContent::MovableList(
+                    JsonMovableListOp::Delete { .. } | JsonMovableListOp::Move { .. },
+                ) => true,
+                JsonOpContent::Tree(
+                    JsonTreeOp::Create { target, .. }
+                    | JsonTreeOp::Move { target, .. }
+                    | JsonTreeOp::Delete { target },
+                ) => self.nodes.contains(target),
+                _ => false,
+            },
+        };
+        if keep {
+            out.push(op);
+            return;
+        }
+        let sink = self
+            .sink
+            .get_or_insert_with(|| {
+                ContainerID::new_normal(ID::new(peer, op.counter), ContainerType::Map)
+            })
+            .clone();
+        for i in 0..op.content.op_len() as i32 {
+            out.push(JsonOp {
+                content: JsonOpContent::Map(JsonMapOp::Delete { key: String::new() }),
+                container: sink.clone(),
+                counter: op.counter + i,
+            });
+        }
+    }
+}
+
+/// Export the containers `ids` and their descendants at `frontiers`, or at the
+/// latest version.
+pub fn export_containers(
+    doc: &LoroDoc,
+    ids: &[ContainerID],
+    frontiers: Option<&Frontiers>,
+) -> PyLoroResult<Vec<u8>> {
+    if doc.is_shallow() {
+        return Err(InvalidArgumentError::new_err(
+            "the containers of a shallow document cannot be exported",
+        )
+        .into());
+    }
+    let vv = match frontiers {
+        Some(frontiers) => doc.frontiers_to_vv(frontiers).ok_or_else(|| {
+            VersionNotFoundError::new_err(format!(
+                "Frontiers {frontiers:?} are not included in the document"
+            ))
+        })?,
+        None => doc.oplog_vv(),
+    };
+    let mut json = doc.export_json_updates_without_peer_compression(&Default::default(), &vv);
+    let mut pruner = Pruner::new(doc, &json.changes, ids, frontiers.is_none())?;
+    for change in json.changes.iter_mut() {
+        let ops = std::mem::take(&mut change.ops);
+        for op in ops {
+            pruner.rewrite(change.id.peer, op, &mut change.ops);
+        }
+    }
+
+    let pruned = LoroDoc::new();
+    pruned.import_json_updates(json)?;
+    Ok(pruned.export(ExportMode::Snapshot)?)
+}
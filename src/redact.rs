@@ -0,0 +1,237 @@
+//! Redaction of a document's history with a configurable policy.
+//!
+//! The history is exported in the JSON format, the operations in the version
+//! range are rewritten according to the [RedactPolicy], and the result is
+//! imported into a new document that is exported as binary. Operations keep
+//! their length, so the ids of the redacted history stay the same: text that
+//! is not kept is always replaced with U+FFFD, whatever the action.
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use loro::{
+    ContainerID, Counter, ExportMode, JsonFutureOp, JsonListOp, JsonMapOp, JsonMovableListOp,
+    JsonOpContent, JsonTextOp, LoroDoc, LoroValue, ToJson,
+};
+use pyo3::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    err::{PyLoroError, PyLoroResult},
+    history::container_parents,
+    value::ContainerID as PyContainerID,
+    version::VersionRange,
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<RedactAction>()?;
+    m.add_class::<RedactPolicy>()?;
+    Ok(())
+}
+
+/// What `LoroDoc.redact` does with the content of an operation.
+///
+/// Child containers are always kept, so that the structure of the document
+/// and the operations inside of them stay valid.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactAction {
+    /// Keep the content.
+    Keep,
+    /// Replace values with null, text with U+FFFD and counter updates with 0.
+    #[default]
+    Null,
+    /// Replace values with `"sha256:<hex>"`, the hash of the salt followed by
+    /// the JSON of the value, so that equal values can still be matched.
+    Hash,
+    /// Turn map insertions into deletions of the key, and redact the rest
+    /// like `Null`.
+    Drop,
+}
+
+/// How `LoroDoc.redact` redacts each operation.
+///
+/// The action of a map entry is the one of its key in `keys`. Otherwise, the
+/// action is the one of the nearest container in `containers` on the path
+/// from the root to the operation's container, or `default`. The values of
+/// the text marks in `marks` are kept whatever the action.
+#[pyclass(frozen)]
+#[derive(Debug, Clone, Default)]
+pub struct RedactPolicy {
+    default: RedactAction,
+    containers: HashMap<ContainerID, RedactAction>,
+    keys: HashMap<String, RedactAction>,
+    marks: HashSet<String>,
+    salt: Vec<u8>,
+}
+
+#[pymethods]
+impl RedactPolicy {
+    #[new]
+    #[pyo3(signature = (default=RedactAction::Null, *, containers=None, keys=None, marks=None, salt=None))]
+    pub fn new(
+        default: RedactAction,
+        containers: Option<HashMap<PyContainerID, RedactAction>>,
+        keys: Option<HashMap<String, RedactAction>>,
+        marks: Option<Vec<String>>,
+        salt: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            default,
+            containers: containers
+                .into_iter()
+                .flatten()
+                .map(|(id, action)| (id.into(), action))
+                .collect(),
+            keys: keys.unwrap_or_default(),
+            marks: marks.into_iter().flatten().collect(),
+            salt: salt.unwrap_or_default(),
+        }
+    }
+}
+
+impl RedactPolicy {
+    /// The action of the operations in `container`, ignoring `keys`.
+    /// `parents` is the parent of every container created in the history, so
+    /// that the containers deleted since then are redacted like the others.
+    fn container_action(
+        &self,
+        parents: &HashMap<ContainerID, ContainerID>,
+        container: &ContainerID,
+    ) -> RedactAction {
+        let mut current = Some(container);
+        while let Some(id) = current {
+            if let Some(&action) = self.containers.get(id) {
+                return action;
+            }
+            current = parents.get(id);
+        }
+        self.default
+    }
+
+    fn hash(&self, value: &LoroValue) -> LoroValue {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.salt);
+        hasher.update(value.to_json().as_bytes());
+        let hex: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        LoroValue::from(format!("sha256:{hex}"))
+    }
+
+    fn redact_value(&self, action: RedactAction, value: &mut LoroValue) {
+        match (action, &value) {
+            (RedactAction::Keep, _) | (_, LoroValue::Container(_)) => {}
+            (RedactAction::Hash, _) => *value = self.hash(value),
+            (RedactAction::Null | RedactAction::Drop, _) => *value = LoroValue::Null,
+        }
+    }
+
+    /// Redact the part `range` of the operation `op`.
+    fn redact_op(
+        &self,
+        action: RedactAction,
+        op: &mut JsonOpContent,
+        range: Range<Counter>,
+    ) -> PyLoroResult<()> {
+        let range = range.start as usize..range.end as usize;
+        match op {
+            JsonOpContent::Map(JsonMapOp::Insert { key, value }) => {
+                let action = self.keys.get(key.as_str()).copied().unwrap_or(action);
+                if action == RedactAction::Drop && !matches!(value, LoroValue::Container(_)) {
+                    *op = JsonOpContent::Map(JsonMapOp::Delete { key: key.clone() });
+                } else {
+                    self.redact_value(action, value);
+                }
+            }
+            _ if action == RedactAction::Keep => {}
+            JsonOpContent::List(JsonListOp::Insert { value, .. })
+            | JsonOpContent::MovableList(JsonMovableListOp::Insert { value, .. }) => {
+                for value in &mut value[range] {
+                    self.redact_value(action, value);
+                }
+            }
+            JsonOpContent::MovableList(JsonMovableListOp::Set { value, .. }) => {
+                self.redact_value(action, value)
+            }
+            JsonOpContent::Text(JsonTextOp::Insert { text, .. }) => {
+                *text = text
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| if range.contains(&i) { '\u{FFFD}' } else { c })
+                    .collect();
+            }
+            JsonOpContent::Text(JsonTextOp::Mark {
+                style_key,
+                style_value,
+                ..
+            }) if !self.marks.contains(style_key.as_str()) => {
+                self.redact_value(action, style_value)
+            }
+            JsonOpContent::Future(wrapper) => match &mut wrapper.value {
+                JsonFutureOp::Counter(value) => {
+                    *value = serde_json::from_value(serde_json::json!({
+                        "value_type": "i64",
+                        "value": 0,
+                    }))
+                    .unwrap()
+                }
+                JsonFutureOp::Unknown(_) => {
+                    return Err(PyLoroError::Error(
+                        "Cannot redact an operation of an unknown type".to_string(),
+                    ))
+                }
+            },
+            // Deletions, moves, tree operations and mark ends carry no content,
+            // and the values of the marks in `marks` are kept.
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Redact the operations of `doc` in `version_range` with `policy`, and export
+/// the whole redacted history as a snapshot or as updates.
+pub fn redact(
+    doc: &LoroDoc,
+    version_range: &VersionRange,
+    policy: &RedactPolicy,
+    snapshot: bool,
+) -> PyLoroResult<Vec<u8>> {
+    let range = loro::VersionRange::from(version_range.clone());
+    let mut json =
+        doc.export_json_updates_without_peer_compression(&Default::default(), &doc.oplog_vv());
+    let parents = container_parents(&json.changes);
+    let mut actions: HashMap<ContainerID, RedactAction> = HashMap::new();
+    for change in json.changes.iter_mut() {
+        let id = change.id;
+        if !range.has_overlap_with(id.to_span(change.op_len())) {
+            continue;
+        }
+        let (start, end) = range.get(&id.peer).copied().unwrap();
+        for op in change.ops.iter_mut() {
+            if op.counter >= end {
+                break;
+            }
+            let len = op.content.op_len() as Counter;
+            if op.counter + len <= start {
+                continue;
+            }
+            let action = *actions
+                .entry(op.container.clone())
+                .or_insert_with(|| policy.container_action(&parents, &op.container));
+            let range = (start - op.counter).clamp(0, len)..(end - op.counter).clamp(0, len);
+            policy.redact_op(action, &mut op.content, range)?;
+        }
+    }
+
+    let redacted = LoroDoc::new();
+    redacted.import_json_updates(json)?;
+    let mode = if snapshot {
+        ExportMode::Snapshot
+    } else {
+        ExportMode::all_updates()
+    };
+    Ok(redacted.export(mode)?)
+}
@@ -0,0 +1,502 @@
+//! Human-readable reports of the changes in a `DiffBatch`.
+//!
+//! Each changed container gets an entry addressed by its JSON pointer in the
+//! document, with one line per change. Text changes are also rendered as a
+//! unified diff, which needs the text before the changes: it is only known
+//! when the batch was calculated by `LoroDoc.diff`. Map and list changes are
+//! also rendered as a JSON patch (RFC 6902) relative to the root of the
+//! document. A new child container is added empty by the patch of its parent,
+//! and filled by its own entry, which comes after the parent's one. A moved
+//! container is added with its content after the changes, and the patch of a
+//! whole batch leaves out its own entry and the ones of its descendants. The
+//! patch of a whole batch also adds the new root containers empty first.
+use std::fmt;
+
+use loro::{
+    event::{Diff, DiffBatch, ListDiffItem, MapDelta},
+    ContainerID, ContainerTrait, ContainerType, Frontiers, Index, LoroDoc, LoroMapValue, LoroValue,
+    TextDelta, ToJson, TreeExternalDiff, TreeParentId, ValueOrContainer,
+};
+use pyo3::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    err::{InvalidArgumentError, PyLoroResult},
+    json_patch,
+    value::{ContainerID as PyContainerID, LoroValue as PyLoroValue},
+    view::state_at,
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<DiffReport>()?;
+    m.add_class::<DiffReportEntry>()?;
+    Ok(())
+}
+
+/// The document and the versions a diff was calculated between.
+#[derive(Debug, Clone)]
+pub struct DiffSource {
+    pub doc: LoroDoc,
+    pub from: Frontiers,
+    pub to: Frontiers,
+}
+
+/// A human-readable report of the changes in a `DiffBatch`, created by
+/// `DiffBatch.to_report`.
+///
+/// `str(report)` renders all the entries.
+#[pyclass(frozen, get_all, str)]
+#[derive(Debug, Clone)]
+pub struct DiffReport {
+    pub entries: Vec<DiffReportEntry>,
+}
+
+/// The changes of a container in a `DiffReport`.
+#[pyclass(frozen, get_all, str)]
+#[derive(Debug, Clone)]
+pub struct DiffReportEntry {
+    pub container: PyContainerID,
+    /// The JSON pointer of the container in the document, or its id if it
+    /// cannot be resolved.
+    pub path: String,
+    /// One line per change.
+    pub changes: Vec<String>,
+    /// The unified diff of a text, if the text before the changes is known.
+    pub unified_diff: Option<String>,
+    /// The JSON patch of a map or a list.
+    pub json_patch: Option<PyLoroValue>,
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            write!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DiffReportEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = ContainerID::from(self.container.clone());
+        writeln!(f, "{} ({:?})", self.path, id.container_type())?;
+        for change in &self.changes {
+            writeln!(f, "  {change}")?;
+        }
+        if let Some(diff) = &self.unified_diff {
+            write!(f, "{diff}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The JSON pointer of `id` in `doc`.
+fn pointer(doc: &LoroDoc, id: &ContainerID) -> Option<String> {
+    let path = doc.get_path_to_container(id)?;
+    let mut pointer = String::new();
+    for (_, index) in path {
+        pointer.push('/');
+        match index {
+            Index::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            Index::Seq(i) => pointer.push_str(&i.to_string()),
+            Index::Node(node) => pointer.push_str(&node.to_string()),
+        }
+    }
+    Some(pointer)
+}
+
+/// The value of a new child container, before its own changes.
+fn empty_value(kind: ContainerType) -> LoroValue {
+    match kind {
+        ContainerType::Map => LoroValue::Map(Default::default()),
+        ContainerType::List | ContainerType::MovableList | ContainerType::Tree => {
+            LoroValue::List(Default::default())
+        }
+        ContainerType::Text => LoroValue::from(""),
+        ContainerType::Counter => LoroValue::Double(0.),
+        ContainerType::Unknown(_) => LoroValue::Null,
+    }
+}
+
+/// The value of `value` in a JSON patch. A moved container keeps its content,
+/// which is the one in `after`, the document after the changes, if it is
+/// known.
+fn patch_value(value: &ValueOrContainer, is_move: bool, after: Option<&LoroDoc>) -> LoroValue {
+    match value {
+        ValueOrContainer::Value(value) => value.clone(),
+        ValueOrContainer::Container(c) if is_move => match after {
+            Some(after) => json_patch::container(after, c.id())
+                .map(|c| ValueOrContainer::Container(c).get_deep_value())
+                .unwrap_or(LoroValue::Null),
+            None => value.get_deep_value(),
+        },
+        ValueOrContainer::Container(c) => empty_value(c.get_type()),
+    }
+}
+
+fn describe(value: &ValueOrContainer) -> String {
+    match value {
+        ValueOrContainer::Value(value) => value.to_json(),
+        ValueOrContainer::Container(c) => format!("a new {:?}", c.get_type()),
+    }
+}
+
+fn describe_parent(parent: &TreeParentId) -> String {
+    match parent {
+        TreeParentId::Node(node) => format!("node {node}"),
+        TreeParentId::Root => "the root".to_string(),
+        TreeParentId::Deleted | TreeParentId::Unexist => "nothing".to_string(),
+    }
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        format!("{n} {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+fn patch_op(op: &str, path: String, value: Option<LoroValue>) -> LoroValue {
+    let mut map = LoroMapValue::default();
+    map.make_mut().insert("op".into(), op.into());
+    map.make_mut().insert("path".into(), path.into());
+    if let Some(value) = value {
+        map.make_mut().insert("value".into(), value);
+    }
+    LoroValue::Map(map)
+}
+
+/// Apply a text delta to `text`.
+fn apply_delta(text: &str, delta: &[TextDelta]) -> String {
+    let mut chars = text.chars();
+    let mut ans = String::new();
+    for item in delta {
+        match item {
+            TextDelta::Retain { retain, .. } => ans.extend(chars.by_ref().take(*retain)),
+            TextDelta::Insert { insert, .. } => ans.push_str(insert),
+            TextDelta::Delete { delete } => chars.by_ref().take(*delete).for_each(drop),
+        }
+    }
+    ans.extend(chars);
+    ans
+}
+
+fn text_changes(delta: &[TextDelta]) -> Vec<String> {
+    let attributes = |attributes: &Option<FxHashMap<String, LoroValue>>| match attributes {
+        Some(attributes) => {
+            let map: LoroMapValue = attributes
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<FxHashMap<_, _>>()
+                .into();
+            format!(" with {}", LoroValue::Map(map).to_json())
+        }
+        None => String::new(),
+    };
+    let mut changes = Vec::new();
+    let mut index = 0;
+    for item in delta {
+        match item {
+            TextDelta::Retain {
+                retain,
+                attributes: attrs,
+            } => {
+                if attrs.is_some() {
+                    changes.push(format!(
+                        "formatted {} at {index}{}",
+                        plural(*retain, "character"),
+                        attributes(attrs)
+                    ));
+                }
+                index += retain;
+            }
+            TextDelta::Insert {
+                insert,
+                attributes: attrs,
+            } => {
+                changes.push(format!(
+                    "inserted {} at {index}{}",
+                    LoroValue::from(insert.as_str()).to_json(),
+                    attributes(attrs)
+                ));
+                index += insert.chars().count();
+            }
+            TextDelta::Delete { delete } => {
+                changes.push(format!(
+                    "deleted {} at {index}",
+                    plural(*delete, "character")
+                ));
+            }
+        }
+    }
+    changes
+}
+
+/// The changes and the JSON patch of a list diff.
+fn list_changes(
+    path: &str,
+    items: &[ListDiffItem],
+    after: Option<&LoroDoc>,
+) -> (Vec<String>, Vec<LoroValue>) {
+    let mut changes = Vec::new();
+    let mut patch = Vec::new();
+    let mut index = 0;
+    for item in items {
+        match item {
+            ListDiffItem::Retain { retain } => index += retain,
+            ListDiffItem::Delete { delete } => {
+                changes.push(format!("deleted {} at {index}", plural(*delete, "item")));
+                for _ in 0..*delete {
+                    patch.push(patch_op("remove", format!("{path}/{index}"), None));
+                }
+            }
+            ListDiffItem::Insert { insert, is_move } => {
+                let values: Vec<_> = insert.iter().map(describe).collect();
+                let verb = if *is_move { "moved" } else { "inserted" };
+                changes.push(format!("{verb} [{}] at {index}", values.join(", ")));
+                for value in insert {
+                    let value = patch_value(value, *is_move, after);
+                    patch.push(patch_op("add", format!("{path}/{index}"), Some(value)));
+                    index += 1;
+                }
+            }
+        }
+    }
+    (changes, patch)
+}
+
+/// The changes and the JSON patch of the diff of the map `id`. If `before`,
+/// the document before the changes, is known, the deletions of the keys the
+/// map does not have in it, which were added and deleted by the changes, are
+/// left out.
+fn map_changes(
+    path: &str,
+    id: &ContainerID,
+    delta: &MapDelta,
+    before: Option<&LoroDoc>,
+) -> (Vec<String>, Vec<LoroValue>) {
+    let existed = |key: &str| match before {
+        Some(doc) => doc.has_container(id) && doc.get_map(id.clone()).get(key).is_some(),
+        None => true,
+    };
+    let mut changes = Vec::new();
+    let mut patch = Vec::new();
+    let mut updated: Vec<_> = delta.updated.iter().collect();
+    updated.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in updated {
+        let key_path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+        let name = LoroValue::from(key.as_ref()).to_json();
+        match value {
+            Some(value) => {
+                changes.push(format!("set {name} to {}", describe(value)));
+                patch.push(patch_op(
+                    "add",
+                    key_path,
+                    Some(patch_value(value, false, None)),
+                ));
+            }
+            None if !existed(key) => {}
+            None => {
+                changes.push(format!("deleted {name}"));
+                patch.push(patch_op("remove", key_path, None));
+            }
+        }
+    }
+    (changes, patch)
+}
+
+/// The states of the document before and after the changes of a batch,
+/// created when they are first needed.
+struct States<'a> {
+    source: Option<&'a DiffSource>,
+    before: Option<LoroDoc>,
+    after: Option<LoroDoc>,
+}
+
+impl<'a> States<'a> {
+    fn new(source: Option<&'a DiffSource>) -> Self {
+        Self {
+            source,
+            before: None,
+            after: None,
+        }
+    }
+
+    fn before(&mut self) -> PyLoroResult<Option<LoroDoc>> {
+        let Some(source) = self.source else {
+            return Ok(None);
+        };
+        if self.before.is_none() {
+            self.before = Some(state(&source.doc, &source.from)?);
+        }
+        Ok(self.before.clone())
+    }
+
+    fn after(&mut self) -> PyLoroResult<Option<LoroDoc>> {
+        let Some(source) = self.source else {
+            return Ok(None);
+        };
+        if self.after.is_none() {
+            self.after = Some(state(&source.doc, &source.to)?);
+        }
+        Ok(self.after.clone())
+    }
+}
+
+/// The state of `doc` at `frontiers`, which is `doc` itself if it is at that
+/// version.
+fn state(doc: &LoroDoc, frontiers: &Frontiers) -> PyLoroResult<LoroDoc> {
+    if doc.state_frontiers() == *frontiers {
+        Ok(doc.clone())
+    } else {
+        state_at(doc, frontiers)
+    }
+}
+
+/// Create the report of `batch`. If its source is known, the paths are the
+/// ones after the changes, and the texts before the changes are known.
+pub fn report(batch: &DiffBatch, source: Option<&DiffSource>) -> PyLoroResult<DiffReport> {
+    let mut states = States::new(source);
+    let mut entries = Vec::new();
+    for (id, diff) in batch.iter() {
+        let path = states
+            .after()?
+            .and_then(|after| pointer(&after, id))
+            .unwrap_or_else(|| id.to_string());
+        let mut entry = DiffReportEntry {
+            container: id.clone().into(),
+            path,
+            changes: Vec::new(),
+            unified_diff: None,
+            json_patch: None,
+        };
+        match diff {
+            Diff::Map(delta) => {
+                let before = states.before()?;
+                let (changes, patch) = map_changes(&entry.path, id, delta, before.as_ref());
+                entry.changes = changes;
+                entry.json_patch = Some(LoroValue::from(patch).into());
+            }
+            Diff::List(items) => {
+                let after = states.after()?;
+                let (changes, patch) = list_changes(&entry.path, items, after.as_ref());
+                entry.changes = changes;
+                entry.json_patch = Some(LoroValue::from(patch).into());
+            }
+            Diff::Text(delta) => {
+                entry.changes = text_changes(delta);
+                if let Some(before) = states.before()? {
+                    let old = before.get_text(id.clone()).to_string();
+                    let new = apply_delta(&old, delta);
+                    let name = entry.path.trim_start_matches('/');
+                    let diff = similar::TextDiff::from_lines(&old, &new)
+                        .unified_diff()
+                        .header(&format!("a/{name}"), &format!("b/{name}"))
+                        .to_string();
+                    entry.unified_diff = Some(diff);
+                }
+            }
+            Diff::Tree(tree) => {
+                for item in &tree.diff {
+                    let target = item.target;
+                    entry.changes.push(match &item.action {
+                        TreeExternalDiff::Create { parent, index, .. } => format!(
+                            "created node {target} under {} at {index}",
+                            describe_parent(parent)
+                        ),
+                        TreeExternalDiff::Move { parent, index, .. } => format!(
+                            "moved node {target} under {} at {index}",
+                            describe_parent(parent)
+                        ),
+                        TreeExternalDiff::Delete { .. } => format!("deleted node {target}"),
+                    });
+                }
+            }
+            Diff::Counter(delta) if *delta < 0. => {
+                entry.changes.push(format!("decremented by {}", -delta))
+            }
+            Diff::Counter(delta) => entry.changes.push(format!("incremented by {delta}")),
+            Diff::Unknown => entry.changes.push("unknown changes".to_string()),
+        }
+        entries.push(entry);
+    }
+    Ok(DiffReport { entries })
+}
+
+/// Convert `batch` to a JSON patch. Texts, counters and trees are replaced by
+/// their values after the changes.
+pub fn json_patch(batch: &DiffBatch, source: Option<&DiffSource>) -> PyLoroResult<Vec<LoroValue>> {
+    let mut states = States::new(source);
+    let Some(before) = states.before()? else {
+        return Err(InvalidArgumentError::new_err(
+            "Only a batch calculated by LoroDoc.diff can be converted to a JSON patch",
+        )
+        .into());
+    };
+    let LoroValue::Map(existing) = before.get_value() else {
+        unreachable!("the value of a document is a map")
+    };
+    let after = states.after()?.unwrap();
+    let mut patch = Vec::new();
+    // The roots that do not exist before the changes are added empty first.
+    let mut added = FxHashSet::default();
+    for (id, _) in batch.iter() {
+        let Some(Some((root, Index::Key(name)))) = after
+            .get_path_to_container(id)
+            .map(|path| path.into_iter().next())
+        else {
+            continue;
+        };
+        if !existing.contains_key(name.as_str()) && added.insert(name.clone()) {
+            let path = format!("/{}", name.replace('~', "~0").replace('/', "~1"));
+            let value = empty_value(root.container_type());
+            patch.push(patch_op("add", path, Some(value)));
+        }
+    }
+    // A moved container is added with its content after the changes, so its
+    // changes, and the ones of its descendants, are already applied.
+    let moved: FxHashSet<ContainerID> = batch
+        .iter()
+        .filter_map(|(_, diff)| match diff {
+            Diff::List(items) => Some(items),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|item| match item {
+            ListDiffItem::Insert {
+                insert,
+                is_move: true,
+            } => Some(insert),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|value| match value {
+            ValueOrContainer::Container(c) => Some(c.id()),
+            ValueOrContainer::Value(_) => None,
+        })
+        .collect();
+    for (id, diff) in batch.iter() {
+        // A container deleted by the changes is removed by its parent's patch.
+        let Some(containers) = after.get_path_to_container(id) else {
+            continue;
+        };
+        if containers.iter().any(|(c, _)| moved.contains(c)) {
+            continue;
+        }
+        let Some(path) = pointer(&after, id) else {
+            continue;
+        };
+        match diff {
+            Diff::Map(delta) => patch.extend(map_changes(&path, id, delta, Some(&before)).1),
+            Diff::List(items) => patch.extend(list_changes(&path, items, Some(&after)).1),
+            _ => {
+                let value = json_patch::container(&after, id.clone())
+                    .map(|c| ValueOrContainer::Container(c).get_deep_value())
+                    .unwrap_or(LoroValue::Null);
+                patch.push(patch_op("replace", path, Some(value)));
+            }
+        }
+    }
+    Ok(patch)
+}
@@ -0,0 +1,790 @@
+//! Converting rich text to and from Markdown or HTML.
+//!
+//! To render a text, it is read as a delta: a list of text segments, each with
+//! the marks that apply to it. Marks are opened and closed so that they are
+//! always properly nested, even when they overlap: when a mark ends inside
+//! another one, the marks opened after it are closed and opened again.
+//!
+//! To import a text, the source is parsed into plain text plus a list of mark
+//! ranges, which are applied as a delta, or reconciled with the current marks
+//! after `LoroText::update` in diff mode.
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+};
+
+use loro::{ContainerTrait, LoroText, LoroValue, TextDelta, ToJson, UpdateOptions};
+use pyo3::prelude::*;
+
+use crate::err::{LoroError, PyLoroResult};
+
+mod html;
+mod markdown;
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<MarkStyle>()?;
+    Ok(())
+}
+
+/// How a mark is rendered: the text it covers is wrapped between `open` and
+/// `close`.
+///
+/// `{value}` in `open` or `close` is replaced by the value of the mark, escaped
+/// for the output format. It is how `link` marks render their URL.
+///
+/// The text covered by a `verbatim` mark is not escaped in Markdown, which is
+/// what code spans need. HTML text is always escaped. If the delimiters of a
+/// `verbatim` mark are backticks, more of them are used when the text contains
+/// a run of backticks as long, so that the code span is not closed early.
+///
+/// `link` marks whose URL has a scheme other than `http`, `https` or `mailto`,
+/// such as `javascript:`, are not rendered.
+#[pyclass(get_all, frozen, eq, str)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkStyle {
+    pub open: String,
+    pub close: String,
+    pub verbatim: bool,
+}
+
+impl std::fmt::Display for MarkStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl MarkStyle {
+    fn new(open: &str, close: &str) -> Self {
+        Self {
+            open: open.to_string(),
+            close: close.to_string(),
+            verbatim: false,
+        }
+    }
+}
+
+#[pymethods]
+impl MarkStyle {
+    #[new]
+    #[pyo3(signature = (open, close, verbatim=false))]
+    pub fn py_new(open: String, close: String, verbatim: bool) -> Self {
+        Self {
+            open,
+            close,
+            verbatim,
+        }
+    }
+
+    /// The styles used by `LoroText.to_markdown()` by default.
+    #[staticmethod]
+    pub fn markdown_defaults() -> HashMap<String, MarkStyle> {
+        Format::Markdown.default_styles()
+    }
+
+    /// The styles used by `LoroText.to_html()` by default.
+    #[staticmethod]
+    pub fn html_defaults() -> HashMap<String, MarkStyle> {
+        Format::Html.default_styles()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    fn default_styles(self) -> HashMap<String, MarkStyle> {
+        let styles = match self {
+            Format::Markdown => [
+                ("bold", MarkStyle::new("**", "**")),
+                ("italic", MarkStyle::new("*", "*")),
+                ("underline", MarkStyle::new("<u>", "</u>")),
+                ("strike", MarkStyle::new("~~", "~~")),
+                ("strikethrough", MarkStyle::new("~~", "~~")),
+                ("code", MarkStyle::py_new("`".into(), "`".into(), true)),
+                ("link", MarkStyle::new("[", "]({value})")),
+            ],
+            Format::Html => [
+                ("bold", MarkStyle::new("<strong>", "</strong>")),
+                ("italic", MarkStyle::new("<em>", "</em>")),
+                ("underline", MarkStyle::new("<u>", "</u>")),
+                ("strike", MarkStyle::new("<s>", "</s>")),
+                ("strikethrough", MarkStyle::new("<s>", "</s>")),
+                ("code", MarkStyle::new("<code>", "</code>")),
+                ("link", MarkStyle::new("<a href=\"{value}\">", "</a>")),
+            ],
+        };
+        styles
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    /// The default styles, updated with `overrides`. A key mapped to `None`
+    /// is not rendered.
+    pub fn styles(
+        self,
+        overrides: Option<HashMap<String, Option<MarkStyle>>>,
+    ) -> HashMap<String, MarkStyle> {
+        let mut styles = self.default_styles();
+        for (key, style) in overrides.into_iter().flatten() {
+            match style {
+                Some(style) => styles.insert(key, style),
+                None => styles.remove(&key),
+            };
+        }
+        styles
+    }
+
+    fn escape_text(self, text: &str, at_line_start: bool, out: &mut String) {
+        match self {
+            Format::Html => escape_html(text, out),
+            Format::Markdown => escape_markdown(text, at_line_start, out),
+        }
+    }
+
+    fn escape_value(self, value: &str, out: &mut String) {
+        match self {
+            Format::Html => escape_html(value, out),
+            Format::Markdown => {
+                for c in value.chars() {
+                    match c {
+                        '\\' | '(' | ')' => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                        ' ' => out.push_str("%20"),
+                        '<' => out.push_str("%3C"),
+                        '>' => out.push_str("%3E"),
+                        _ => out.push(c),
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_delimiter(self, template: &str, value: &LoroValue, out: &mut String) {
+        let mut parts = template.split("{value}");
+        out.push_str(parts.next().unwrap_or_default());
+        for part in parts {
+            let value = match value {
+                LoroValue::String(s) => s.to_string(),
+                LoroValue::Bool(b) => b.to_string(),
+                LoroValue::I64(i) => i.to_string(),
+                LoroValue::Double(d) => d.to_string(),
+                other => other.to_json(),
+            };
+            self.escape_value(&value, out);
+            out.push_str(part);
+        }
+    }
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            '\n' => out.push_str("<br>"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Backslash-escape the characters that would be read as Markdown syntax.
+/// `text` does not contain line breaks.
+fn escape_markdown(text: &str, at_line_start: bool, out: &mut String) {
+    let mut line_start = at_line_start;
+    for (i, c) in text.char_indices() {
+        let escape = match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '~' | '|' => true,
+            // Headings, block quotes, list items and setext underlines.
+            '#' | '-' | '+' | '=' if line_start => true,
+            '.' | ')' if line_start => {
+                // An ordered list item: the marker follows the leading digits.
+                i > 0 && text[..i].trim_start().chars().all(|c| c.is_ascii_digit())
+            }
+            _ => false,
+        };
+        if line_start && !c.is_whitespace() && !c.is_ascii_digit() {
+            line_start = false;
+        }
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+struct Segment {
+    text: String,
+    /// The rendered marks of the segment, sorted by key.
+    marks: Vec<(String, LoroValue)>,
+}
+
+/// Render a rich text delta, as returned by `LoroText::to_delta`.
+pub fn render(
+    delta: &[loro::TextDelta],
+    format: Format,
+    styles: &HashMap<String, MarkStyle>,
+) -> String {
+    let mut segments = Vec::new();
+    for item in delta {
+        let loro::TextDelta::Insert { insert, attributes } = item else {
+            continue;
+        };
+        let mut marks: Vec<(String, LoroValue)> = attributes
+            .iter()
+            .flatten()
+            .filter(|(key, value)| styles.contains_key(*key) && !is_off(value))
+            .filter(|(key, value)| *key != "link" || is_safe_link(value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        marks.sort_by(|a, b| a.0.cmp(&b.0));
+        if format == Format::Markdown {
+            // Markdown inline marks cannot span paragraphs: close them at
+            // every line break.
+            for (i, line) in insert.split('\n').enumerate() {
+                if i > 0 {
+                    segments.push(Segment {
+                        text: "\n".to_string(),
+                        marks: Vec::new(),
+                    });
+                }
+                if !line.is_empty() {
+                    segments.push(Segment {
+                        text: line.to_string(),
+                        marks: marks.clone(),
+                    });
+                }
+            }
+        } else {
+            segments.push(Segment {
+                text: insert.clone(),
+                marks,
+            });
+        }
+    }
+
+    // How many characters each mark of a segment still covers from the start
+    // of the segment. Marks that go further are opened first, so that they are
+    // reopened less often.
+    let mut reach: Vec<HashMap<&str, usize>> = vec![HashMap::new(); segments.len()];
+    for i in (0..segments.len()).rev() {
+        let len = segments[i].text.chars().count();
+        for (key, value) in &segments[i].marks {
+            let next = segments
+                .get(i + 1)
+                .filter(|next| next.marks.iter().any(|(k, v)| k == key && v == value))
+                .map_or(0, |_| reach[i + 1][key.as_str()]);
+            reach[i].insert(key.as_str(), len + next);
+        }
+    }
+
+    // The delimiters Markdown would not read as emphasis are replaced by HTML
+    // tags, which may in turn change how their neighbors are read.
+    let mut tagged = HashSet::new();
+    loop {
+        let writer = write(&segments, &reach, format, styles, &tagged);
+        let misread = misread(&writer.out, &writer.delimiters);
+        if misread.is_empty() {
+            return writer.out;
+        }
+        tagged.extend(misread);
+    }
+}
+
+/// An emphasis delimiter written to Markdown.
+struct Delimiter<'a> {
+    /// The byte range of the delimiter in the output.
+    range: Range<usize>,
+    opens: bool,
+    /// The index of the segment the mark is opened at, and the key of the mark.
+    mark: (usize, &'a str),
+}
+
+/// A delimiter run: consecutive delimiter characters, which Markdown reads
+/// together.
+struct Run {
+    range: Range<usize>,
+    can_open: bool,
+    can_close: bool,
+}
+
+impl Run {
+    /// The run of `out` that contains `delimiter`.
+    fn new(out: &str, delimiter: &Range<usize>) -> Self {
+        let ch = out[delimiter.clone()].chars().next().unwrap_or_default();
+        let mut start = delimiter.start;
+        while out[..start].ends_with(ch) {
+            let escapes = &out[..start - 1];
+            if (escapes.len() - escapes.trim_end_matches('\\').len()) % 2 == 1 {
+                break;
+            }
+            start -= 1;
+        }
+        let end = out.len() - out[delimiter.end..].trim_start_matches(ch).len();
+        let prev = out[..start].chars().next_back();
+        let next = out[end..].chars().next();
+        let (can_open, can_close) = markdown::flanking(ch, prev, next);
+        Run {
+            range: start..end,
+            can_open,
+            can_close,
+        }
+    }
+
+    /// Whether the CommonMark "rule of 3" forbids matching the runs.
+    fn mismatches(&self, closer: &Run) -> bool {
+        let (a, b) = (self.range.len(), closer.range.len());
+        (self.can_close || closer.can_open) && (a + b) % 3 == 0 && !(a % 3 == 0 && b % 3 == 0)
+    }
+}
+
+/// The marks of `delimiters` that Markdown would not read from `out`: a
+/// delimiter cannot open or close as it should, is in the same run as
+/// delimiters that do the opposite, opens but could close an enclosing mark
+/// with the same delimiters, or cannot be matched with the other delimiter of
+/// its mark.
+fn misread<'a>(out: &str, delimiters: &[Delimiter<'a>]) -> HashSet<(usize, &'a str)> {
+    let runs: Vec<Run> = delimiters
+        .iter()
+        .map(|delimiter| Run::new(out, &delimiter.range))
+        .collect();
+    let mut misread = HashSet::new();
+    for (i, (delimiter, run)) in delimiters.iter().zip(&runs).enumerate() {
+        let flanks = if delimiter.opens {
+            run.can_open
+        } else {
+            run.can_close
+        };
+        let mixed = delimiter.opens
+            && delimiters
+                .iter()
+                .zip(&runs)
+                .any(|(other, other_run)| !other.opens && other_run.range == run.range);
+        let ch = |d: &Delimiter| out[d.range.clone()].chars().next();
+        let closes_outer = delimiter.opens
+            && run.can_close
+            && delimiters[..i].iter().any(|outer| {
+                outer.opens
+                    && ch(outer) == ch(delimiter)
+                    && delimiters[i..]
+                        .iter()
+                        .any(|closer| !closer.opens && closer.mark == outer.mark)
+            });
+        let unmatched = !delimiter.opens
+            && delimiters[..i]
+                .iter()
+                .zip(&runs)
+                .rfind(|(opener, _)| opener.opens && opener.mark == delimiter.mark)
+                .is_some_and(|(_, opener_run)| opener_run.mismatches(run));
+        if !flanks || mixed || closes_outer || unmatched {
+            misread.insert(delimiter.mark);
+        }
+    }
+    misread
+}
+
+/// The HTML tags of the marks whose style is an emphasis delimiter of
+/// Markdown, used when the delimiter would not be read as such.
+fn emphasis_tags(style: &MarkStyle) -> Option<(&'static str, &'static str)> {
+    let ch = style.open.chars().next()?;
+    if style.open != style.close || style.open.chars().any(|c| c != ch) {
+        return None;
+    }
+    match (ch, style.open.len()) {
+        ('*' | '_', 1) => Some(("<em>", "</em>")),
+        ('*' | '_', 2) => Some(("<strong>", "</strong>")),
+        ('~', 2) => Some(("<s>", "</s>")),
+        _ => None,
+    }
+}
+
+struct Writer<'a, 't> {
+    format: Format,
+    styles: &'a HashMap<String, MarkStyle>,
+    /// The marks written with HTML tags instead of their emphasis delimiters.
+    tagged: &'t HashSet<(usize, &'a str)>,
+    out: String,
+    /// The delimiters of the open code spans, by key, when they are longer
+    /// than their style.
+    fences: HashMap<&'a str, (String, String)>,
+    delimiters: Vec<Delimiter<'a>>,
+}
+
+impl<'a> Writer<'a, '_> {
+    /// Write the delimiter opening or closing `mark`, opened at the segment
+    /// `at`.
+    fn delimiter(&mut self, at: usize, (key, value): &'a (String, LoroValue), opens: bool) {
+        let style = &self.styles[key];
+        let template = if opens { &style.open } else { &style.close };
+        if self.format == Format::Markdown {
+            if let Some((open, close)) = emphasis_tags(style) {
+                let mark = (at, key.as_str());
+                if self.tagged.contains(&mark) {
+                    self.out.push_str(if opens { open } else { close });
+                } else {
+                    let start = self.out.len();
+                    self.out.push_str(template);
+                    let range = start..self.out.len();
+                    self.delimiters.push(Delimiter { range, opens, mark });
+                }
+                return;
+            }
+        }
+        self.format.write_delimiter(template, value, &mut self.out);
+    }
+
+    fn close(&mut self, at: usize, mark: &'a (String, LoroValue)) {
+        match self.fences.remove(mark.0.as_str()) {
+            Some((_, close)) => self.out.push_str(&close),
+            None => self.delimiter(at, mark, false),
+        }
+    }
+}
+
+/// Write `segments`, rendering the marks in `tagged` with HTML tags.
+fn write<'a, 't>(
+    segments: &'a [Segment],
+    reach: &[HashMap<&str, usize>],
+    format: Format,
+    styles: &'a HashMap<String, MarkStyle>,
+    tagged: &'t HashSet<(usize, &'a str)>,
+) -> Writer<'a, 't> {
+    let mut writer = Writer {
+        format,
+        styles,
+        tagged,
+        out: String::new(),
+        fences: HashMap::new(),
+        delimiters: Vec::new(),
+    };
+    // The open marks, with the index of the segment they are opened at.
+    let mut stack: Vec<(usize, &(String, LoroValue))> = Vec::new();
+    // Markdown delimiters must not be next to whitespace on their inner side,
+    // so whitespace at the edges of marked text is moved outside of the marks.
+    let mut trailing = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let keep = stack
+            .iter()
+            .take_while(|(_, mark)| segment.marks.contains(mark))
+            .count();
+        for (at, mark) in stack.drain(keep..).rev() {
+            writer.close(at, mark);
+        }
+        writer.out.push_str(&std::mem::take(&mut trailing));
+
+        let mut text = segment.text.as_str();
+        let mut opens: Vec<_> = segment
+            .marks
+            .iter()
+            .filter(|mark| !stack.iter().any(|(_, open)| open == mark))
+            .collect();
+        if format == Format::Markdown {
+            if text.trim().is_empty() {
+                opens.clear();
+            }
+            if !opens.is_empty() {
+                let trimmed = text.trim_start();
+                writer.out.push_str(&text[..text.len() - trimmed.len()]);
+                text = trimmed;
+            }
+            let trimmed = text.trim_end();
+            trailing.push_str(&text[trimmed.len()..]);
+            text = trimmed;
+        }
+        opens.sort_by_key(|(key, _)| std::cmp::Reverse(reach[i][key.as_str()]));
+        for mark in opens {
+            let style = &styles[&mark.0];
+            if format == Format::Markdown && style.verbatim {
+                let content: String = segments[i..]
+                    .iter()
+                    .take_while(|segment| segment.marks.contains(mark))
+                    .map(|segment| segment.text.as_str())
+                    .collect();
+                if let Some(fence) = code_fence(style, content.trim()) {
+                    writer.out.push_str(&fence.0);
+                    writer.fences.insert(mark.0.as_str(), fence);
+                    stack.push((i, mark));
+                    continue;
+                }
+            }
+            writer.delimiter(i, mark, true);
+            stack.push((i, mark));
+        }
+
+        if format == Format::Markdown && stack.iter().any(|(_, (key, _))| styles[key].verbatim) {
+            writer.out.push_str(text);
+        } else {
+            let out = &writer.out;
+            let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+            let at_line_start = line.trim().is_empty();
+            format.escape_text(text, at_line_start, &mut writer.out);
+        }
+    }
+    for (at, mark) in stack.into_iter().rev() {
+        writer.close(at, mark);
+    }
+    writer.out.push_str(&trailing);
+    writer
+}
+
+/// The delimiters of a code span over `content`, if `style` delimits it with
+/// backticks and `content` needs other ones: a run of backticks longer than
+/// any in `content`, separated from it by spaces if `content` starts or ends
+/// with a backtick.
+fn code_fence(style: &MarkStyle, content: &str) -> Option<(String, String)> {
+    let is_fence = |s: &str| !s.is_empty() && s.chars().all(|c| c == '`');
+    if !is_fence(&style.open) || !is_fence(&style.close) {
+        return None;
+    }
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let pad = content.starts_with('`') || content.ends_with('`');
+    if longest < style.open.len().min(style.close.len()) && !pad {
+        return None;
+    }
+    let fence = "`".repeat(longest + 1).max(style.open.clone());
+    let space = if pad { " " } else { "" };
+    Some((format!("{fence}{space}"), format!("{space}{fence}")))
+}
+
+/// Whether the URL of a link can be rendered: it is relative, or its scheme
+/// is `http`, `https` or `mailto`. Browsers ignore whitespace and control
+/// characters in schemes, so they are ignored as well.
+fn is_safe_link(value: &LoroValue) -> bool {
+    let LoroValue::String(url) = value else {
+        return true;
+    };
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    let Some((scheme, _)) = url.split_once(':') else {
+        return true;
+    };
+    if scheme.contains(['/', '?', '#']) {
+        // The colon is in the path, query or fragment of a relative URL.
+        return true;
+    }
+    ["http", "https", "mailto"]
+        .iter()
+        .any(|safe| scheme.eq_ignore_ascii_case(safe))
+}
+
+/// Whether a mark value means that the mark is not applied.
+fn is_off(value: &LoroValue) -> bool {
+    matches!(value, LoroValue::Null | LoroValue::Bool(false))
+}
+
+/// Plain text plus mark ranges, parsed from Markdown or HTML.
+pub struct Parsed {
+    pub text: String,
+    pub marks: Vec<ParsedMark>,
+}
+
+/// The kinds of marks produced by the parsers.
+const KINDS: &[&str] = &[
+    "bold",
+    "italic",
+    "underline",
+    "strikethrough",
+    "code",
+    "link",
+    "highlight",
+];
+
+/// A mark over the Unicode range `start..end` of the parsed text.
+pub struct ParsedMark {
+    pub start: usize,
+    pub end: usize,
+    /// The syntax that produced the mark, one of [KINDS].
+    pub kind: &'static str,
+    pub value: LoroValue,
+}
+
+impl Parsed {
+    pub fn markdown(src: &str) -> Self {
+        markdown::parse(src)
+    }
+
+    pub fn html(src: &str) -> Self {
+        html::parse(src)
+    }
+
+    /// The text as runs of characters with the same marks. A mark kind is
+    /// stored under the key `keys` maps it to, and is dropped if it maps to
+    /// `None`.
+    fn runs(
+        &self,
+        keys: &HashMap<String, Option<String>>,
+    ) -> Vec<(String, HashMap<String, LoroValue>)> {
+        let mut bounds: BTreeSet<usize> = [0, self.text.chars().count()].into();
+        for mark in &self.marks {
+            bounds.insert(mark.start);
+            bounds.insert(mark.end);
+        }
+        let bounds: Vec<usize> = bounds.into_iter().collect();
+        let mut chars = self.text.chars();
+        let mut runs = Vec::new();
+        for range in bounds.windows(2) {
+            let (start, end) = (range[0], range[1]);
+            let text: String = chars.by_ref().take(end - start).collect();
+            let mut attributes = HashMap::new();
+            for mark in &self.marks {
+                if mark.start <= start && end <= mark.end {
+                    let key = match keys.get(mark.kind) {
+                        Some(Some(key)) => key.as_str(),
+                        Some(None) => continue,
+                        None => mark.kind,
+                    };
+                    attributes.insert(key.to_string(), mark.value.clone());
+                }
+            }
+            runs.push((text, attributes));
+        }
+        runs
+    }
+
+    /// Replace the content of `text` with the parsed text and marks.
+    ///
+    /// In diff mode, the content is changed with `LoroText::update`, and only
+    /// the marks that differ are marked or unmarked, so that importing a
+    /// document similar to the current content generates few ops. Marks with
+    /// keys the parsers do not produce, such as comments, are kept.
+    pub fn apply(
+        &self,
+        text: &LoroText,
+        keys: Option<HashMap<String, Option<String>>>,
+        diff: bool,
+    ) -> PyLoroResult<()> {
+        let keys = keys.unwrap_or_default();
+        let runs = self.runs(&keys);
+        let managed: BTreeSet<&str> = KINDS
+            .iter()
+            .filter_map(|kind| match keys.get(*kind) {
+                Some(key) => key.as_deref(),
+                None => Some(*kind),
+            })
+            .collect();
+        check_styles(text, &runs, diff.then_some(&managed))?;
+        if !diff {
+            let mut delta = Vec::with_capacity(runs.len() + 1);
+            let len = text.len_unicode();
+            if len > 0 {
+                delta.push(TextDelta::Delete { delete: len });
+            }
+            for (insert, attributes) in runs {
+                if insert.is_empty() {
+                    continue;
+                }
+                delta.push(TextDelta::Insert {
+                    insert,
+                    attributes: (!attributes.is_empty()).then(|| attributes.into_iter().collect()),
+                });
+            }
+            text.apply_delta(&delta)?;
+            return Ok(());
+        }
+
+        text.update(&self.text, UpdateOptions::default())
+            .map_err(|e| LoroError::new_err(e.to_string()))?;
+        // The mark changes, as `(key, value)` to apply over ranges, where a
+        // `None` value unmarks.
+        let mut changes: Vec<(String, Option<LoroValue>, usize, usize)> = Vec::new();
+        let current = text.to_delta();
+        let mut current = current.iter().filter_map(|item| match item {
+            TextDelta::Insert { insert, attributes } => Some((
+                insert.chars().count(),
+                attributes.clone().unwrap_or_default(),
+            )),
+            _ => None,
+        });
+        let mut desired = runs
+            .into_iter()
+            .map(|(text, attributes)| (text.chars().count(), attributes))
+            .filter(|(len, _)| *len > 0);
+        let mut pos = 0;
+        let mut a = current.next();
+        let mut b = desired.next();
+        while let (Some((a_len, a_attrs)), Some((b_len, b_attrs))) = (&mut a, &mut b) {
+            let len = (*a_len).min(*b_len);
+            let keys: BTreeSet<&String> = a_attrs.keys().chain(b_attrs.keys()).collect();
+            for key in keys.into_iter().filter(|k| managed.contains(k.as_str())) {
+                let want = b_attrs.get(key).filter(|v| !is_off(v));
+                if a_attrs.get(key).filter(|v| !is_off(v)) == want {
+                    continue;
+                }
+                match changes.last_mut() {
+                    Some((k, v, _, end)) if k == key && v.as_ref() == want && *end == pos => {
+                        *end = pos + len;
+                    }
+                    _ => changes.push((key.clone(), want.cloned(), pos, pos + len)),
+                }
+            }
+            pos += len;
+            *a_len -= len;
+            *b_len -= len;
+            if *a_len == 0 {
+                a = current.next();
+            }
+            if *b_len == 0 {
+                b = desired.next();
+            }
+        }
+        for (key, value, start, end) in changes {
+            match value {
+                Some(value) => text.mark(start..end, &key, value)?,
+                None => text.unmark(start..end, &key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that the keys `runs` are marked with, and in diff mode the `managed`
+/// keys of the current marks, which may be unmarked, have a style config in
+/// the document of `text`, so that the import fails before changing anything.
+fn check_styles(
+    text: &LoroText,
+    runs: &[(String, HashMap<String, LoroValue>)],
+    managed: Option<&BTreeSet<&str>>,
+) -> PyLoroResult<()> {
+    let Some(doc) = text.doc() else {
+        return Ok(());
+    };
+    let current = match managed {
+        Some(_) => text.to_delta(),
+        None => Vec::new(),
+    };
+    let current_keys = current.iter().flat_map(|item| match item {
+        TextDelta::Insert {
+            attributes: Some(attributes),
+            ..
+        } => attributes
+            .keys()
+            .filter(|key| managed.is_some_and(|managed| managed.contains(key.as_str())))
+            .collect(),
+        _ => Vec::new(),
+    });
+    let config = doc.config().text_style_config().read().unwrap();
+    for key in runs
+        .iter()
+        .flat_map(|(_, attributes)| attributes.keys())
+        .chain(current_keys)
+    {
+        if config.get(&key.as_str().into()).is_none() {
+            return Err(loro::LoroError::StyleConfigMissing(key.as_str().into()).into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,348 @@
+//! A lenient parser for the inline formatting of HTML.
+//!
+//! Formatting elements become marks and `<br>` becomes a line break. Block
+//! elements (`<p>`, `<div>`, `<li>`, ...) are separated by line breaks, the
+//! content of `<script>` and `<style>` is dropped, and every other tag is
+//! ignored while its content is kept. Whitespace is collapsed as a browser
+//! would, except inside `<pre>`.
+use loro::LoroValue;
+
+use super::{Parsed, ParsedMark};
+
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// The mark kind of a formatting element.
+fn mark_kind(tag: &str) -> Option<&'static str> {
+    Some(match tag {
+        "b" | "strong" => "bold",
+        "i" | "em" => "italic",
+        "u" | "ins" => "underline",
+        "s" | "strike" | "del" => "strikethrough",
+        "code" | "kbd" | "samp" => "code",
+        "a" => "link",
+        "mark" => "highlight",
+        _ => return None,
+    })
+}
+
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attributes: &'a str,
+    /// The length of the tag in the source.
+    len: usize,
+}
+
+struct Open {
+    tag: String,
+    start: usize,
+    kind: &'static str,
+    value: LoroValue,
+}
+
+#[derive(Default)]
+struct Builder {
+    text: String,
+    /// The length of `text` in Unicode characters.
+    len: usize,
+    marks: Vec<ParsedMark>,
+    open: Vec<Open>,
+    pre: usize,
+}
+
+impl Builder {
+    fn push(&mut self, c: char) {
+        if self.pre == 0 && c.is_ascii_whitespace() {
+            if self.text.is_empty() || self.text.ends_with([' ', '\n']) {
+                return;
+            }
+            self.text.push(' ');
+        } else {
+            self.text.push(c);
+        }
+        self.len += 1;
+    }
+
+    fn line_break(&mut self) {
+        self.text.push('\n');
+        self.len += 1;
+    }
+
+    fn block_boundary(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.line_break();
+        }
+    }
+
+    fn tag(&mut self, tag: Tag<'_>) {
+        if BLOCKS.contains(&tag.name.as_str()) {
+            self.block_boundary();
+            if tag.name == "pre" {
+                if tag.closing {
+                    self.pre = self.pre.saturating_sub(1);
+                } else {
+                    self.pre += 1;
+                }
+            }
+            return;
+        }
+        if tag.name == "br" {
+            self.line_break();
+            return;
+        }
+        let Some(kind) = mark_kind(&tag.name) else {
+            return;
+        };
+        if tag.closing {
+            if let Some(i) = self.open.iter().rposition(|open| open.tag == tag.name) {
+                let open = self.open.remove(i);
+                if open.start < self.len {
+                    self.marks.push(ParsedMark {
+                        start: open.start,
+                        end: self.len,
+                        kind: open.kind,
+                        value: open.value,
+                    });
+                }
+            }
+            return;
+        }
+        let value = if kind == "link" {
+            match attribute(tag.attributes, "href") {
+                Some(href) => LoroValue::String(href.into()),
+                // An anchor without a link.
+                None => return,
+            }
+        } else {
+            LoroValue::Bool(true)
+        };
+        self.open.push(Open {
+            tag: tag.name,
+            start: self.len,
+            kind,
+            value,
+        });
+    }
+}
+
+pub fn parse(src: &str) -> Parsed {
+    let mut builder = Builder::default();
+    let mut pos = 0;
+    while let Some(c) = src[pos..].chars().next() {
+        let rest = &src[pos..];
+        if c == '<' {
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                pos += comment.find("-->").map_or(rest.len(), |end| end + 7);
+                continue;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                pos += rest.find('>').map_or(rest.len(), |end| end + 1);
+                continue;
+            }
+            if let Some(tag) = parse_tag(rest) {
+                pos += tag.len;
+                if !tag.closing && (tag.name == "script" || tag.name == "style") {
+                    let close = format!("</{}", tag.name);
+                    pos += find_ignore_case(&src[pos..], &close).unwrap_or(src.len() - pos);
+                    continue;
+                }
+                builder.tag(tag);
+                continue;
+            }
+        }
+        if c == '&' {
+            if let Some((decoded, len)) = entity(rest) {
+                builder.push(decoded);
+                pos += len;
+                continue;
+            }
+        }
+        builder.push(c);
+        pos += c.len_utf8();
+    }
+
+    // Drop the whitespace left at the end of the text.
+    let trimmed = builder
+        .text
+        .trim_end_matches(|c: char| c.is_ascii_whitespace())
+        .len();
+    let removed = builder.text[trimmed..].chars().count();
+    builder.text.truncate(trimmed);
+    let len = builder.len - removed;
+    let mut marks = builder.marks;
+    for open in builder.open {
+        // Unclosed elements end with the document.
+        marks.push(ParsedMark {
+            start: open.start,
+            end: len,
+            kind: open.kind,
+            value: open.value,
+        });
+    }
+    for mark in &mut marks {
+        mark.end = mark.end.min(len);
+    }
+    marks.retain(|m| m.start < m.end);
+    Parsed {
+        text: builder.text,
+        marks,
+    }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.char_indices().map(|(i, _)| i).find(|&i| {
+        haystack
+            .get(i..i + needle.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(needle))
+    })
+}
+
+fn parse_tag(src: &str) -> Option<Tag<'_>> {
+    let mut rest = &src[1..];
+    let closing = rest.starts_with('/');
+    if closing {
+        rest = &rest[1..];
+    }
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name = rest[..name_len].to_ascii_lowercase();
+    let after_name = &rest[name_len..];
+    // Find the end of the tag, skipping over quoted attribute values.
+    let mut quote = None;
+    for (i, c) in after_name.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => {
+                return Some(Tag {
+                    name,
+                    closing,
+                    attributes: after_name[..i].trim_end_matches('/'),
+                    len: src.len() - after_name.len() + i + 1,
+                });
+            }
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Read the decoded value of the attribute `name`.
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+        let key_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = &rest[..key_len];
+        rest = rest[key_len..].trim_start();
+        let mut value = "";
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (v, remaining) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let end = after[1..].find(q).map_or(after.len(), |e| e + 1);
+                    (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = v;
+            rest = remaining.trim_start();
+        }
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode_entities(value));
+        }
+    }
+    None
+}
+
+fn decode_entities(src: &str) -> String {
+    let mut out = String::new();
+    let mut pos = 0;
+    while let Some(c) = src[pos..].chars().next() {
+        if c == '&' {
+            if let Some((decoded, len)) = entity(&src[pos..]) {
+                out.push(decoded);
+                pos += len;
+                continue;
+            }
+        }
+        out.push(c);
+        pos += c.len_utf8();
+    }
+    out
+}
+
+/// Decode the character reference at the start of `src`, returning the
+/// character and the length of the reference.
+fn entity(src: &str) -> Option<(char, usize)> {
+    let end = src.find(';').filter(|&end| end <= 32)?;
+    let name = &src[1..end];
+    let c = if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+    } else {
+        match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => '\u{a0}',
+            "ndash" => '–',
+            "mdash" => '—',
+            "hellip" => '…',
+            "copy" => '©',
+            "reg" => '®',
+            "trade" => '™',
+            "lsquo" => '‘',
+            "rsquo" => '’',
+            "ldquo" => '“',
+            "rdquo" => '”',
+            _ => return None,
+        }
+    };
+    Some((c, end + 1))
+}
@@ -0,0 +1,470 @@
+//! A parser for the inline syntax of Markdown.
+//!
+//! It follows the CommonMark rules for emphasis, code spans, links and
+//! backslash escapes, plus `~~strikethrough~~` and the HTML tags `<u>`,
+//! `<strong>`, `<em>` and `<s>`, which `render` writes when delimiters would
+//! not be read as emphasis. Block syntax (headings, lists, quotes, ...) has no
+//! rich text equivalent and is kept as text.
+use loro::LoroValue;
+
+use super::{Parsed, ParsedMark};
+
+enum Node {
+    Text(String),
+    /// A run of `*`, `_` or `~` that may open or close emphasis.
+    Delim {
+        ch: char,
+        /// The number of characters not consumed by emphasis yet.
+        count: usize,
+        /// The length of the run in the source.
+        len: usize,
+        can_open: bool,
+        can_close: bool,
+    },
+}
+
+/// A mark between the start of two nodes.
+struct NodeMark {
+    start: usize,
+    end: usize,
+    kind: &'static str,
+    value: LoroValue,
+}
+
+struct Bracket {
+    node: usize,
+    active: bool,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    nodes: Vec<Node>,
+    pending: String,
+    marks: Vec<NodeMark>,
+    brackets: Vec<Bracket>,
+    /// The open HTML tags, as the kind of their mark and their node.
+    tags: Vec<(&'static str, usize)>,
+}
+
+/// The HTML tags read as marks, and the kinds of these marks.
+const TAGS: &[(&str, &str)] = &[
+    ("u", "underline"),
+    ("strong", "bold"),
+    ("em", "italic"),
+    ("s", "strikethrough"),
+];
+
+pub fn parse(src: &str) -> Parsed {
+    let mut parser = Parser {
+        src,
+        pos: 0,
+        nodes: Vec::new(),
+        pending: String::new(),
+        marks: Vec::new(),
+        brackets: Vec::new(),
+        tags: Vec::new(),
+    };
+    parser.run();
+    parser.finish()
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+/// Whether a delimiter run of `ch` between the characters `prev` and `next`
+/// can open and close emphasis. A missing character is a line boundary.
+pub fn flanking(ch: char, prev: Option<char>, next: Option<char>) -> (bool, bool) {
+    let next_ws = next.is_none_or(char::is_whitespace);
+    let prev_ws = prev.is_none_or(char::is_whitespace);
+    let next_punct = next.is_some_and(is_punctuation);
+    let prev_punct = prev.is_some_and(is_punctuation);
+    let left = !next_ws && (!next_punct || prev_ws || prev_punct);
+    let right = !prev_ws && (!prev_punct || next_ws || next_punct);
+    if ch == '_' {
+        (
+            left && (!right || prev_punct),
+            right && (!left || next_punct),
+        )
+    } else {
+        (left, right)
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// Start a new node at the current position, and return its index.
+    fn flush(&mut self) -> usize {
+        if !self.pending.is_empty() {
+            let text = std::mem::take(&mut self.pending);
+            self.nodes.push(Node::Text(text));
+        }
+        self.nodes.len()
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        let index = self.flush();
+        self.nodes.push(node);
+        index
+    }
+
+    fn run(&mut self) {
+        while let Some(c) = self.rest().chars().next() {
+            match c {
+                '\\' => self.escape(),
+                '`' => self.code_span(),
+                '*' | '_' | '~' => self.delimiter_run(c),
+                '[' => {
+                    self.pos += 1;
+                    let node = self.push(Node::Text("[".into()));
+                    self.brackets.push(Bracket { node, active: true });
+                }
+                ']' => self.close_bracket(),
+                '<' => self.angle(),
+                _ => {
+                    self.pending.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn escape(&mut self) {
+        self.pos += 1;
+        match self.rest().chars().next() {
+            Some(c) if c.is_ascii_punctuation() => {
+                self.pending.push(c);
+                self.pos += 1;
+            }
+            // A hard line break.
+            Some('\n') => {}
+            _ => self.pending.push('\\'),
+        }
+    }
+
+    fn code_span(&mut self) {
+        let len = self.rest().len() - self.rest().trim_start_matches('`').len();
+        let start = self.pos + len;
+        let mut search = start;
+        while let Some(offset) = self.src[search..].find('`') {
+            let run_start = search + offset;
+            let run_len =
+                self.src[run_start..].len() - self.src[run_start..].trim_start_matches('`').len();
+            if run_len == len {
+                let mut content = self.src[start..run_start].replace('\n', " ");
+                if content.len() >= 2
+                    && content.starts_with(' ')
+                    && content.ends_with(' ')
+                    && !content.trim().is_empty()
+                {
+                    content = content[1..content.len() - 1].to_string();
+                }
+                let node = self.push(Node::Text(content));
+                self.marks.push(NodeMark {
+                    start: node,
+                    end: node + 1,
+                    kind: "code",
+                    value: LoroValue::Bool(true),
+                });
+                self.pos = run_start + run_len;
+                return;
+            }
+            search = run_start + run_len;
+        }
+        // No closing run: the backticks are literal.
+        self.pending.push_str(&"`".repeat(len));
+        self.pos = start;
+    }
+
+    fn delimiter_run(&mut self, ch: char) {
+        let prev = self.src[..self.pos].chars().next_back();
+        let len = self.rest().len() - self.rest().trim_start_matches(ch).len();
+        self.pos += len;
+        let next = self.rest().chars().next();
+        let (can_open, can_close) = flanking(ch, prev, next);
+        self.push(Node::Delim {
+            ch,
+            count: len,
+            len,
+            can_open,
+            can_close,
+        });
+    }
+
+    fn close_bracket(&mut self) {
+        self.pos += 1;
+        let Some(bracket) = self.brackets.pop() else {
+            self.pending.push(']');
+            return;
+        };
+        let destination = if bracket.active {
+            link_destination(self.rest())
+        } else {
+            None
+        };
+        let Some((url, consumed)) = destination else {
+            self.pending.push(']');
+            return;
+        };
+        self.pos += consumed;
+        let end = self.flush();
+        self.process_emphasis(bracket.node + 1);
+        self.nodes[bracket.node] = Node::Text(String::new());
+        self.marks.push(NodeMark {
+            start: bracket.node + 1,
+            end,
+            kind: "link",
+            value: LoroValue::String(url.into()),
+        });
+        // Links cannot contain other links.
+        for bracket in &mut self.brackets {
+            bracket.active = false;
+        }
+    }
+
+    /// The HTML tags of [TAGS] and autolinks. Other inline HTML is kept as
+    /// text.
+    fn angle(&mut self) {
+        let rest = self.rest();
+        for &(tag, kind) in TAGS {
+            let open = format!("<{tag}>");
+            let close = format!("</{tag}>");
+            if rest.starts_with(&open) {
+                // The tag is kept as text unless a closing tag closes it, like `[`.
+                self.pos += open.len();
+                let node = self.push(Node::Text(open));
+                self.tags.push((kind, node));
+                return;
+            }
+            let Some(i) = self.tags.iter().rposition(|(k, _)| *k == kind) else {
+                continue;
+            };
+            if rest.starts_with(&close) {
+                self.pos += close.len();
+                let end = self.flush();
+                let (_, start) = self.tags.remove(i);
+                self.nodes[start] = Node::Text(String::new());
+                self.marks.push(NodeMark {
+                    start: start + 1,
+                    end,
+                    kind,
+                    value: LoroValue::Bool(true),
+                });
+                return;
+            }
+        }
+        if let Some(url) = autolink(rest) {
+            self.pos += url.len() + 2;
+            let node = self.push(Node::Text(url.to_string()));
+            self.marks.push(NodeMark {
+                start: node,
+                end: node + 1,
+                kind: "link",
+                value: LoroValue::String(url.into()),
+            });
+        } else {
+            self.pending.push('<');
+            self.pos += 1;
+        }
+    }
+
+    /// Match the delimiter runs after the node `bottom` into emphasis, as
+    /// described by the CommonMark spec.
+    fn process_emphasis(&mut self, bottom: usize) {
+        let mut closer = bottom;
+        while closer < self.nodes.len() {
+            let Node::Delim {
+                ch,
+                count,
+                len,
+                can_open: closer_open,
+                can_close: true,
+            } = self.nodes[closer]
+            else {
+                closer += 1;
+                continue;
+            };
+            if count == 0 {
+                closer += 1;
+                continue;
+            }
+            let opener = (bottom..closer).rev().find(|&i| match self.nodes[i] {
+                Node::Delim {
+                    ch: c,
+                    count: n,
+                    len: opener_len,
+                    can_open: true,
+                    can_close: opener_close,
+                } if c == ch && n > 0 => {
+                    let multiple_of_3 = (opener_close || closer_open)
+                        && (opener_len + len) % 3 == 0
+                        && !(opener_len % 3 == 0 && len % 3 == 0);
+                    let strike_ok = ch != '~' || (n >= 2 && count >= 2);
+                    !multiple_of_3 && strike_ok
+                }
+                _ => false,
+            });
+            let Some(opener) = opener else {
+                closer += 1;
+                continue;
+            };
+            let Node::Delim { count: n, .. } = self.nodes[opener] else {
+                unreachable!()
+            };
+            let used = if n >= 2 && count >= 2 { 2 } else { 1 };
+            let kind = match (ch, used) {
+                ('~', _) => "strikethrough",
+                (_, 2) => "bold",
+                _ => "italic",
+            };
+            self.marks.push(NodeMark {
+                start: opener + 1,
+                end: closer,
+                kind,
+                value: LoroValue::Bool(true),
+            });
+            for i in [opener, closer] {
+                if let Node::Delim { count, .. } = &mut self.nodes[i] {
+                    *count -= used;
+                }
+            }
+            // The delimiters between the opener and the closer are literal.
+            for node in &mut self.nodes[opener + 1..closer] {
+                if let Node::Delim {
+                    can_open,
+                    can_close,
+                    ..
+                } = node
+                {
+                    *can_open = false;
+                    *can_close = false;
+                }
+            }
+        }
+        for node in &mut self.nodes[bottom..] {
+            if let Node::Delim {
+                can_open,
+                can_close,
+                ..
+            } = node
+            {
+                *can_open = false;
+                *can_close = false;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Parsed {
+        self.flush();
+        self.process_emphasis(0);
+        let mut text = String::new();
+        let mut offsets = Vec::with_capacity(self.nodes.len() + 1);
+        let mut len = 0;
+        for node in &self.nodes {
+            offsets.push(len);
+            match node {
+                Node::Text(s) => {
+                    text.push_str(s);
+                    len += s.chars().count();
+                }
+                Node::Delim { ch, count, .. } => {
+                    text.extend(std::iter::repeat_n(*ch, *count));
+                    len += count;
+                }
+            }
+        }
+        offsets.push(len);
+        let marks = self
+            .marks
+            .into_iter()
+            .map(|m| ParsedMark {
+                start: offsets[m.start],
+                end: offsets[m.end],
+                kind: m.kind,
+                value: m.value,
+            })
+            .collect();
+        Parsed { text, marks }
+    }
+}
+
+/// Parse `(destination "optional title")` following the `]` of a link.
+/// Returns the destination and the number of bytes consumed.
+fn link_destination(src: &str) -> Option<(String, usize)> {
+    let trimmed = src.strip_prefix('(')?.trim_start();
+    let mut url = String::new();
+    let mut end = trimmed.len();
+    let mut chars = trimmed.char_indices();
+    if let Some(inner) = trimmed.strip_prefix('<') {
+        chars = inner.char_indices();
+        end = loop {
+            let (i, c) = chars.next()?;
+            match c {
+                '>' => break i + 2,
+                '\n' | '<' => return None,
+                '\\' => match chars.clone().next() {
+                    Some((_, e)) if e.is_ascii_punctuation() => {
+                        chars.next();
+                        url.push(e);
+                    }
+                    _ => url.push(c),
+                },
+                _ => url.push(c),
+            }
+        };
+    } else {
+        let mut depth = 0usize;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                _ if c.is_whitespace() || c.is_ascii_control() => {
+                    end = i;
+                    break;
+                }
+                ')' if depth == 0 => {
+                    end = i;
+                    break;
+                }
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                '\\' => {
+                    if let Some((_, e)) = chars
+                        .clone()
+                        .next()
+                        .filter(|(_, e)| e.is_ascii_punctuation())
+                    {
+                        chars.next();
+                        url.push(e);
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            url.push(c);
+        }
+    }
+    let mut rest = trimmed[end..].trim_start();
+    if let Some(quote @ ('"' | '\'')) = rest.chars().next() {
+        let close = rest[1..].find(quote)?;
+        rest = rest[close + 2..].trim_start();
+    }
+    let rest = rest.strip_prefix(')')?;
+    Some((url, src.len() - rest.len()))
+}
+
+/// Parse an autolink such as `<https://loro.dev>`, returning the URL.
+fn autolink(src: &str) -> Option<&str> {
+    let end = src.find('>')?;
+    let url = &src[1..end];
+    let (scheme, rest) = url.split_once(':')?;
+    let valid_scheme = (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'));
+    let valid = valid_scheme && !rest.chars().any(|c| c.is_whitespace() || c == '<');
+    valid.then_some(url)
+}
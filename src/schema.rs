@@ -0,0 +1,589 @@
+//! Declarative schemas for the layout of a document.
+//!
+//! A [DocSchema] describes the root containers of a document, the type of
+//! every container below them, the keys allowed in maps and the types of the
+//! values. `LoroDoc.validate` reports every place where a document does not
+//! match, addressed by the path returned by `get_path_to_container`, and
+//! `LoroDoc.migrate` upgrades documents written with an older version of the
+//! schema.
+use std::fmt;
+
+use loro::{ContainerTrait, ContainerType, ValueOrContainer};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyType},
+};
+
+use crate::{
+    doc::LoroDoc,
+    err::{PyLoroResult, SchemaError},
+    event::Index,
+    model::{value_type, ValueType},
+    value::ContainerID,
+};
+
+/// The root map holding the version of the schema of a document.
+pub const VERSION_ROOT: &str = "$schema";
+const VERSION_KEY: &str = "version";
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Schema>()?;
+    m.add_class::<DocSchema>()?;
+    m.add_class::<SchemaViolation>()?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+enum SchemaNode {
+    Any,
+    Value {
+        ty: ValueType,
+        /// The annotation the type was built from, for error messages.
+        name: String,
+    },
+    Text,
+    Counter,
+    List(Box<SchemaNode>),
+    MovableList(Box<SchemaNode>),
+    Map {
+        fields: Vec<(String, SchemaNode)>,
+        required: Vec<String>,
+        extra: Option<Box<SchemaNode>>,
+    },
+    Tree(Option<Box<SchemaNode>>),
+    OneOf(Vec<SchemaNode>),
+}
+
+impl fmt::Display for SchemaNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaNode::Any => write!(f, "any"),
+            SchemaNode::Value { name, .. } => write!(f, "{name}"),
+            SchemaNode::Text => write!(f, "LoroText"),
+            SchemaNode::Counter => write!(f, "LoroCounter"),
+            SchemaNode::List(_) => write!(f, "LoroList"),
+            SchemaNode::MovableList(_) => write!(f, "LoroMovableList"),
+            SchemaNode::Map { .. } => write!(f, "LoroMap"),
+            SchemaNode::Tree(_) => write!(f, "LoroTree"),
+            SchemaNode::OneOf(options) => {
+                for (i, option) in options.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{option}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn describe_value(value: &loro::LoroValue) -> String {
+    use loro::LoroValue as V;
+    match value {
+        V::Null => "None".into(),
+        V::Bool(_) => "bool".into(),
+        V::Double(_) => "float".into(),
+        V::I64(_) => "int".into(),
+        V::Binary(_) => "bytes".into(),
+        V::String(_) => "str".into(),
+        V::List(_) => "list".into(),
+        V::Map(_) => "dict".into(),
+        V::Container(id) => describe_container(id.container_type()),
+    }
+}
+
+fn describe_container(ty: ContainerType) -> String {
+    match ty {
+        ContainerType::Text => "LoroText".into(),
+        ContainerType::Map => "LoroMap".into(),
+        ContainerType::List => "LoroList".into(),
+        ContainerType::MovableList => "LoroMovableList".into(),
+        ContainerType::Tree => "LoroTree".into(),
+        ContainerType::Counter => "LoroCounter".into(),
+        ContainerType::Unknown(_) => "LoroUnknown".into(),
+    }
+}
+
+/// The schema of a container or of a value.
+///
+/// Schemas are built with the static methods of this class and combined
+/// into a `DocSchema`.
+#[pyclass(frozen, str)]
+#[derive(Debug, Clone)]
+pub struct Schema(SchemaNode);
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[pymethods]
+impl Schema {
+    /// Accept any value or container.
+    #[staticmethod]
+    pub fn any() -> Self {
+        Self(SchemaNode::Any)
+    }
+
+    /// A value matching the type annotation `annotation`, checked like the
+    /// fields of a `LoroModel`: `int` is accepted for `float`, and
+    /// `list[...]`, `dict[str, ...]` and `Optional`/`Union` are checked
+    /// recursively.
+    #[staticmethod]
+    pub fn value(annotation: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let name = match annotation.downcast::<PyType>() {
+            Ok(ty) => ty.qualname()?.to_string(),
+            Err(_) => annotation.str()?.to_string(),
+        };
+        Ok(Self(SchemaNode::Value {
+            ty: value_type(annotation)?,
+            name,
+        }))
+    }
+
+    /// A `LoroText`.
+    #[staticmethod]
+    pub fn text() -> Self {
+        Self(SchemaNode::Text)
+    }
+
+    /// A `LoroCounter`.
+    #[staticmethod]
+    pub fn counter() -> Self {
+        Self(SchemaNode::Counter)
+    }
+
+    /// A `LoroList` whose elements match `item`.
+    #[staticmethod]
+    pub fn list(item: Schema) -> Self {
+        Self(SchemaNode::List(Box::new(item.0)))
+    }
+
+    /// A `LoroMovableList` whose elements match `item`.
+    #[staticmethod]
+    pub fn movable_list(item: Schema) -> Self {
+        Self(SchemaNode::MovableList(Box::new(item.0)))
+    }
+
+    /// A `LoroMap` with the given fields.
+    ///
+    /// The keys in `required` must be present. Keys that are not fields are
+    /// rejected, unless `extra` is given, in which case their entries must
+    /// match it.
+    #[staticmethod]
+    #[pyo3(signature = (fields, *, required=None, extra=None))]
+    pub fn map(
+        fields: &Bound<'_, PyDict>,
+        required: Option<Vec<String>>,
+        extra: Option<Schema>,
+    ) -> PyResult<Self> {
+        let fields = fields
+            .iter()
+            .map(|(k, v)| Ok((k.extract()?, v.extract::<Schema>()?.0)))
+            .collect::<PyResult<Vec<(String, SchemaNode)>>>()?;
+        let required = required.unwrap_or_default();
+        if extra.is_none() {
+            if let Some(key) = required
+                .iter()
+                .find(|key| !fields.iter().any(|(k, _)| k == *key))
+            {
+                return Err(PyValueError::new_err(format!(
+                    "required key {key:?} is not a field"
+                )));
+            }
+        }
+        Ok(Self(SchemaNode::Map {
+            fields,
+            required,
+            extra: extra.map(|s| Box::new(s.0)),
+        }))
+    }
+
+    /// A `LoroTree`. If `meta` is given, the metadata map of every node must
+    /// match it.
+    #[staticmethod]
+    #[pyo3(signature = (meta=None))]
+    pub fn tree(meta: Option<Schema>) -> Self {
+        Self(SchemaNode::Tree(meta.map(|s| Box::new(s.0))))
+    }
+
+    /// Accept anything matching one of `options`.
+    #[staticmethod]
+    #[pyo3(signature = (*options))]
+    pub fn one_of(options: Vec<Schema>) -> Self {
+        Self(SchemaNode::OneOf(
+            options.into_iter().map(|s| s.0).collect(),
+        ))
+    }
+}
+
+/// A place where a document does not match its schema.
+#[pyclass(get_all, frozen, str)]
+#[derive(Debug, Clone)]
+pub struct SchemaViolation {
+    /// The path from the root of the document, as returned by
+    /// `get_path_to_container`, followed by the key or index of the offending
+    /// value if it is not a container.
+    pub path: Vec<Index>,
+    /// The innermost container on the path.
+    pub container: Option<ContainerID>,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, index) in self.path.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            match index {
+                Index::Key { key } => write!(f, "{key}")?,
+                Index::Seq { index } => write!(f, "{index}")?,
+                Index::Node { target } => write!(f, "{}@{}", target.counter, target.peer)?,
+            }
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The schema of a document.
+///
+/// `roots` maps the name of every root container to its schema. With
+/// `strict`, non-empty root containers that are not in `roots` are reported.
+///
+/// `version` is the current version of the schema, and `migrations` maps a
+/// version `n` to a function that rewrites a document of version `n - 1`
+/// into version `n`. The version of a document is stored under `"version"`
+/// in the `"$schema"` root map; documents without one are at version 0.
+#[pyclass(frozen)]
+pub struct DocSchema {
+    roots: Vec<(String, SchemaNode)>,
+    strict: bool,
+    version: i64,
+    migrations: Vec<(i64, Py<PyAny>)>,
+}
+
+#[pymethods]
+impl DocSchema {
+    #[new]
+    #[pyo3(signature = (roots, *, strict=true, version=0, migrations=None))]
+    pub fn new(
+        roots: &Bound<'_, PyDict>,
+        strict: bool,
+        version: i64,
+        migrations: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let roots = roots
+            .iter()
+            .map(|(k, v)| Ok((k.extract()?, v.extract::<Schema>()?.0)))
+            .collect::<PyResult<Vec<(String, SchemaNode)>>>()?;
+        let mut sorted = Vec::new();
+        for (k, f) in migrations.into_iter().flatten() {
+            let target: i64 = k.extract()?;
+            if !(1..=version).contains(&target) {
+                return Err(PyValueError::new_err(format!(
+                    "migration to version {target} is outside of 1..={version}"
+                )));
+            }
+            sorted.push((target, f.unbind()));
+        }
+        sorted.sort_by_key(|(v, _)| *v);
+        Ok(Self {
+            roots,
+            strict,
+            version,
+            migrations: sorted,
+        })
+    }
+
+    /// The current version of the schema.
+    #[getter]
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// The version of the schema `doc` was written with.
+    #[staticmethod]
+    pub fn version_of(doc: &LoroDoc) -> i64 {
+        stored_version(&doc.doc)
+    }
+}
+
+fn stored_version(doc: &loro::LoroDoc) -> i64 {
+    // Looked up by path so that the root map is not created.
+    let path = [
+        loro::Index::Key(VERSION_ROOT.into()),
+        loro::Index::Key(VERSION_KEY.into()),
+    ];
+    match doc.get_by_path(&path) {
+        Some(ValueOrContainer::Value(loro::LoroValue::I64(v))) => v,
+        _ => 0,
+    }
+}
+
+fn is_empty(value: &loro::LoroValue) -> bool {
+    use loro::LoroValue as V;
+    match value {
+        V::Null => true,
+        V::String(s) => s.is_empty(),
+        V::List(l) => l.is_empty(),
+        V::Map(m) => m.is_empty(),
+        V::Double(d) => *d == 0.0,
+        _ => false,
+    }
+}
+
+struct Validator<'a> {
+    doc: &'a loro::LoroDoc,
+    violations: Vec<SchemaViolation>,
+}
+
+impl Validator<'_> {
+    fn path(&self, id: &loro::ContainerID) -> Vec<Index> {
+        self.doc
+            .get_path_to_container(id)
+            .into_iter()
+            .flatten()
+            .map(|(_, index)| Index::from(&index))
+            .collect()
+    }
+
+    fn report(&mut self, path: Vec<Index>, container: Option<loro::ContainerID>, message: String) {
+        self.violations.push(SchemaViolation {
+            path,
+            container: container.map(ContainerID::from),
+            message,
+        });
+    }
+
+    /// Check the entry `index` of the container at `parent`.
+    fn check(
+        &mut self,
+        slot: ValueOrContainer,
+        schema: &SchemaNode,
+        parent: (&[Index], Option<&loro::ContainerID>),
+        index: Index,
+    ) {
+        let value_path = || {
+            let mut path = parent.0.to_vec();
+            path.push(index.clone());
+            path
+        };
+        match (schema, slot) {
+            (SchemaNode::Any, _) => {}
+            (SchemaNode::OneOf(options), slot) => {
+                let matches = options.iter().any(|option| {
+                    let mut validator = Validator {
+                        doc: self.doc,
+                        violations: Vec::new(),
+                    };
+                    validator.check(slot.clone(), option, parent, index.clone());
+                    validator.violations.is_empty()
+                });
+                if !matches {
+                    let found = match &slot {
+                        ValueOrContainer::Value(v) => describe_value(v),
+                        ValueOrContainer::Container(c) => describe_container(c.get_type()),
+                    };
+                    self.report(
+                        value_path(),
+                        parent.1.cloned(),
+                        format!("expected {schema}, found {found}"),
+                    );
+                }
+            }
+            (SchemaNode::Value { ty, .. }, ValueOrContainer::Value(v)) => {
+                if ty.coerce(v.clone()).is_err() {
+                    self.report(
+                        value_path(),
+                        parent.1.cloned(),
+                        format!("expected {schema}, found {}", describe_value(&v)),
+                    );
+                }
+            }
+            (_, ValueOrContainer::Value(v)) => self.report(
+                value_path(),
+                parent.1.cloned(),
+                format!("expected {schema}, found {}", describe_value(&v)),
+            ),
+            (_, ValueOrContainer::Container(c)) => self.container(c, schema),
+        }
+    }
+
+    fn container(&mut self, container: loro::Container, schema: &SchemaNode) {
+        let id = container.id();
+        let path = self.path(&id);
+        let parent = (path.as_slice(), Some(&id));
+        match (schema, container) {
+            (SchemaNode::Text, loro::Container::Text(_))
+            | (SchemaNode::Counter, loro::Container::Counter(_)) => {}
+            (SchemaNode::List(item), loro::Container::List(list)) => {
+                for i in 0..list.len() {
+                    if let Some(slot) = list.get(i) {
+                        self.check(slot, item, parent, Index::Seq { index: i as u32 });
+                    }
+                }
+            }
+            (SchemaNode::MovableList(item), loro::Container::MovableList(list)) => {
+                for i in 0..list.len() {
+                    if let Some(slot) = list.get(i) {
+                        self.check(slot, item, parent, Index::Seq { index: i as u32 });
+                    }
+                }
+            }
+            (
+                SchemaNode::Map {
+                    fields,
+                    required,
+                    extra,
+                },
+                loro::Container::Map(map),
+            ) => {
+                let mut entries = Vec::new();
+                map.for_each(|key, slot| entries.push((key.to_string(), slot)));
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for key in required {
+                    if !entries.iter().any(|(k, _)| k == key) {
+                        self.report(
+                            path.clone(),
+                            Some(id.clone()),
+                            format!("missing required key {key:?}"),
+                        );
+                    }
+                }
+                for (key, slot) in entries {
+                    let field = fields.iter().find(|(k, _)| *k == key).map(|(_, s)| s);
+                    match field.or(extra.as_deref()) {
+                        Some(schema) => self.check(slot, schema, parent, Index::Key { key }),
+                        None => {
+                            let mut key_path = path.clone();
+                            key_path.push(Index::Key { key: key.clone() });
+                            self.report(
+                                key_path,
+                                Some(id.clone()),
+                                format!("unexpected key {key:?}"),
+                            );
+                        }
+                    }
+                }
+            }
+            (SchemaNode::Tree(meta), loro::Container::Tree(tree)) => {
+                if let Some(meta) = meta {
+                    for node in tree.get_nodes(false) {
+                        if let Ok(map) = tree.get_meta(node.id) {
+                            self.container(loro::Container::Map(map), meta);
+                        }
+                    }
+                }
+            }
+            (schema, container) => self.report(
+                path.clone(),
+                Some(id),
+                format!(
+                    "expected {schema}, found {}",
+                    describe_container(container.get_type())
+                ),
+            ),
+        }
+    }
+}
+
+impl DocSchema {
+    pub fn validate(&self, doc: &loro::LoroDoc) -> Vec<SchemaViolation> {
+        let mut validator = Validator {
+            doc,
+            violations: Vec::new(),
+        };
+        let loro::LoroValue::Map(roots) = doc.get_deep_value() else {
+            return Vec::new();
+        };
+        let mut roots: Vec<_> = roots.iter().collect();
+        roots.sort_by_key(|(name, _)| *name);
+        for (name, value) in roots {
+            if name == VERSION_ROOT {
+                continue;
+            }
+            let path = [loro::Index::Key(name.as_str().into())];
+            let Some(slot) = doc.get_by_path(&path) else {
+                continue;
+            };
+            match self.roots.iter().find(|(n, _)| n == name) {
+                Some((_, schema)) => {
+                    let index = Index::Key { key: name.clone() };
+                    validator.check(slot, schema, (&[], None), index);
+                }
+                None if self.strict && !is_empty(value) => {
+                    let id = match slot {
+                        ValueOrContainer::Container(c) => Some(c.id()),
+                        ValueOrContainer::Value(_) => None,
+                    };
+                    validator.report(
+                        vec![Index::Key { key: name.clone() }],
+                        id,
+                        "unexpected root container".into(),
+                    );
+                }
+                None => {}
+            }
+        }
+        validator.violations
+    }
+
+    /// Run the pending migrations of `doc` in a single commit, and return
+    /// the version it was at.
+    ///
+    /// The migrations run on a fork of the document, so that `doc` is left
+    /// untouched if one of them fails or if the result does not match the
+    /// schema. The changes are then applied to `doc` with `apply_diff`.
+    pub fn migrate(&self, py: Python<'_>, doc: &loro::LoroDoc) -> PyLoroResult<i64> {
+        doc.commit();
+        let current = stored_version(doc);
+        if current == self.version {
+            return Ok(current);
+        }
+        if current > self.version {
+            return Err(SchemaError::new_err(format!(
+                "the document is at version {current}, which is newer than the schema version {}",
+                self.version
+            ))
+            .into());
+        }
+        if doc.is_detached() {
+            return Err(loro::LoroError::EditWhenDetached.into());
+        }
+
+        let fork = doc.fork();
+        let before = doc.state_frontiers();
+        let fork_py = Py::new(py, LoroDoc::from(fork.clone()))?;
+        for (version, migration) in &self.migrations {
+            if *version > current {
+                migration.call1(py, (fork_py.clone_ref(py),))?;
+            }
+        }
+        fork.get_map(VERSION_ROOT)
+            .insert(VERSION_KEY, self.version)?;
+        fork.commit();
+
+        let violations = self.validate(&fork);
+        if !violations.is_empty() {
+            return Err(crate::err::schema_error(
+                format!(
+                    "the migration to version {} produced an invalid document",
+                    self.version
+                ),
+                violations,
+            )
+            .into());
+        }
+        let diff = fork.diff(&before, &fork.state_frontiers())?;
+        doc.apply_diff(diff)?;
+        doc.commit_with(loro::CommitOptions::new().commit_msg(&format!(
+            "Migrate from schema version {current} to {}",
+            self.version
+        )));
+        Ok(current)
+    }
+}
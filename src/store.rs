@@ -0,0 +1,329 @@
+//! Persisting a document as a snapshot plus an append-only log of updates.
+//!
+//! A [LoroStore] subscribes to the local updates of a document and appends
+//! each of them to a log. When the log grows beyond a threshold, it is
+//! compacted: a new snapshot of the document replaces the old one and the log
+//! is cleared. Opening the store imports the snapshot, then the log.
+//!
+//! Both steps are crash-safe. An update is durable once it has been appended
+//! (with `fsync=True`), and compaction never leaves the store without either
+//! the old or the new snapshot. Updates that end up both in the snapshot and
+//! in the log are imported twice, which is a no-op.
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use loro::{LoroDoc as LoroDocInner, VersionVector};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    doc::LoroDoc,
+    err::{PyLoroError, PyLoroResult},
+};
+
+mod dir;
+mod sqlite;
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<LoroStore>()?;
+    Ok(())
+}
+
+/// The default size of the log, in bytes, above which it is compacted.
+pub const DEFAULT_COMPACT_THRESHOLD: usize = 1 << 20;
+
+/// The snapshot, if any, and the updates appended to the log since.
+type Stored = (Option<Vec<u8>>, Vec<Vec<u8>>);
+
+/// Where a [LoroStore] keeps its snapshot and its log.
+trait Backend: Send {
+    /// Read the snapshot and the log.
+    fn load(&mut self) -> io::Result<Stored>;
+    /// Append an update to the log. It must be durable on return if `sync`.
+    fn append(&mut self, update: &[u8], sync: bool) -> io::Result<()>;
+    /// Make the appended updates durable.
+    fn sync(&mut self) -> io::Result<()>;
+    /// Atomically replace the snapshot and clear the log.
+    fn compact(&mut self, snapshot: &[u8]) -> io::Result<()>;
+}
+
+struct Options {
+    compact_threshold: usize,
+    shallow: bool,
+    fsync: bool,
+}
+
+struct State {
+    backend: Option<Box<dyn Backend>>,
+    options: Options,
+    /// The size of the log, in bytes.
+    log_size: usize,
+    /// The ops that have been written to the backend.
+    persisted_vv: VersionVector,
+    /// An error raised while persisting an update in the subscriber, reported
+    /// by the next call to the store.
+    error: Option<PyLoroError>,
+}
+
+impl State {
+    fn backend(&mut self) -> PyResult<&mut Box<dyn Backend>> {
+        self.backend
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("I/O operation on a closed store"))
+    }
+
+    fn take_error(&mut self) -> PyLoroResult<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn append(&mut self, doc: &LoroDocInner, update: &[u8], sync: bool) -> PyLoroResult<()> {
+        let meta = LoroDocInner::decode_import_blob_meta(update, false)?;
+        self.backend()?.append(update, sync).map_err(PyErr::from)?;
+        self.log_size += update.len();
+        self.persisted_vv.merge(&meta.partial_end_vv);
+        if self.log_size > self.options.compact_threshold {
+            self.compact(doc)?;
+        }
+        Ok(())
+    }
+
+    fn compact(&mut self, doc: &LoroDocInner) -> PyLoroResult<()> {
+        let mode = if self.options.shallow {
+            loro::ExportMode::shallow_snapshot_owned(doc.oplog_frontiers())
+        } else {
+            loro::ExportMode::Snapshot
+        };
+        let snapshot = doc.export(mode)?;
+        self.backend()?.compact(&snapshot).map_err(PyErr::from)?;
+        self.log_size = 0;
+        self.persisted_vv = doc.oplog_vv();
+        Ok(())
+    }
+
+    /// Append the ops that did not go through the local update subscription,
+    /// such as imported ones.
+    fn flush(&mut self, doc: &LoroDocInner) -> PyLoroResult<()> {
+        let vv = doc.oplog_vv();
+        if !self.persisted_vv.includes_vv(&vv) {
+            let updates = doc.export(loro::ExportMode::updates(&self.persisted_vv))?;
+            self.append(doc, &updates, true)?;
+        } else if !self.options.fsync {
+            self.backend()?.sync().map_err(PyErr::from)?;
+        }
+        Ok(())
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Persists a `LoroDoc` as a base snapshot plus an append-only log of its
+/// local updates.
+///
+/// Every local update of the document is appended to the log as soon as it is
+/// committed. With `fsync=True`, it is durable once `commit()` returns;
+/// otherwise it is only guaranteed to be durable after `flush()`. When the log
+/// exceeds `compact_threshold` bytes, it is compacted into a new snapshot, a
+/// `ShallowSnapshot` if `shallow` is true.
+///
+/// Imported updates are not local updates: they are persisted by `flush()`,
+/// `compact()` or `close()`.
+///
+/// Errors that occur while appending an update in the middle of a commit are
+/// raised by the next call to the store.
+#[pyclass(frozen)]
+pub struct LoroStore {
+    doc: LoroDocInner,
+    state: Arc<Mutex<State>>,
+    subscription: Mutex<Option<loro::Subscription>>,
+}
+
+impl LoroStore {
+    fn open(
+        py: Python<'_>,
+        mut backend: Box<dyn Backend>,
+        doc: Option<&LoroDoc>,
+        options: Options,
+    ) -> PyLoroResult<Self> {
+        let doc = doc.map(|d| d.doc.clone()).unwrap_or_default();
+        let (snapshot, log) = py.detach(|| backend.load()).map_err(PyErr::from)?;
+        let mut persisted_vv = VersionVector::default();
+        let mut log_size = 0;
+        for blob in snapshot.iter().chain(log.iter()) {
+            let meta = LoroDocInner::decode_import_blob_meta(blob, false)?;
+            persisted_vv.merge(&meta.partial_end_vv);
+        }
+        if let Some(snapshot) = &snapshot {
+            py.detach(|| doc.import(snapshot))?;
+        }
+        if !log.is_empty() {
+            log_size = log.iter().map(Vec::len).sum();
+            py.detach(|| doc.import_batch(&log))?;
+        }
+
+        let state = Arc::new(Mutex::new(State {
+            backend: Some(backend),
+            options,
+            log_size,
+            persisted_vv,
+            error: None,
+        }));
+        // The ops the given document had before it was opened.
+        lock(&state).flush(&doc)?;
+
+        let weak = Arc::downgrade(&state);
+        let subscriber_doc = doc.clone();
+        let subscription = doc.subscribe_local_update(Box::new(move |update| {
+            let Some(state) = weak.upgrade() else {
+                return false;
+            };
+            let mut state = lock(&state);
+            if state.backend.is_none() {
+                return false;
+            }
+            let sync = state.options.fsync;
+            if let Err(e) = state.append(&subscriber_doc, update, sync) {
+                state.error.get_or_insert(e);
+            }
+            true
+        }));
+        Ok(Self {
+            doc,
+            state,
+            subscription: Mutex::new(Some(subscription)),
+        })
+    }
+}
+
+#[pymethods]
+impl LoroStore {
+    /// Open the store kept in the directory `path`, creating it if needed.
+    ///
+    /// The stored snapshot and log are imported into `doc`, or into a new
+    /// document if it is not given. The ops `doc` already had are appended to
+    /// the log.
+    #[staticmethod]
+    #[pyo3(signature = (path, doc=None, *, compact_threshold=DEFAULT_COMPACT_THRESHOLD, shallow=false, fsync=true))]
+    pub fn open_directory(
+        py: Python<'_>,
+        path: PathBuf,
+        doc: Option<PyRef<'_, LoroDoc>>,
+        compact_threshold: usize,
+        shallow: bool,
+        fsync: bool,
+    ) -> PyLoroResult<Self> {
+        let backend = dir::DirBackend::open(&path).map_err(PyErr::from)?;
+        Self::open(
+            py,
+            Box::new(backend),
+            doc.as_deref(),
+            Options {
+                compact_threshold,
+                shallow,
+                fsync,
+            },
+        )
+    }
+
+    /// Open the store kept in the SQLite database `path`, creating it if needed.
+    ///
+    /// The stored snapshot and log are imported into `doc`, or into a new
+    /// document if it is not given. The ops `doc` already had are appended to
+    /// the log.
+    #[staticmethod]
+    #[pyo3(signature = (path, doc=None, *, compact_threshold=DEFAULT_COMPACT_THRESHOLD, shallow=false, fsync=true))]
+    pub fn open_sqlite(
+        py: Python<'_>,
+        path: PathBuf,
+        doc: Option<PyRef<'_, LoroDoc>>,
+        compact_threshold: usize,
+        shallow: bool,
+        fsync: bool,
+    ) -> PyLoroResult<Self> {
+        let backend = sqlite::SqliteBackend::open(&path).map_err(PyErr::from)?;
+        Self::open(
+            py,
+            Box::new(backend),
+            doc.as_deref(),
+            Options {
+                compact_threshold,
+                shallow,
+                fsync,
+            },
+        )
+    }
+
+    /// The persisted document.
+    #[getter]
+    pub fn doc(&self) -> LoroDoc {
+        self.doc.clone().into()
+    }
+
+    /// The size of the log, in bytes.
+    #[getter]
+    pub fn log_size(&self) -> usize {
+        lock(&self.state).log_size
+    }
+
+    /// Whether the store has been closed.
+    #[getter]
+    pub fn closed(&self) -> bool {
+        lock(&self.state).backend.is_none()
+    }
+
+    /// Persist the ops that are not stored yet, such as imported ones, and make
+    /// the log durable.
+    pub fn flush(&self, py: Python<'_>) -> PyLoroResult<()> {
+        // Committing with the state locked would deadlock in the subscriber.
+        self.doc.commit();
+        py.detach(|| {
+            let mut state = lock(&self.state);
+            state.take_error()?;
+            state.flush(&self.doc)
+        })
+    }
+
+    /// Replace the snapshot with a new one and clear the log.
+    pub fn compact(&self, py: Python<'_>) -> PyLoroResult<()> {
+        self.doc.commit();
+        py.detach(|| {
+            let mut state = lock(&self.state);
+            state.take_error()?;
+            state.compact(&self.doc)
+        })
+    }
+
+    /// Flush the store and stop persisting the document.
+    ///
+    /// Closing a closed store does nothing.
+    pub fn close(&self, py: Python<'_>) -> PyLoroResult<()> {
+        if lock(&self.state).backend.is_none() {
+            return Ok(());
+        }
+        let flushed = self.flush(py);
+        if let Some(subscription) = self.subscription.lock().unwrap().take() {
+            subscription.unsubscribe();
+        }
+        lock(&self.state).backend = None;
+        flushed
+    }
+
+    pub fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    pub fn __exit__(
+        &self,
+        py: Python<'_>,
+        _args: &Bound<'_, pyo3::types::PyTuple>,
+    ) -> PyLoroResult<()> {
+        self.close(py)
+    }
+}
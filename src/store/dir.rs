@@ -0,0 +1,125 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{Backend, Stored};
+
+const SNAPSHOT: &str = "snapshot";
+const LOG: &str = "log";
+
+/// Stores the snapshot and the log as two files in a directory.
+///
+/// Each log record is `len (u32 LE) | crc32 (u32 LE) | payload`. A record that
+/// was torn by a crash fails its length or checksum check and is dropped, with
+/// everything after it, when the log is loaded. The snapshot is replaced by
+/// writing a temporary file that is renamed over it.
+pub struct DirBackend {
+    dir: PathBuf,
+    log: File,
+}
+
+impl DirBackend {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            log,
+        })
+    }
+
+    /// Atomically replace `name` with `data`.
+    fn replace(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        let tmp = self.dir.join(format!("{name}.tmp"));
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(name))?;
+        sync_dir(&self.dir)
+    }
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl Backend for DirBackend {
+    fn load(&mut self) -> io::Result<Stored> {
+        let snapshot = match fs::read(self.dir.join(SNAPSHOT)) {
+            Ok(data) => Some(data),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        let mut data = Vec::new();
+        File::open(self.dir.join(LOG))?.read_to_end(&mut data)?;
+        let mut records = Vec::new();
+        let mut rest = data.as_slice();
+        while rest.len() >= 8 {
+            let len = u32::from_le_bytes(rest[0..4].try_into().unwrap()) as usize;
+            let crc = u32::from_le_bytes(rest[4..8].try_into().unwrap());
+            let Some(payload) = rest.get(8..8 + len) else {
+                break;
+            };
+            if crc32(payload) != crc {
+                break;
+            }
+            records.push(payload.to_vec());
+            rest = &rest[8 + len..];
+        }
+        let valid = data.len() - rest.len();
+        if valid != data.len() {
+            // Drop the torn tail so that new records are not appended after it.
+            self.log.set_len(valid as u64)?;
+            self.log.sync_all()?;
+        }
+        Ok((snapshot, records))
+    }
+
+    fn append(&mut self, update: &[u8], sync: bool) -> io::Result<()> {
+        let mut record = Vec::with_capacity(8 + update.len());
+        record.extend_from_slice(&(update.len() as u32).to_le_bytes());
+        record.extend_from_slice(&crc32(update).to_le_bytes());
+        record.extend_from_slice(update);
+        self.log.write_all(&record)?;
+        if sync {
+            self.log.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.log.sync_data()
+    }
+
+    fn compact(&mut self, snapshot: &[u8]) -> io::Result<()> {
+        // A crash between the two steps leaves a log whose updates are already
+        // in the snapshot. Importing them again is a no-op.
+        self.replace(SNAPSHOT, snapshot)?;
+        self.replace(LOG, &[])?;
+        self.log = OpenOptions::new().append(true).open(self.dir.join(LOG))?;
+        Ok(())
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
@@ -0,0 +1,96 @@
+use std::{io, path::Path};
+
+use rusqlite::{params, Connection};
+
+use super::{Backend, Stored};
+
+/// Stores the snapshot and the log in two tables of a SQLite database.
+///
+/// The database is opened in WAL mode. Appends are committed with
+/// `synchronous=FULL` when they must be durable, and compaction replaces the
+/// snapshot and clears the log in a single transaction.
+pub struct SqliteBackend {
+    conn: Connection,
+}
+
+fn to_io(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl SqliteBackend {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(to_io)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = FULL;
+             CREATE TABLE IF NOT EXISTS loro_snapshot (
+                 id INTEGER PRIMARY KEY CHECK (id = 0),
+                 data BLOB NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS loro_log (
+                 seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                 data BLOB NOT NULL
+             );",
+        )
+        .map_err(to_io)?;
+        Ok(Self { conn })
+    }
+
+    fn set_synchronous(&self, sync: bool) -> io::Result<()> {
+        let mode = if sync { "FULL" } else { "NORMAL" };
+        self.conn
+            .pragma_update(None, "synchronous", mode)
+            .map_err(to_io)
+    }
+}
+
+impl Backend for SqliteBackend {
+    fn load(&mut self) -> io::Result<Stored> {
+        let snapshot =
+            match self
+                .conn
+                .query_row("SELECT data FROM loro_snapshot WHERE id = 0", [], |row| {
+                    row.get(0)
+                }) {
+                Ok(data) => Some(data),
+                Err(rusqlite::Error::QueryReturnedNoRows) => None,
+                Err(e) => return Err(to_io(e)),
+            };
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM loro_log ORDER BY seq")
+            .map_err(to_io)?;
+        let log = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(to_io)?
+            .collect::<Result<Vec<Vec<u8>>, _>>()
+            .map_err(to_io)?;
+        Ok((snapshot, log))
+    }
+
+    fn append(&mut self, update: &[u8], sync: bool) -> io::Result<()> {
+        self.set_synchronous(sync)?;
+        self.conn
+            .execute("INSERT INTO loro_log (data) VALUES (?1)", params![update])
+            .map_err(to_io)?;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(FULL)", [], |_| Ok(()))
+            .map_err(to_io)
+    }
+
+    fn compact(&mut self, snapshot: &[u8]) -> io::Result<()> {
+        self.set_synchronous(true)?;
+        let tx = self.conn.transaction().map_err(to_io)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO loro_snapshot (id, data) VALUES (0, ?1)",
+            params![snapshot],
+        )
+        .map_err(to_io)?;
+        tx.execute("DELETE FROM loro_log", []).map_err(to_io)?;
+        tx.commit().map_err(to_io)
+    }
+}
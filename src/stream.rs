@@ -0,0 +1,243 @@
+//! asyncio streams over Loro subscriptions.
+//!
+//! Loro invokes subscribers synchronously in the middle of `commit()`,
+//! `import_()`, ..., possibly on a thread that is not running the event loop.
+//! An [EventStream] buffers the items produced by a subscriber in a bounded
+//! queue, and hands them to the coroutine awaiting `__anext__` through
+//! `loop.call_soon_threadsafe`.
+//!
+//! A subscriber cannot wait for the consumer, so back-pressure is applied by
+//! bounding the buffer: once it holds `maxsize` items, the [OverflowPolicy]
+//! decides what happens to the next one.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyStopAsyncIteration, PyValueError},
+    prelude::*,
+    types::{PyCFunction, PyDict, PyTuple},
+};
+
+use crate::err::StreamOverflowError;
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<EventStream>()?;
+    m.add_class::<OverflowPolicy>()?;
+    Ok(())
+}
+
+/// The default number of items an [EventStream] can buffer.
+pub const DEFAULT_MAXSIZE: usize = 1024;
+
+/// What an `EventStream` does with a new item when its buffer is full.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Close the stream. The consumer gets the buffered items, then
+    /// `StreamOverflowError` is raised.
+    #[default]
+    Error,
+    /// Discard the oldest buffered item to make room for the new one.
+    DropOldest,
+    /// Discard the new item.
+    DropNewest,
+}
+
+struct State {
+    queue: VecDeque<Py<PyAny>>,
+    maxsize: usize,
+    overflow: OverflowPolicy,
+    closed: bool,
+    overflowed: bool,
+    /// The event loop and the future of the pending `__anext__` call.
+    waiter: Option<(Py<PyAny>, Py<PyAny>)>,
+}
+
+/// The producing side of an [EventStream], owned by the subscriber callback.
+#[derive(Clone)]
+pub struct Sender(Arc<Mutex<State>>);
+
+impl Sender {
+    /// Buffer `item` and wake up the consumer.
+    ///
+    /// Returns whether the stream still accepts items, so that it can be used
+    /// as the return value of a keep-alive subscriber.
+    pub fn send(&self, py: Python<'_>, item: Py<PyAny>) -> bool {
+        let mut state = lock(&self.0);
+        if state.closed {
+            return false;
+        }
+        if state.queue.len() >= state.maxsize {
+            match state.overflow {
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                }
+                OverflowPolicy::DropNewest => return true,
+                OverflowPolicy::Error => {
+                    state.overflowed = true;
+                    state.closed = true;
+                    wake(py, &self.0, state);
+                    return false;
+                }
+            }
+        }
+        state.queue.push_back(item);
+        wake(py, &self.0, state);
+        true
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Schedule the resolution of the pending `__anext__` future on its loop.
+fn wake(py: Python<'_>, shared: &Arc<Mutex<State>>, mut state: MutexGuard<'_, State>) {
+    let Some((event_loop, future)) = state.waiter.take() else {
+        return;
+    };
+    drop(state);
+    let shared = shared.clone();
+    let loop_ = event_loop.clone_ref(py);
+    let resolve = PyCFunction::new_closure(
+        py,
+        None,
+        None,
+        move |args: &Bound<'_, PyTuple>, _kwargs: Option<&Bound<'_, PyDict>>| -> PyResult<()> {
+            let py = args.py();
+            let future = future.bind(py);
+            // The awaiting coroutine was cancelled. The item stays buffered
+            // for the next `__anext__`.
+            if future.call_method0("done")?.extract::<bool>()? {
+                return Ok(());
+            }
+            let mut state = lock(&shared);
+            if let Some(item) = state.queue.pop_front() {
+                drop(state);
+                future.call_method1("set_result", (item,))?;
+            } else if state.overflowed {
+                drop(state);
+                future.call_method1("set_exception", (overflow_error(),))?;
+            } else if state.closed {
+                drop(state);
+                future.call_method1("set_exception", (PyStopAsyncIteration::new_err(()),))?;
+            } else {
+                state.waiter = Some((loop_.clone_ref(py), future.clone().unbind()));
+            }
+            Ok(())
+        },
+    );
+    let scheduled = resolve.and_then(|resolve| {
+        event_loop
+            .bind(py)
+            .call_method1("call_soon_threadsafe", (resolve,))
+    });
+    if let Err(e) = scheduled {
+        // The loop is closed: nobody can await the future anymore.
+        e.write_unraisable(py, None);
+    }
+}
+
+fn overflow_error() -> PyErr {
+    StreamOverflowError::new_err("the event stream buffer is full, the consumer is too slow")
+}
+
+/// An asynchronous iterator over the items produced by a Loro subscription.
+///
+/// Items are buffered in a queue of at most `maxsize` items. When the queue
+/// is full, the `overflow` policy decides whether the stream is closed with a
+/// `StreamOverflowError`, or whether the oldest or the newest item is dropped.
+///
+/// The subscription is removed when the stream is closed or garbage collected.
+#[pyclass(frozen)]
+pub struct EventStream {
+    state: Arc<Mutex<State>>,
+    subscription: Mutex<Option<loro::Subscription>>,
+}
+
+impl EventStream {
+    /// Create a stream fed by the subscription returned by `subscribe`.
+    pub fn new(
+        maxsize: usize,
+        overflow: OverflowPolicy,
+        subscribe: impl FnOnce(Sender) -> Option<loro::Subscription>,
+    ) -> PyResult<Self> {
+        if maxsize == 0 {
+            return Err(PyValueError::new_err("maxsize must be greater than 0"));
+        }
+        let state = Arc::new(Mutex::new(State {
+            queue: VecDeque::new(),
+            maxsize,
+            overflow,
+            closed: false,
+            overflowed: false,
+            waiter: None,
+        }));
+        let subscription = subscribe(Sender(state.clone()));
+        if subscription.is_none() {
+            // Detached containers cannot be subscribed to.
+            lock(&state).closed = true;
+        }
+        Ok(Self {
+            state,
+            subscription: Mutex::new(subscription),
+        })
+    }
+}
+
+#[pymethods]
+impl EventStream {
+    pub fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let future = event_loop.call_method0("create_future")?;
+        let mut state = lock(&self.state);
+        if let Some(item) = state.queue.pop_front() {
+            drop(state);
+            future.call_method1("set_result", (item,))?;
+            return Ok(future);
+        }
+        if state.overflowed {
+            return Err(overflow_error());
+        }
+        if state.closed {
+            return Err(PyStopAsyncIteration::new_err(()));
+        }
+        if let Some((_, pending)) = &state.waiter {
+            if !pending.bind(py).call_method0("done")?.extract::<bool>()? {
+                return Err(PyRuntimeError::new_err(
+                    "anext() is already awaiting the next item of this stream",
+                ));
+            }
+        }
+        state.waiter = Some((event_loop.unbind(), future.clone().unbind()));
+        Ok(future)
+    }
+
+    /// The number of buffered items.
+    pub fn __len__(&self) -> usize {
+        lock(&self.state).queue.len()
+    }
+
+    /// Whether the stream stopped receiving new items.
+    #[getter]
+    pub fn closed(&self) -> bool {
+        lock(&self.state).closed
+    }
+
+    /// Unsubscribe and stop receiving new items.
+    ///
+    /// The items that are already buffered can still be consumed, after that the
+    /// iteration stops.
+    pub fn close(&self, py: Python<'_>) {
+        if let Some(subscription) = self.subscription.lock().unwrap().take() {
+            subscription.unsubscribe();
+        }
+        let mut state = lock(&self.state);
+        state.closed = true;
+        wake(py, &self.state, state);
+    }
+}
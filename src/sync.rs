@@ -0,0 +1,316 @@
+//! A small framed protocol to synchronize a document between two peers.
+//!
+//! Every message is a frame made of a 6 bytes header followed by a payload:
+//!
+//! | bytes | content                          |
+//! |-------|----------------------------------|
+//! | 0     | protocol version ([VERSION])     |
+//! | 1     | message kind ([MessageKind])     |
+//! | 2..6  | payload length, big-endian `u32` |
+//!
+//! Frames can be sent one by one over a message based transport, or
+//! concatenated over a stream: [SyncSession::receive] buffers incomplete frames.
+use std::sync::{Arc, Mutex};
+
+use loro::{awareness::EphemeralStore as EphemeralStoreInner, LoroDoc as LoroDocInner};
+use pyo3::{prelude::*, types::PyBytes};
+
+use crate::{
+    awareness::EphemeralStore,
+    doc::LoroDoc,
+    err::{PyLoroError, PyLoroResult},
+    version::VersionVector,
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<SyncSession>()?;
+    m.add_class::<MessageKind>()?;
+    Ok(())
+}
+
+/// The version of the sync protocol.
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 6;
+
+/// The kind of a sync protocol message.
+#[pyclass(eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    /// The version vector of the sender. The receiver answers with the updates
+    /// the sender is missing.
+    Hello = 1,
+    /// Updates exported by the sender.
+    Updates = 2,
+    /// The version vector of the sender after it imported updates.
+    Ack = 3,
+    /// Encoded `EphemeralStore` states.
+    Ephemeral = 4,
+}
+
+impl TryFrom<u8> for MessageKind {
+    type Error = PyLoroError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => MessageKind::Hello,
+            2 => MessageKind::Updates,
+            3 => MessageKind::Ack,
+            4 => MessageKind::Ephemeral,
+            _ => return Err(decode_error(format!("unknown message kind {value}"))),
+        })
+    }
+}
+
+fn decode_error(msg: String) -> PyLoroError {
+    loro::LoroError::DecodeError(msg.into_boxed_str()).into()
+}
+
+fn frame(kind: MessageKind, payload: &[u8]) -> Vec<u8> {
+    let mut ans = Vec::with_capacity(HEADER_LEN + payload.len());
+    ans.push(VERSION);
+    ans.push(kind as u8);
+    ans.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    ans.extend_from_slice(payload);
+    ans
+}
+
+/// One end of a sync session between two peers editing the same document.
+///
+/// The session does not do any I/O: it produces frames that must be delivered
+/// to the other peer, and consumes the frames received from it. The handshake
+/// goes as follows:
+///
+/// 1. Both peers call `start()` and send the `Hello` frame carrying their
+///    version vector. A peer that receives a `Hello` before calling `start()`
+///    answers with its own `Hello`.
+/// 2. On `Hello`, a peer answers with the `Updates` the other peer is missing.
+/// 3. On `Updates`, a peer imports them and answers with an `Ack` carrying its
+///    new version vector. If the updates depend on ops it does not have, it
+///    sends a `Hello` again to get them.
+///
+/// Local edits are sent with `flush()`. If an `EphemeralStore` is given, its
+/// local updates are sent by `flush()` as well, and the received ones are
+/// applied to it.
+#[pyclass]
+pub struct SyncSession {
+    doc: LoroDocInner,
+    ephemeral: Option<EphemeralStoreInner>,
+    ephemeral_updates: Arc<Mutex<Vec<Vec<u8>>>>,
+    _ephemeral_subscription: Option<loro::Subscription>,
+    /// The latest version vector acknowledged by the remote peer.
+    remote_vv: Option<loro::VersionVector>,
+    /// The ops the remote peer has, or that have been sent to it.
+    sent_vv: loro::VersionVector,
+    hello_sent: bool,
+    buffer: Vec<u8>,
+    /// The frames produced by a `receive` that raised, which are returned by
+    /// the next `receive` or `flush`.
+    replies: Vec<Vec<u8>>,
+}
+
+impl SyncSession {
+    fn hello(&mut self) -> Vec<u8> {
+        self.hello_sent = true;
+        frame(MessageKind::Hello, &self.doc.oplog_vv().encode())
+    }
+
+    /// Export the updates the remote peer has not received yet.
+    fn updates(&mut self, py: Python<'_>) -> PyLoroResult<Option<Vec<u8>>> {
+        let vv = self.doc.oplog_vv();
+        if self.sent_vv.includes_vv(&vv) {
+            return Ok(None);
+        }
+        let doc = &self.doc;
+        let from = &self.sent_vv;
+        let updates = py.detach(|| doc.export(loro::ExportMode::updates(from)))?;
+        self.sent_vv.merge(&vv);
+        Ok(Some(frame(MessageKind::Updates, &updates)))
+    }
+
+    /// Handle the complete frames of `buffer` from `offset`, which is moved
+    /// past each frame before it is handled.
+    fn receive_frames(
+        &mut self,
+        py: Python<'_>,
+        buffer: &[u8],
+        offset: &mut usize,
+        out: &mut Vec<Vec<u8>>,
+    ) -> PyLoroResult<()> {
+        while buffer.len() - *offset >= HEADER_LEN {
+            let header = &buffer[*offset..*offset + HEADER_LEN];
+            let len = u32::from_be_bytes([header[2], header[3], header[4], header[5]]) as usize;
+            let start = *offset + HEADER_LEN;
+            if buffer.len() - start < len {
+                break;
+            }
+            *offset = start + len;
+            if header[0] != VERSION {
+                return Err(decode_error(format!(
+                    "unsupported sync protocol version {}",
+                    header[0]
+                )));
+            }
+            let kind = MessageKind::try_from(header[1])?;
+            self.handle(py, kind, &buffer[start..start + len], out)?;
+        }
+        Ok(())
+    }
+
+    fn handle(
+        &mut self,
+        py: Python<'_>,
+        kind: MessageKind,
+        payload: &[u8],
+        out: &mut Vec<Vec<u8>>,
+    ) -> PyLoroResult<()> {
+        match kind {
+            MessageKind::Hello => {
+                let vv = loro::VersionVector::decode(payload)?;
+                self.sent_vv = vv.clone();
+                self.remote_vv = Some(vv);
+                if !self.hello_sent {
+                    out.extend(self.start());
+                }
+                out.extend(self.updates(py)?);
+            }
+            MessageKind::Updates => {
+                let meta = LoroDocInner::decode_import_blob_meta(payload, false)?;
+                let doc = &self.doc;
+                let status = py.detach(|| doc.import(payload))?;
+                // The sender has the ops it sent, no need to send them back.
+                self.sent_vv.merge(&meta.partial_end_vv);
+                if status.pending.is_some() {
+                    out.push(self.hello());
+                }
+                out.push(frame(MessageKind::Ack, &self.doc.oplog_vv().encode()));
+            }
+            MessageKind::Ack => {
+                let vv = loro::VersionVector::decode(payload)?;
+                self.sent_vv.merge(&vv);
+                self.remote_vv = Some(vv);
+            }
+            MessageKind::Ephemeral => {
+                if let Some(store) = &self.ephemeral {
+                    store
+                        .apply(payload)
+                        .map_err(|e| decode_error(e.to_string()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl SyncSession {
+    /// Create a session synchronizing `doc`, and `ephemeral` if given, with a
+    /// remote peer.
+    #[new]
+    #[pyo3(signature = (doc, ephemeral=None))]
+    pub fn new(doc: &LoroDoc, ephemeral: Option<PyRef<'_, EphemeralStore>>) -> Self {
+        let ephemeral = ephemeral.map(|e| e.0.clone());
+        let ephemeral_updates = Arc::new(Mutex::new(Vec::new()));
+        let subscription = ephemeral.as_ref().map(|store| {
+            let updates = ephemeral_updates.clone();
+            store.subscribe_local_updates(Box::new(move |data| {
+                updates.lock().unwrap().push(data.clone());
+                true
+            }))
+        });
+        Self {
+            doc: doc.doc.clone(),
+            ephemeral,
+            ephemeral_updates,
+            _ephemeral_subscription: subscription,
+            remote_vv: None,
+            sent_vv: Default::default(),
+            hello_sent: false,
+            buffer: Vec::new(),
+            replies: Vec::new(),
+        }
+    }
+
+    /// Start the handshake.
+    ///
+    /// Returns the frames to send to the remote peer: a `Hello`, followed by
+    /// the whole `EphemeralStore` state if there is one.
+    pub fn start(&mut self) -> Vec<Vec<u8>> {
+        let mut ans = vec![self.hello()];
+        if let Some(store) = &self.ephemeral {
+            self.ephemeral_updates.lock().unwrap().clear();
+            let all = store.encode_all();
+            if !all.is_empty() {
+                ans.push(frame(MessageKind::Ephemeral, &all));
+            }
+        }
+        ans
+    }
+
+    /// Consume bytes received from the remote peer.
+    ///
+    /// `data` may contain any number of frames, and may end with an incomplete
+    /// frame which is kept until the rest of it is received. Returns the frames
+    /// to send back to the remote peer.
+    ///
+    /// If a frame cannot be decoded or handled, it is dropped and the error is
+    /// raised. The bytes after it are kept, and the frames produced for the
+    /// frames before it are returned by the next `receive` or `flush`.
+    pub fn receive(
+        &mut self,
+        py: Python<'_>,
+        data: &Bound<'_, PyBytes>,
+    ) -> PyLoroResult<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(data.as_bytes());
+        let buffer = std::mem::take(&mut self.buffer);
+        let mut out = std::mem::take(&mut self.replies);
+        let mut offset = 0;
+        let result = self.receive_frames(py, &buffer, &mut offset, &mut out);
+        self.buffer = buffer[offset..].to_vec();
+        match result {
+            Ok(()) => Ok(out),
+            Err(e) => {
+                self.replies = out;
+                Err(e)
+            }
+        }
+    }
+
+    /// Return the frames carrying the local changes the remote peer has not
+    /// received yet.
+    ///
+    /// Document updates are only sent once the remote version is known, that
+    /// is after the remote `Hello` has been received.
+    pub fn flush(&mut self, py: Python<'_>) -> PyLoroResult<Vec<Vec<u8>>> {
+        let mut out = std::mem::take(&mut self.replies);
+        if self.remote_vv.is_some() {
+            match self.updates(py) {
+                Ok(updates) => out.extend(updates),
+                Err(e) => {
+                    self.replies = out;
+                    return Err(e);
+                }
+            }
+        }
+        let ephemeral = std::mem::take(&mut *self.ephemeral_updates.lock().unwrap());
+        out.extend(
+            ephemeral
+                .iter()
+                .map(|data| frame(MessageKind::Ephemeral, data)),
+        );
+        Ok(out)
+    }
+
+    /// The latest version vector announced or acknowledged by the remote peer.
+    #[getter]
+    pub fn remote_version(&self) -> Option<VersionVector> {
+        self.remote_vv.clone().map(VersionVector::from)
+    }
+
+    /// Whether the remote peer acknowledged all the ops of the local document.
+    #[getter]
+    pub fn is_synced(&self) -> bool {
+        self.remote_vv
+            .as_ref()
+            .is_some_and(|vv| vv.includes_vv(&self.doc.oplog_vv()))
+    }
+}
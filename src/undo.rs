@@ -1,127 +1,872 @@
+//! Undo/redo of the local changes of a document.
+//!
+//! An `UndoManager` of the whole document wraps Loro's own undo manager,
+//! whose stacks cannot be exported. An `UndoManager` scoped to some
+//! containers, or one of the whole document whose state can be exported,
+//! uses the engine below, which only records the local changes in its scope,
+//! and treats the other local changes like remote changes.
+//!
+//! Every undo item of this engine is the version range of some local
+//! changes. Undoing it computes the diff from the end of the range back to its
+//! start, keeps the part of it on the containers the changes touched,
+//! transforms it against the other changes made since, and applies it as a
+//! new local change, the same way Loro's own undo manager does.
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use loro::{
+    event::{Diff, DiffBatch, ListDiffItem, MapDelta},
+    CommitOptions, ContainerID, ContainerTrait, Counter, CounterSpan as LoroCounterSpan,
+    EventTriggerKind, Frontiers, LoroError, TreeExternalDiff, TreeID, TreeParentId,
+    ValueOrContainer, VersionVector,
+};
 use pyo3::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    callback,
     container::Cursor,
     doc::{AbsolutePosition, CounterSpan, LoroDoc},
-    err::PyLoroResult,
+    err::{PyLoroError, PyLoroResult, UndoError},
     event::DiffEvent,
-    value::LoroValue,
+    value::{ContainerID as PyContainerID, LoroValue},
 };
 
 pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<UndoManager>()?;
     m.add_class::<UndoOrRedo>()?;
+    m.add_class::<UndoItemMeta>()?;
+    m.add_class::<CursorWithPos>()?;
     Ok(())
 }
 
+/// The origin of the changes made by `undo` and `redo`.
+const UNDO_ORIGIN: &str = "undo";
+
+struct Item {
+    /// The version before the changes.
+    before: Frontiers,
+    /// The version after the changes.
+    after: Frontiers,
+    /// The counters of the changes of the local peer.
+    span: LoroCounterSpan,
+    /// The containers changed by the changes.
+    containers: FxHashSet<ContainerID>,
+    meta: loro::undo::UndoItemMeta,
+    /// When the item was pushed, in milliseconds.
+    timestamp: i64,
+    /// The remote changes made after the item, and before the next item.
+    remote: loro::undo::DiffBatch,
+}
+
+struct Inner {
+    /// The containers whose changes are recorded, with their descendants, or
+    /// `None` to record the changes of the whole document.
+    scope: Option<HashSet<ContainerID>>,
+    undo_stack: VecDeque<Item>,
+    redo_stack: VecDeque<Item>,
+    /// The version of the document after the last event.
+    frontiers: Frontiers,
+    vv: VersionVector,
+    /// The diffs of the change made by `undo` or `redo` while it is applying
+    /// an item.
+    performing: Option<Vec<(ContainerID, Diff<'static>)>>,
+    /// The containers and tree nodes recreated by `undo` or `redo`. Restoring
+    /// a deleted container creates a new one, and the items referring to the
+    /// old one are applied to the new one instead.
+    remap: FxHashMap<ContainerID, ContainerID>,
+    /// Whether a group is active, and whether it has an item yet.
+    group: Option<bool>,
+    merge_interval: i64,
+    max_undo_steps: usize,
+    exclude_origin_prefixes: Vec<String>,
+    last_popped_cursors: Option<Vec<loro::undo::CursorWithPos>>,
+    on_push: Option<Arc<Py<PyAny>>>,
+    on_pop: Option<Arc<Py<PyAny>>>,
+}
+
+impl Inner {
+    fn stack(&mut self, kind: loro::undo::UndoOrRedo) -> &mut VecDeque<Item> {
+        match kind {
+            loro::undo::UndoOrRedo::Undo => &mut self.undo_stack,
+            loro::undo::UndoOrRedo::Redo => &mut self.redo_stack,
+        }
+    }
+
+    fn in_scope(&self, target: &ContainerID, path: &[(ContainerID, loro::Index)]) -> bool {
+        self.scope.as_ref().is_none_or(|scope| {
+            scope.contains(target) || path.iter().any(|(id, _)| scope.contains(id))
+        })
+    }
+
+    /// Push `item` on a stack, dropping the oldest items above the limit.
+    fn push(&mut self, kind: loro::undo::UndoOrRedo, item: Item) {
+        let max = self.max_undo_steps;
+        let stack = self.stack(kind);
+        stack.push_back(item);
+        while stack.len() > max {
+            stack.pop_front();
+        }
+    }
+}
+
+fn opposite(kind: loro::undo::UndoOrRedo) -> loro::undo::UndoOrRedo {
+    match kind {
+        loro::undo::UndoOrRedo::Undo => loro::undo::UndoOrRedo::Redo,
+        loro::undo::UndoOrRedo::Redo => loro::undo::UndoOrRedo::Undo,
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+fn on_push(
+    callback: Option<Arc<Py<PyAny>>>,
+    kind: loro::undo::UndoOrRedo,
+    span: LoroCounterSpan,
+    event: Option<loro::event::DiffEvent>,
+) -> loro::undo::UndoItemMeta {
+    let Some(callback) = callback else {
+        return Default::default();
+    };
+    Python::attach(|py| {
+        callback::call(
+            py,
+            &callback,
+            (
+                UndoOrRedo::from(kind),
+                CounterSpan::from(span),
+                event.map(DiffEvent::from),
+            ),
+        )
+        .filter(|ret| !ret.is_none())
+        .and_then(|ret| match ret.extract::<UndoItemMeta>() {
+            Ok(meta) => Some(meta.into()),
+            Err(e) => {
+                e.write_unraisable(py, Some(callback.bind(py)));
+                None
+            }
+        })
+        .unwrap_or_default()
+    })
+}
+
+/// The metadata of a popped item, with its cursors resolved in the current
+/// version of the document, so that the selection can be restored.
+///
+/// The cursors that cannot be resolved anymore keep their recorded position.
+fn resolve_cursors(doc: &loro::LoroDoc, meta: &loro::undo::UndoItemMeta) -> UndoItemMeta {
+    let cursors = meta
+        .cursors
+        .iter()
+        .map(|c| match doc.get_cursor_pos(&c.cursor) {
+            Ok(result) => loro::undo::CursorWithPos {
+                cursor: result.update.unwrap_or_else(|| c.cursor.clone()),
+                pos: result.current,
+            },
+            Err(_) => c.clone(),
+        })
+        .collect::<Vec<_>>();
+    loro::undo::UndoItemMeta {
+        value: meta.value.clone(),
+        cursors,
+    }
+    .into()
+}
+
+/// Keep the diffs of `containers` in `batch`, and of the containers they
+/// create, e.g. a deleted child map that is restored with its content.
+fn restrict(batch: &DiffBatch, containers: &FxHashSet<ContainerID>) -> DiffBatch {
+    let mut kept = containers.clone();
+    loop {
+        let len = kept.len();
+        for (id, diff) in batch.iter() {
+            if kept.contains(id) {
+                kept.extend(created_containers(diff));
+            }
+        }
+        if kept.len() == len {
+            break;
+        }
+    }
+    let mut ans = DiffBatch::default();
+    for (id, diff) in batch.iter() {
+        if kept.contains(id) {
+            let _ = ans.push(id.clone(), diff.clone());
+        }
+    }
+    ans
+}
+
+fn created_containers(diff: &Diff) -> Vec<ContainerID> {
+    let child = |value: &ValueOrContainer| match value {
+        ValueOrContainer::Container(c) => Some(c.id()),
+        ValueOrContainer::Value(_) => None,
+    };
+    match diff {
+        Diff::List(items) => items
+            .iter()
+            .flat_map(|item| match item {
+                ListDiffItem::Insert { insert, .. } => insert.iter().filter_map(child).collect(),
+                _ => Vec::new(),
+            })
+            .collect(),
+        Diff::Map(map) => map.updated.values().flatten().filter_map(child).collect(),
+        Diff::Tree(tree) => tree
+            .diff
+            .iter()
+            .filter(|item| matches!(item.action, TreeExternalDiff::Create { .. }))
+            .map(|item| item.target.associated_meta_container())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn remap_id(remap: &FxHashMap<ContainerID, ContainerID>, id: &ContainerID) -> ContainerID {
+    let mut id = id;
+    while let Some(new_id) = remap.get(id) {
+        id = new_id;
+    }
+    id.clone()
+}
+
+fn remap_node(remap: &FxHashMap<ContainerID, ContainerID>, node: &mut TreeID) {
+    if let ContainerID::Normal { peer, counter, .. } =
+        remap_id(remap, &node.associated_meta_container())
+    {
+        *node = TreeID::new(peer, counter);
+    }
+}
+
+/// Apply the recorded remapping to the containers and tree nodes of `batch`,
+/// except to the containers created by `batch` itself.
+fn remap_batch(batch: DiffBatch, remap: &FxHashMap<ContainerID, ContainerID>) -> DiffBatch {
+    if remap.is_empty() {
+        return batch;
+    }
+    let created: FxHashSet<ContainerID> = batch
+        .iter()
+        .flat_map(|(_, diff)| created_containers(diff))
+        .collect();
+    let mut ans = DiffBatch::default();
+    for (id, diff) in batch.iter() {
+        let id = if created.contains(id) {
+            id.clone()
+        } else {
+            remap_id(remap, id)
+        };
+        let diff = match diff {
+            Diff::Tree(tree) => {
+                let mut tree = tree.clone().into_owned();
+                for item in tree.diff.iter_mut() {
+                    remap_node(remap, &mut item.target);
+                    if let TreeExternalDiff::Create { parent, .. }
+                    | TreeExternalDiff::Move { parent, .. } = &mut item.action
+                    {
+                        if let TreeParentId::Node(parent) = parent {
+                            remap_node(remap, parent);
+                        }
+                    }
+                }
+                Diff::Tree(Cow::Owned(tree))
+            }
+            diff => diff.clone(),
+        };
+        let _ = ans.push(id, diff);
+    }
+    ans
+}
+
+/// The nodes that applying `batch` creates anew, because they are deleted.
+fn deleted_nodes(doc: &loro::LoroDoc, batch: &DiffBatch) -> FxHashMap<ContainerID, Vec<TreeID>> {
+    let mut ans = FxHashMap::default();
+    for (id, diff) in batch.iter() {
+        let Diff::Tree(tree_diff) = diff else {
+            continue;
+        };
+        let tree = doc.get_tree(id.clone());
+        let nodes: Vec<TreeID> = tree_diff
+            .diff
+            .iter()
+            .filter(|item| !matches!(item.action, TreeExternalDiff::Delete { .. }))
+            .filter(|item| tree.is_node_deleted(&item.target).unwrap_or(true))
+            .map(|item| item.target)
+            .collect();
+        ans.insert(id.clone(), nodes);
+    }
+    ans
+}
+
+/// Record which containers and nodes were created by applying `batch`, whose
+/// change has the diffs `performed`.
+fn learn_remap(
+    remap: &mut FxHashMap<ContainerID, ContainerID>,
+    batch: &DiffBatch,
+    mut recreated: FxHashMap<ContainerID, Vec<TreeID>>,
+    performed: &[(ContainerID, Diff<'static>)],
+) {
+    let applied: FxHashMap<&ContainerID, &Diff> = batch.iter().collect();
+    for (id, new) in performed {
+        let Some(old) = applied.get(id) else {
+            continue;
+        };
+        match (old, new) {
+            (Diff::Map(old), Diff::Map(new)) => {
+                for (key, value) in &old.updated {
+                    if let (
+                        Some(ValueOrContainer::Container(old)),
+                        Some(Some(ValueOrContainer::Container(new))),
+                    ) = (value, new.updated.get(key))
+                    {
+                        remap.insert(old.id(), new.id());
+                    }
+                }
+            }
+            (Diff::List(_), Diff::List(_)) => {
+                for (old, new) in created_containers(old)
+                    .into_iter()
+                    .zip(created_containers(new))
+                {
+                    remap.insert(old, new);
+                }
+            }
+            (Diff::Tree(_), Diff::Tree(tree)) => {
+                let old = recreated.remove(id).unwrap_or_default();
+                let new = tree.diff.iter().filter_map(|item| match item.action {
+                    TreeExternalDiff::Create { .. } if !old.contains(&item.target) => {
+                        Some(item.target)
+                    }
+                    _ => None,
+                });
+                for (old, new) in old.iter().zip(new) {
+                    remap.insert(
+                        old.associated_meta_container(),
+                        new.associated_meta_container(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn to_static(diff: &Diff) -> Diff<'static> {
+    match diff {
+        Diff::List(items) => Diff::List(items.clone()),
+        Diff::Text(delta) => Diff::Text(delta.clone()),
+        Diff::Map(map) => Diff::Map(MapDelta {
+            updated: map
+                .updated
+                .iter()
+                .map(|(k, v)| (Cow::Owned(k.to_string()), v.clone()))
+                .collect(),
+        }),
+        Diff::Tree(tree) => Diff::Tree(Cow::Owned(tree.as_ref().clone())),
+        Diff::Counter(n) => Diff::Counter(*n),
+        Diff::Unknown => Diff::Unknown,
+    }
+}
+
+/// Record a new local change or take another change into account.
+fn on_event(inner: &Arc<Mutex<Inner>>, doc: &loro::LoroDoc, event: loro::event::DiffEvent) {
+    let mut guard = inner.lock().unwrap();
+    let frontiers = doc.oplog_frontiers();
+    let vv = doc.oplog_vv();
+    let before = std::mem::replace(&mut guard.frontiers, frontiers.clone());
+    let before_vv = std::mem::replace(&mut guard.vv, vv.clone());
+    if event.triggered_by == EventTriggerKind::Checkout {
+        guard.undo_stack.clear();
+        guard.redo_stack.clear();
+        guard.group = None;
+        return;
+    }
+    if let Some(performing) = &mut guard.performing {
+        performing.extend(
+            event
+                .events
+                .iter()
+                .map(|e| (e.target.clone(), to_static(&e.diff))),
+        );
+        return;
+    }
+
+    let recorded = event.triggered_by == EventTriggerKind::Local
+        && !guard
+            .exclude_origin_prefixes
+            .iter()
+            .any(|prefix| event.origin.starts_with(prefix.as_str()));
+    let touched: FxHashSet<ContainerID> = event
+        .events
+        .iter()
+        .filter(|e| recorded && guard.in_scope(e.target, e.path))
+        .map(|e| e.target.clone())
+        .collect();
+    if touched.is_empty() {
+        let mut remote = DiffBatch::default();
+        for e in &event.events {
+            let _ = remote.push(e.target.clone(), to_static(&e.diff));
+        }
+        let remote = loro::undo::DiffBatch::from(remote);
+        let guard = &mut *guard;
+        if let Some(top) = guard.undo_stack.back() {
+            // A remote change to the containers of the active group ends it.
+            if remote.order.iter().any(|id| top.containers.contains(id)) {
+                guard.group = None;
+            }
+        }
+        for stack in [&mut guard.undo_stack, &mut guard.redo_stack] {
+            if let Some(top) = stack.back_mut() {
+                top.remote.compose(&remote);
+            }
+        }
+        return;
+    }
+
+    let peer = doc.peer_id();
+    let span = LoroCounterSpan::new(
+        before_vv.get(&peer).copied().unwrap_or(0),
+        vv.get(&peer).copied().unwrap_or(0),
+    );
+    let timestamp = now();
+    let merge = match (guard.group, guard.undo_stack.back()) {
+        (_, None) => false,
+        (Some(has_item), Some(_)) => has_item,
+        (None, Some(top)) => {
+            top.remote.cid_to_events.is_empty() && timestamp - top.timestamp < guard.merge_interval
+        }
+    };
+    if guard.group.is_some() {
+        guard.group = Some(true);
+    }
+    guard.redo_stack.clear();
+    if merge {
+        let top = guard.undo_stack.back_mut().unwrap();
+        top.after = frontiers;
+        top.span.end = span.end;
+        top.containers.extend(touched);
+        return;
+    }
+
+    let callback = guard.on_push.clone();
+    drop(guard);
+    let meta = on_push(callback, loro::undo::UndoOrRedo::Undo, span, Some(event));
+    let item = Item {
+        before,
+        after: frontiers,
+        span,
+        containers: touched,
+        meta,
+        timestamp,
+        remote: Default::default(),
+    };
+    inner
+        .lock()
+        .unwrap()
+        .push(loro::undo::UndoOrRedo::Undo, item);
+}
+
+/// The engine of the `UndoManager`s scoped to some containers, or whose
+/// state can be exported.
+struct ScopedUndoManager {
+    // The subscriptions are dropped first: the callback of the first one holds
+    // a reference to the document, which must not be the last one, or
+    // dropping it would commit and emit an event while the subscriptions are
+    // locked.
+    _subscription: loro::Subscription,
+    _peer_id_change_subscription: loro::Subscription,
+    doc: loro::LoroDoc,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ScopedUndoManager {
+    fn new(doc: &loro::LoroDoc, scope: Option<HashSet<ContainerID>>) -> Self {
+        let doc = doc.clone();
+        let inner = Arc::new(Mutex::new(Inner {
+            scope,
+            undo_stack: VecDeque::new(),
+            redo_stack: VecDeque::new(),
+            frontiers: doc.oplog_frontiers(),
+            vv: doc.oplog_vv(),
+            performing: None,
+            remap: FxHashMap::default(),
+            group: None,
+            merge_interval: 0,
+            max_undo_steps: 100,
+            exclude_origin_prefixes: Vec::new(),
+            last_popped_cursors: None,
+            on_push: None,
+            on_pop: None,
+        }));
+        let subscription = {
+            let inner = inner.clone();
+            let doc_clone = doc.clone();
+            doc.subscribe_root(Arc::new(move |event| on_event(&inner, &doc_clone, event)))
+        };
+        // Like Loro's own undo manager, the stacks are cleared when the peer
+        // changes, since their spans are counters of the previous peer.
+        let peer_id_change_subscription = {
+            let inner = inner.clone();
+            doc.subscribe_peer_id_change(Box::new(move |_| {
+                let mut inner = inner.lock().unwrap();
+                inner.undo_stack.clear();
+                inner.redo_stack.clear();
+                inner.group = None;
+                true
+            }))
+        };
+        Self {
+            _subscription: subscription,
+            _peer_id_change_subscription: peer_id_change_subscription,
+            doc,
+            inner,
+        }
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+
+    fn perform(&mut self, kind: loro::undo::UndoOrRedo) -> PyLoroResult<bool> {
+        if self.doc.is_detached() {
+            return Err(LoroError::EditWhenDetached.into());
+        }
+        self.doc.commit();
+        loop {
+            let Some(item) = self.inner().stack(kind).pop_back() else {
+                return Ok(false);
+            };
+            let before = self.doc.oplog_frontiers();
+            let before_vv = self.doc.oplog_vv();
+            let undo = loro::undo::DiffBatch::from(restrict(
+                &self.doc.diff(&item.after, &item.before)?,
+                &item.containers,
+            ));
+            let mut diff = undo.clone();
+            diff.transform(&item.remote, true);
+            // The remote changes made after the item are now based on the
+            // version before it, like the ones made after the next item.
+            let mut remote = item.remote;
+            remote.transform(&undo, false);
+            if let Some(next) = self.inner().stack(kind).back_mut() {
+                next.remote.compose(&remote);
+            }
+
+            let diff = remap_batch(diff.into(), &self.inner().remap);
+            let recreated = deleted_nodes(&self.doc, &diff);
+            self.inner().performing = Some(Vec::new());
+            // Like Loro's own undo manager, the diff is applied on a best
+            // effort basis: concurrent moves can make parts of it fail.
+            let _ = self.doc.apply_diff(diff.clone());
+            self.doc
+                .commit_with(CommitOptions::new().origin(UNDO_ORIGIN));
+            let performed = self.inner().performing.take().unwrap_or_default();
+            learn_remap(&mut self.inner().remap, &diff, recreated, &performed);
+            let containers = performed.into_iter().map(|(id, _)| id).collect();
+
+            let on_pop = self.inner().on_pop.clone();
+            if let Some(on_pop) = on_pop {
+                Python::attach(|py| {
+                    callback::call(
+                        py,
+                        &on_pop,
+                        (
+                            UndoOrRedo::from(kind),
+                            CounterSpan::from(item.span),
+                            resolve_cursors(&self.doc, &item.meta),
+                        ),
+                    );
+                });
+            }
+            let popped_cursors = self.inner().last_popped_cursors.replace(item.meta.cursors);
+
+            let after = self.doc.oplog_frontiers();
+            if after == before {
+                // The item is a no-op now, try the next one.
+                continue;
+            }
+            let peer = self.doc.peer_id();
+            let span = LoroCounterSpan::new(
+                before_vv.get(&peer).copied().unwrap_or(0),
+                self.doc.oplog_vv().get(&peer).copied().unwrap_or(0),
+            );
+            let callback = self.inner().on_push.clone();
+            let mut meta = on_push(callback, opposite(kind), span, None);
+            let mut inner = self.inner();
+            // When undoing for the first time, the cursors are the ones given
+            // by the user. Otherwise, they are the ones of the item popped
+            // before, which were restored before the popped item was applied.
+            let first_undo =
+                kind == loro::undo::UndoOrRedo::Undo && inner.stack(opposite(kind)).is_empty();
+            if let (false, Some(cursors)) = (first_undo, popped_cursors) {
+                meta.cursors = cursors;
+            }
+            inner.push(
+                opposite(kind),
+                Item {
+                    before,
+                    after,
+                    span,
+                    containers,
+                    meta,
+                    timestamp: now(),
+                    remote: Default::default(),
+                },
+            );
+            return Ok(true);
+        }
+    }
+
+    fn export_state(&self) -> Cow<'_, [u8]> {
+        self.doc.commit();
+        let inner = self.inner();
+        let state = EncodedState {
+            version: STATE_VERSION,
+            vv: self.doc.oplog_vv().encode(),
+            undo: inner.undo_stack.iter().map(EncodedItem::from).collect(),
+            redo: inner.redo_stack.iter().map(EncodedItem::from).collect(),
+            remap: inner
+                .remap
+                .iter()
+                .map(|(old, new)| (old.to_string(), new.to_string()))
+                .collect(),
+        };
+        Cow::Owned(serde_json::to_vec(&state).unwrap())
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> PyLoroResult<bool> {
+        let state: EncodedState = serde_json::from_slice(state).map_err(decode_error)?;
+        if state.version != STATE_VERSION {
+            return Err(decode_error(format!(
+                "unsupported undo state version {}",
+                state.version
+            )));
+        }
+        self.doc.commit();
+        let vv = self.doc.oplog_vv();
+        if !vv.includes_vv(&VersionVector::decode(&state.vv)?) {
+            return Ok(false);
+        }
+        let mut stacks = [VecDeque::new(), VecDeque::new()];
+        for (stack, items) in stacks.iter_mut().zip([state.undo, state.redo]) {
+            for item in items {
+                let item = item.decode()?;
+                if !item
+                    .before
+                    .iter()
+                    .chain(item.after.iter())
+                    .all(|id| vv.includes_id(id))
+                {
+                    return Ok(false);
+                }
+                stack.push_back(item);
+            }
+        }
+        // The remote changes made after an item are the ones made before the
+        // next item, or since the item if it is the top of the stack.
+        let current = self.doc.oplog_frontiers();
+        for stack in stacks.iter_mut() {
+            for i in 0..stack.len() {
+                let next = stack.get(i + 1).map_or(&current, |next| &next.before);
+                if stack[i].after != *next {
+                    stack[i].remote = self.doc.diff(&stack[i].after, next)?.into();
+                }
+            }
+        }
+        let mut remap = FxHashMap::default();
+        for (old, new) in state.remap {
+            remap.insert(parse_container_id(&old)?, parse_container_id(&new)?);
+        }
+
+        let [undo_stack, redo_stack] = stacks;
+        let mut inner = self.inner();
+        inner.undo_stack = undo_stack;
+        inner.redo_stack = redo_stack;
+        inner.remap = remap;
+        inner.group = None;
+        inner.last_popped_cursors = None;
+        Ok(true)
+    }
+}
+
+enum Engine {
+    /// Loro's own undo manager of the whole document, and the document.
+    Loro(loro::UndoManager, loro::LoroDoc),
+    Scoped(ScopedUndoManager),
+}
+
 #[pyclass]
-pub struct UndoManager(loro::UndoManager);
+pub struct UndoManager(Engine);
+
+/// The error of exporting or importing the stacks of Loro's undo manager.
+fn not_exportable() -> PyLoroError {
+    UndoError::new_err(
+        "The state of an UndoManager of the whole document can only be exported \
+         if it is created with exportable=True",
+    )
+    .into()
+}
 
 #[pymethods]
 impl UndoManager {
     /// Create a new UndoManager.
+    ///
+    /// If `containers` is given, only the local changes to these containers
+    /// and their descendants are recorded and reverted, so that independent
+    /// parts of a document can have their own undo stacks. The other local
+    /// changes are treated like remote changes. Otherwise, Loro's own undo
+    /// manager records the local changes of the whole document, unless
+    /// `exportable` is true: its stacks cannot be exported, so the local
+    /// changes of the whole document are then recorded the same way as the
+    /// ones of a scoped manager, and `export_state` can be used.
     #[new]
-    pub fn new(doc: &LoroDoc) -> Self {
-        Self(loro::UndoManager::new(&doc.doc))
+    #[pyo3(signature = (doc, containers=None, *, exportable=false))]
+    pub fn new(doc: &LoroDoc, containers: Option<Vec<PyContainerID>>, exportable: bool) -> Self {
+        Self(match containers {
+            Some(ids) => Engine::Scoped(ScopedUndoManager::new(
+                &doc.doc,
+                Some(ids.into_iter().map(Into::into).collect()),
+            )),
+            None if exportable => Engine::Scoped(ScopedUndoManager::new(&doc.doc, None)),
+            None => Engine::Loro(loro::UndoManager::new(&doc.doc), doc.doc.clone()),
+        })
     }
 
     /// Undo the last change made by the peer.
     pub fn undo(&mut self) -> PyLoroResult<bool> {
-        Ok(self.0.undo()?)
+        match &mut self.0 {
+            Engine::Loro(m, _) => Ok(m.undo()?),
+            Engine::Scoped(m) => m.perform(loro::undo::UndoOrRedo::Undo),
+        }
     }
 
     /// Redo the last change made by the peer.
     pub fn redo(&mut self) -> PyLoroResult<bool> {
-        Ok(self.0.redo()?)
+        match &mut self.0 {
+            Engine::Loro(m, _) => Ok(m.redo()?),
+            Engine::Scoped(m) => m.perform(loro::undo::UndoOrRedo::Redo),
+        }
     }
 
     /// How many times the undo manager can undo.
     pub fn undo_count(&self) -> usize {
-        self.0.undo_count()
+        match &self.0 {
+            Engine::Loro(m, _) => m.undo_count(),
+            Engine::Scoped(m) => m.inner().undo_stack.len(),
+        }
     }
 
     /// How many times the undo manager can redo.
     pub fn redo_count(&self) -> usize {
-        self.0.redo_count()
+        match &self.0 {
+            Engine::Loro(m, _) => m.redo_count(),
+            Engine::Scoped(m) => m.inner().redo_stack.len(),
+        }
     }
 
     /// Record a new checkpoint.
     pub fn record_new_checkpoint(&mut self) -> PyLoroResult<()> {
-        Ok(self.0.record_new_checkpoint()?)
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.record_new_checkpoint()?,
+            Engine::Scoped(m) => m.doc.commit(),
+        }
+        Ok(())
     }
 
     /// Whether the undo manager can undo.
     pub fn can_undo(&self) -> bool {
-        self.0.can_undo()
+        self.undo_count() > 0
     }
 
     /// Whether the undo manager can redo.
     pub fn can_redo(&self) -> bool {
-        self.0.can_redo()
+        self.redo_count() > 0
     }
 
     /// If a local event's origin matches the given prefix, it will not be recorded in the
     /// undo stack.
     pub fn add_exclude_origin_prefix(&mut self, prefix: &str) {
-        self.0.add_exclude_origin_prefix(prefix)
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.add_exclude_origin_prefix(prefix),
+            Engine::Scoped(m) => m.inner().exclude_origin_prefixes.push(prefix.to_string()),
+        }
     }
 
     /// Set the maximum number of undo steps. The default value is 100.
     pub fn set_max_undo_steps(&mut self, size: usize) {
-        self.0.set_max_undo_steps(size)
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.set_max_undo_steps(size),
+            Engine::Scoped(m) => m.inner().max_undo_steps = size,
+        }
     }
 
     /// Set the merge interval in ms. The default value is 0, which means no merge.
     pub fn set_merge_interval(&mut self, interval: i64) {
-        self.0.set_merge_interval(interval)
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.set_merge_interval(interval),
+            Engine::Scoped(m) => m.inner().merge_interval = interval,
+        }
     }
 
     /// Set the listener for push events.
     /// The listener will be called when a new undo/redo item is pushed into the stack.
+    ///
+    /// It should return an `UndoItemMeta`, or `None` for no metadata. If it returns
+    /// anything else or raises, the error is reported through `sys.unraisablehook`
+    /// and an empty meta is stored instead.
     pub fn set_on_push(&mut self, on_push: Py<PyAny>) {
-        self.0
-            .set_on_push(Some(Box::new(move |undo_or_redo, span, event| {
-                Python::attach(|py| {
-                    let meta = on_push
-                        .call1(
-                            py,
-                            (
-                                UndoOrRedo::from(undo_or_redo),
-                                CounterSpan::from(span),
-                                event.map(DiffEvent::from),
-                            ),
-                        )
-                        .unwrap()
-                        .extract::<UndoItemMeta>(py)
-                        .unwrap();
-                    loro::undo::UndoItemMeta::from(meta)
-                })
-            })));
+        let callback = Arc::new(on_push);
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.set_on_push(Some(Box::new(move |kind, span, event| {
+                self::on_push(Some(callback.clone()), kind, span, event)
+            }))),
+            Engine::Scoped(m) => m.inner().on_push = Some(callback),
+        }
     }
 
     /// Set the listener for pop events.
     /// The listener will be called when an undo/redo item is popped from the stack.
+    ///
+    /// It is called once the item has been applied, with the cursors of its meta
+    /// resolved in the current version of the document.
     pub fn set_on_pop(&mut self, on_pop: Py<PyAny>) {
-        self.0
-            .set_on_pop(Some(Box::new(move |undo_or_redo, span, meta| {
-                Python::attach(|py| {
-                    on_pop
-                        .call1(
+        let callback = Arc::new(on_pop);
+        match &mut self.0 {
+            Engine::Loro(m, doc) => {
+                let doc = doc.clone();
+                m.set_on_pop(Some(Box::new(move |kind, span, meta| {
+                    Python::attach(|py| {
+                        callback::call(
                             py,
+                            &callback,
                             (
-                                UndoOrRedo::from(undo_or_redo),
+                                UndoOrRedo::from(kind),
                                 CounterSpan::from(span),
-                                UndoItemMeta::from(meta),
+                                resolve_cursors(&doc, &meta),
                             ),
-                        )
-                        .unwrap();
-                })
-            })));
+                        );
+                    })
+                })))
+            }
+            Engine::Scoped(m) => m.inner().on_pop = Some(callback),
+        }
     }
 
     /// Clear the undo stack and the redo stack
     pub fn clear(&self) {
-        self.0.clear();
+        match &self.0 {
+            Engine::Loro(m, _) => m.clear(),
+            Engine::Scoped(m) => {
+                let mut inner = m.inner();
+                inner.undo_stack.clear();
+                inner.redo_stack.clear();
+            }
+        }
     }
 
     /// Will start a new group of changes, all subsequent changes will be merged
@@ -130,39 +875,190 @@ impl UndoManager {
     /// we split the undo item and close the group. If there are no conflict
     /// in changed container ids we continue the group merge.
     pub fn group_start(&mut self) -> PyLoroResult<()> {
-        Ok(self.0.group_start()?)
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.group_start()?,
+            Engine::Scoped(m) => {
+                m.doc.commit();
+                let mut inner = m.inner();
+                if inner.group.is_some() {
+                    return Err(LoroError::UndoGroupAlreadyStarted.into());
+                }
+                inner.group = Some(false);
+            }
+        }
+        Ok(())
     }
 
     /// Ends the current group, calling UndoManager::undo() after this will
     /// undo all changes that occurred during the group.
     pub fn group_end(&mut self) {
-        self.0.group_end()
+        match &mut self.0 {
+            Engine::Loro(m, _) => m.group_end(),
+            Engine::Scoped(m) => {
+                m.doc.commit();
+                m.inner().group = None;
+            }
+        }
     }
 
     /// Get the peer id of the undo manager
     pub fn peer(&self) -> u64 {
-        self.0.peer()
+        match &self.0 {
+            Engine::Loro(m, _) => m.peer(),
+            Engine::Scoped(m) => m.doc.peer_id(),
+        }
     }
 
     /// Get the metadata of the top undo stack item, if any.
     pub fn top_undo_meta(&self) -> Option<UndoItemMeta> {
-        self.0.top_undo_meta().map(UndoItemMeta::from)
+        match &self.0 {
+            Engine::Loro(m, _) => m.top_undo_meta(),
+            Engine::Scoped(m) => m.inner().undo_stack.back().map(|item| item.meta.clone()),
+        }
+        .map(Into::into)
     }
 
     /// Get the metadata of the top redo stack item, if any.
     pub fn top_redo_meta(&self) -> Option<UndoItemMeta> {
-        self.0.top_redo_meta().map(UndoItemMeta::from)
+        match &self.0 {
+            Engine::Loro(m, _) => m.top_redo_meta(),
+            Engine::Scoped(m) => m.inner().redo_stack.back().map(|item| item.meta.clone()),
+        }
+        .map(Into::into)
     }
 
     /// Get the value associated with the top undo stack item, if any.
     pub fn top_undo_value(&self) -> Option<LoroValue> {
-        self.0.top_undo_value().map(Into::into)
+        self.top_undo_meta().map(|meta| meta.value)
     }
 
     /// Get the value associated with the top redo stack item, if any.
     pub fn top_redo_value(&self) -> Option<LoroValue> {
-        self.0.top_redo_value().map(Into::into)
+        self.top_redo_meta().map(|meta| meta.value)
     }
+
+    /// Export the undo and redo stacks, with the metadata of their items.
+    ///
+    /// The state can be imported with `import_state` by an undo manager of the
+    /// same document and the same containers, e.g. after the application
+    /// restarts. An undo manager of the whole document supports it only if it
+    /// is created with `exportable=True`, since the stacks of Loro's own undo
+    /// manager are not accessible.
+    pub fn export_state(&self) -> PyLoroResult<Cow<'_, [u8]>> {
+        match &self.0 {
+            Engine::Loro(..) => Err(not_exportable()),
+            Engine::Scoped(m) => Ok(m.export_state()),
+        }
+    }
+
+    /// Replace the undo and redo stacks with the ones exported by `export_state`.
+    ///
+    /// If the document does not include the version the state was exported
+    /// at, the state is stale: it is discarded, the stacks are left unchanged
+    /// and `False` is returned.
+    pub fn import_state(&mut self, state: &[u8]) -> PyLoroResult<bool> {
+        match &mut self.0 {
+            Engine::Loro(..) => Err(not_exportable()),
+            Engine::Scoped(m) => m.import_state(state),
+        }
+    }
+}
+
+/// The version of the format of `UndoManager.export_state`.
+const STATE_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct EncodedState {
+    version: u8,
+    /// The version of the document the state was exported at.
+    vv: Vec<u8>,
+    undo: Vec<EncodedItem>,
+    redo: Vec<EncodedItem>,
+    remap: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedItem {
+    before: Vec<u8>,
+    after: Vec<u8>,
+    span: (Counter, Counter),
+    containers: Vec<String>,
+    value: loro::LoroValue,
+    cursors: Vec<EncodedCursor>,
+    timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedCursor {
+    cursor: Vec<u8>,
+    pos: usize,
+    side: i32,
+}
+
+impl From<&Item> for EncodedItem {
+    fn from(item: &Item) -> Self {
+        Self {
+            before: item.before.encode(),
+            after: item.after.encode(),
+            span: (item.span.start, item.span.end),
+            containers: item.containers.iter().map(|id| id.to_string()).collect(),
+            value: item.meta.value.clone(),
+            cursors: item
+                .meta
+                .cursors
+                .iter()
+                .map(|c| EncodedCursor {
+                    cursor: c.cursor.encode(),
+                    pos: c.pos.pos,
+                    side: c.pos.side as i32,
+                })
+                .collect(),
+            timestamp: item.timestamp,
+        }
+    }
+}
+
+impl EncodedItem {
+    fn decode(self) -> PyLoroResult<Item> {
+        let cursors = self
+            .cursors
+            .into_iter()
+            .map(|c| {
+                Ok(loro::undo::CursorWithPos {
+                    cursor: loro::cursor::Cursor::decode(&c.cursor).map_err(decode_error)?,
+                    pos: loro::cursor::AbsolutePosition {
+                        pos: c.pos,
+                        side: loro::cursor::Side::from_i32(c.side)
+                            .ok_or_else(|| decode_error(format!("invalid side {}", c.side)))?,
+                    },
+                })
+            })
+            .collect::<PyLoroResult<_>>()?;
+        Ok(Item {
+            before: Frontiers::decode(&self.before)?,
+            after: Frontiers::decode(&self.after)?,
+            span: LoroCounterSpan::new(self.span.0, self.span.1),
+            containers: self
+                .containers
+                .iter()
+                .map(|id| parse_container_id(id))
+                .collect::<PyLoroResult<_>>()?,
+            meta: loro::undo::UndoItemMeta {
+                value: self.value,
+                cursors,
+            },
+            timestamp: self.timestamp,
+            remote: Default::default(),
+        })
+    }
+}
+
+fn decode_error(msg: impl ToString) -> PyLoroError {
+    LoroError::DecodeError(msg.to_string().into_boxed_str()).into()
+}
+
+fn parse_container_id(id: &str) -> PyLoroResult<ContainerID> {
+    ContainerID::try_from(id).map_err(|_| decode_error(format!("invalid container id {id}")))
 }
 
 #[pyclass(eq, eq_int)]
@@ -172,6 +1068,7 @@ pub enum UndoOrRedo {
     Redo,
 }
 
+/// The metadata of an undo/redo item, returned by the `on_push` listener.
 #[pyclass(get_all)]
 #[derive(Debug, Clone)]
 pub struct UndoItemMeta {
@@ -179,9 +1076,30 @@ pub struct UndoItemMeta {
     pub cursors: Vec<CursorWithPos>,
 }
 
+#[pymethods]
+impl UndoItemMeta {
+    #[new]
+    #[pyo3(signature = (value=None, cursors=None))]
+    pub fn new(value: Option<LoroValue>, cursors: Option<Vec<CursorWithPos>>) -> Self {
+        Self {
+            value: value.unwrap_or(LoroValue(loro::LoroValue::Null)),
+            cursors: cursors.unwrap_or_default(),
+        }
+    }
+}
+
+/// A cursor and its position when the undo/redo item was pushed.
 #[pyclass(get_all)]
 #[derive(Debug, Clone)]
 pub struct CursorWithPos {
     pub cursor: Cursor,
     pub pos: AbsolutePosition,
 }
+
+#[pymethods]
+impl CursorWithPos {
+    #[new]
+    pub fn new(cursor: Cursor, pos: AbsolutePosition) -> Self {
+        Self { cursor, pos }
+    }
+}
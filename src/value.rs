@@ -1,10 +1,11 @@
 use loro::{Counter, PeerID};
-use pyo3::prelude::*;
+use pyo3::{prelude::*, types::PyType};
 use std::fmt::Display;
 
 use crate::{
     container::Container,
     convert::{loro_value_to_pyobject, pyobject_to_loro_value},
+    err::InvalidArgumentError,
 };
 
 pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -18,7 +19,7 @@ pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     Ok(())
 }
 
-#[pyclass(eq, str, get_all, set_all)]
+#[pyclass(eq, str, get_all, set_all, module = "loro")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ID {
     pub peer: u64,
@@ -37,6 +38,10 @@ impl ID {
     pub fn new(peer: u64, counter: i32) -> Self {
         Self { peer, counter }
     }
+
+    pub fn __getnewargs__(&self) -> (u64, i32) {
+        (self.peer, self.counter)
+    }
 }
 
 #[pyclass(eq, str, get_all, set_all)]
@@ -70,7 +75,7 @@ impl Display for ContainerType {
     }
 }
 
-#[pyclass(eq, str, hash)]
+#[pyclass(eq, str, hash, module = "loro")]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ContainerID {
     Root {
@@ -90,6 +95,23 @@ impl Display for ContainerID {
     }
 }
 
+#[pymethods]
+impl ContainerID {
+    /// Parse a container id from its canonical string form,
+    /// e.g. `cid:root-text:Text` or `cid:0@1:Map`.
+    #[classmethod]
+    pub fn parse(_cls: &Bound<'_, PyType>, s: &str) -> PyResult<Self> {
+        loro::ContainerID::try_from(s)
+            .map(Self::from)
+            .map_err(|_| InvalidArgumentError::new_err(format!("Invalid ContainerID: {s}")))
+    }
+
+    pub fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        let parse = py.get_type::<Self>().getattr("parse")?;
+        Ok((parse, (loro::ContainerID::from(self).to_string(),)))
+    }
+}
+
 #[pyclass(eq, str, eq_int)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Ordering {
@@ -114,7 +136,7 @@ impl From<std::cmp::Ordering> for Ordering {
     }
 }
 
-#[pyclass(eq, str, get_all, set_all)]
+#[pyclass(eq, str, get_all, set_all, module = "loro")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TreeID {
     pub peer: PeerID,
@@ -133,6 +155,10 @@ impl TreeID {
     pub fn new(peer: PeerID, counter: Counter) -> Self {
         Self { peer, counter }
     }
+
+    pub fn __getnewargs__(&self) -> (PeerID, Counter) {
+        (self.peer, self.counter)
+    }
 }
 
 pub type TreeParentId = Option<TreeID>;
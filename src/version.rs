@@ -19,7 +19,7 @@ pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
     Ok(())
 }
 
-#[pyclass(str)]
+#[pyclass(str, module = "loro")]
 #[derive(Debug, Clone, Default)]
 pub struct Frontiers(loro::Frontiers);
 
@@ -58,6 +58,14 @@ impl Frontiers {
         let ans = Self(loro::Frontiers::decode(bytes.as_bytes())?);
         Ok(ans)
     }
+
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let decode = py.get_type::<Self>().getattr("decode")?;
+        Ok((decode, (PyBytes::new(py, &self.0.encode()),)))
+    }
 }
 
 impl From<Frontiers> for loro::Frontiers {
@@ -78,7 +86,7 @@ impl From<&Frontiers> for loro::Frontiers {
     }
 }
 
-#[pyclass(str)]
+#[pyclass(str, module = "loro")]
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct VersionRange(loro::VersionRange);
 
@@ -158,6 +166,14 @@ impl VersionRange {
     pub fn inner(&self) -> HashMap<PeerID, (Counter, Counter)> {
         self.0.inner().iter().map(|(k, v)| (*k, *v)).collect()
     }
+
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyDict>,))> {
+        let from_map = py.get_type::<Self>().getattr("from_map")?;
+        Ok((from_map, (self.inner().into_pyobject(py)?,)))
+    }
 }
 
 impl Display for VersionRange {
@@ -178,7 +194,7 @@ impl From<loro::VersionRange> for VersionRange {
     }
 }
 
-#[pyclass(str)]
+#[pyclass(str, module = "loro")]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VersionVector(loro::VersionVector);
 
@@ -323,6 +339,14 @@ impl VersionVector {
         Ok(ans)
     }
 
+    pub fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let decode = py.get_type::<Self>().getattr("decode")?;
+        Ok((decode, (PyBytes::new(py, &self.0.encode()),)))
+    }
+
     fn __richcmp__(&self, other: PyRef<Self>, op: CompareOp) -> PyResult<bool> {
         match op {
             CompareOp::Eq => Ok(self.0 == other.0),
@@ -0,0 +1,139 @@
+//! Read-only views of a document at past versions.
+//!
+//! A view is backed by a private document imported from a snapshot of the
+//! viewed document at the requested version, so it does not detach the viewed
+//! document, and only holds the history up to that version. A state-only
+//! export would be smaller, but Loro drops the whole state from it when a map
+//! holds a list of maps. The private document is detached, which makes the
+//! containers returned by a view read-only: editing them raises
+//! `DetachedEditError`.
+use loro::{ExportMode, LoroDoc as LoroDocInner};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::borrow::Cow;
+
+use crate::{
+    container::{LoroCounter, LoroList, LoroMap, LoroMovableList, LoroText, LoroTree},
+    convert::pyobject_to_container_id,
+    err::{PyLoroResult, VersionNotFoundError},
+    event::Index,
+    value::{ContainerType, LoroValue, ValueOrContainer},
+    version::Frontiers,
+};
+
+pub fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<DocView>()?;
+    Ok(())
+}
+
+/// A read-only view of a document at a given version, created by
+/// `LoroDoc.view_at`.
+///
+/// The view does not change when the document is edited, and several views
+/// of different versions can be held at the same time.
+#[pyclass(frozen)]
+pub struct DocView {
+    doc: LoroDocInner,
+    frontiers: loro::Frontiers,
+}
+
+impl DocView {
+    pub fn new(doc: &LoroDocInner, frontiers: loro::Frontiers) -> PyLoroResult<Self> {
+        Ok(Self {
+            doc: state_at(doc, &frontiers)?,
+            frontiers,
+        })
+    }
+}
+
+/// A detached document with the state of `doc` at `frontiers`, and the
+/// history up to that version.
+pub fn state_at(doc: &LoroDocInner, frontiers: &loro::Frontiers) -> PyLoroResult<LoroDocInner> {
+    if doc.frontiers_to_vv(frontiers).is_none() {
+        return Err(VersionNotFoundError::new_err(format!(
+            "Frontiers {frontiers:?} are not included in the document"
+        ))
+        .into());
+    }
+    let state = LoroDocInner::new();
+    if !frontiers.is_empty() {
+        let version = Cow::Borrowed(frontiers);
+        state.import(&doc.export(ExportMode::SnapshotAt { version })?)?;
+    }
+    state.detach();
+    Ok(state)
+}
+
+#[pymethods]
+impl DocView {
+    /// The version of the view.
+    #[getter]
+    pub fn frontiers(&self) -> Frontiers {
+        self.frontiers.clone().into()
+    }
+
+    /// Get the shallow value of the document at the version of the view.
+    pub fn get_value(&self) -> LoroValue {
+        self.doc.get_value().into()
+    }
+
+    /// Get the entire state of the document at the version of the view.
+    pub fn get_deep_value(&self) -> LoroValue {
+        self.doc.get_deep_value().into()
+    }
+
+    /// Get a read-only [LoroMovableList] by container id.
+    pub fn get_movable_list(&self, obj: &Bound<'_, PyAny>) -> PyResult<LoroMovableList> {
+        let container_id = pyobject_to_container_id(obj, ContainerType::MovableList {})?;
+        Ok(LoroMovableList(self.doc.get_movable_list(container_id)))
+    }
+
+    /// Get a read-only [LoroList] by container id.
+    pub fn get_list(&self, obj: &Bound<'_, PyAny>) -> PyResult<LoroList> {
+        let container_id = pyobject_to_container_id(obj, ContainerType::List {})?;
+        Ok(LoroList(self.doc.get_list(container_id)))
+    }
+
+    /// Get a read-only [LoroMap] by container id.
+    pub fn get_map(&self, obj: &Bound<'_, PyAny>) -> PyResult<LoroMap> {
+        let container_id = pyobject_to_container_id(obj, ContainerType::Map {})?;
+        Ok(LoroMap(self.doc.get_map(container_id)))
+    }
+
+    /// Get a read-only [LoroText] by container id.
+    pub fn get_text(&self, obj: &Bound<'_, PyAny>) -> PyResult<LoroText> {
+        let container_id = pyobject_to_container_id(obj, ContainerType::Text {})?;
+        Ok(LoroText(self.doc.get_text(container_id)))
+    }
+
+    /// Get a read-only [LoroTree] by container id.
+    pub fn get_tree(&self, obj: &Bound<'_, PyAny>) -> PyResult<LoroTree> {
+        let container_id = pyobject_to_container_id(obj, ContainerType::Tree {})?;
+        Ok(LoroTree(self.doc.get_tree(container_id)))
+    }
+
+    /// Get a read-only [LoroCounter] by container id.
+    pub fn get_counter(&self, obj: &Bound<'_, PyAny>) -> PyResult<LoroCounter> {
+        let container_id = pyobject_to_container_id(obj, ContainerType::Counter {})?;
+        Ok(LoroCounter(self.doc.get_counter(container_id)))
+    }
+
+    /// Get the value or container by the path.
+    pub fn get_by_path(&self, path: Vec<Index>) -> Option<ValueOrContainer> {
+        self.doc
+            .get_by_path(&path.iter().map(|x| x.into()).collect::<Vec<_>>())
+            .map(ValueOrContainer::from)
+    }
+
+    /// Get the value or container by the string path.
+    pub fn get_by_str_path(&self, path: &str) -> Option<ValueOrContainer> {
+        self.doc.get_by_str_path(path).map(ValueOrContainer::from)
+    }
+
+    /// Evaluate a JSONPath expression on the document at the version of the view.
+    pub fn jsonpath(&self, path: &str) -> PyResult<Vec<ValueOrContainer>> {
+        self.doc
+            .jsonpath(path)
+            .map(|vec| vec.into_iter().map(|v| v.into()).collect())
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
@@ -0,0 +1,118 @@
+import asyncio
+import pytest
+
+from loro import (
+    DiffEvent,
+    EphemeralStore,
+    EphemeralStoreEvent,
+    LoroDoc,
+    OverflowPolicy,
+    StreamOverflowError,
+)
+
+
+def test_events_async_iterator():
+    async def main():
+        doc = LoroDoc()
+        events = doc.events()
+        doc.get_text("text").insert(0, "hello")
+        doc.commit()
+        event = await events.__anext__()
+        assert isinstance(event, DiffEvent)
+        assert event.events[0].target == doc.get_text("text").id
+
+    asyncio.run(main())
+
+
+def test_events_wait_for_commit_from_other_thread():
+    async def main():
+        doc = LoroDoc()
+        text = doc.get_text("text")
+        events = doc.events(text.id)
+
+        def edit():
+            text.insert(0, "a")
+            doc.commit()
+
+        loop = asyncio.get_running_loop()
+        waiter = asyncio.ensure_future(events.__anext__())
+        await asyncio.sleep(0)
+        await loop.run_in_executor(None, edit)
+        event = await asyncio.wait_for(waiter, 5)
+        assert event.current_target == text.id
+
+    asyncio.run(main())
+
+
+def test_close_stops_iteration_after_buffered_items():
+    async def main():
+        doc = LoroDoc()
+        events = doc.events()
+        doc.get_map("map").insert("a", 1)
+        doc.commit()
+        events.close()
+        doc.get_map("map").insert("b", 1)
+        doc.commit()
+        assert events.closed
+        return [e async for e in events]
+
+    assert len(asyncio.run(main())) == 1
+
+
+def test_close_wakes_pending_consumer():
+    async def main():
+        events = LoroDoc().events()
+        consumer = asyncio.ensure_future(events.__anext__())
+        await asyncio.sleep(0)
+        events.close()
+        with pytest.raises(StopAsyncIteration):
+            await asyncio.wait_for(consumer, 5)
+
+    asyncio.run(main())
+
+
+def test_overflow_error():
+    async def main():
+        doc = LoroDoc()
+        updates = doc.local_updates(maxsize=2)
+        for i in range(3):
+            doc.get_map("map").insert("k", i)
+            doc.commit()
+        assert len(updates) == 2
+        assert isinstance(await updates.__anext__(), bytes)
+        await updates.__anext__()
+        with pytest.raises(StreamOverflowError):
+            await updates.__anext__()
+
+    asyncio.run(main())
+
+
+def test_overflow_drop_oldest():
+    async def main():
+        doc = LoroDoc()
+        updates = doc.local_updates(maxsize=1, overflow=OverflowPolicy.DropOldest)
+        for i in range(3):
+            doc.get_map("map").insert("k", i)
+            doc.commit()
+        assert len(updates) == 1
+        assert not updates.closed
+        await updates.__anext__()
+        assert len(updates) == 0
+
+    asyncio.run(main())
+
+
+def test_ephemeral_store_streams():
+    async def main():
+        store = EphemeralStore(30000)
+        updates = store.updates()
+        local = store.local_updates()
+        store.set("cursor", 1)
+        event = await updates.__anext__()
+        assert isinstance(event, EphemeralStoreEvent)
+        assert event.added == ["cursor"]
+        other = EphemeralStore(30000)
+        other.apply(await local.__anext__())
+        assert other.get("cursor") == 1
+
+    asyncio.run(main())
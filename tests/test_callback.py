@@ -0,0 +1,109 @@
+import sys
+
+import pytest
+
+from loro import EphemeralStore, ID, LoroDoc
+
+
+class _Unraisable:
+    def __init__(self):
+        self.errors = []
+
+    def __enter__(self):
+        self._old = sys.unraisablehook
+        sys.unraisablehook = lambda args: self.errors.append(args.exc_value)
+        return self
+
+    def __exit__(self, *exc):
+        sys.unraisablehook = self._old
+
+
+def test_subscriber_exception_is_reported():
+    doc = LoroDoc()
+    calls = []
+
+    def callback(event):
+        calls.append(event)
+        raise RuntimeError("boom")
+
+    sub = doc.subscribe_root(callback)
+    with _Unraisable() as hook:
+        doc.get_text("text").insert(0, "abc")
+        doc.commit()
+        doc.get_text("text").insert(0, "def")
+        doc.commit()
+
+    assert len(calls) == 2
+    assert len(hook.errors) == 2
+    assert isinstance(hook.errors[0], RuntimeError)
+    sub.unsubscribe()
+
+
+def test_local_update_non_bool_return_keeps_subscription():
+    doc = LoroDoc()
+    updates = []
+    sub = doc.subscribe_local_update(lambda u: updates.append(u))
+    doc.get_text("text").insert(0, "a")
+    doc.commit()
+    doc.get_text("text").insert(0, "b")
+    doc.commit()
+    assert len(updates) == 2
+    sub.unsubscribe()
+
+
+def test_local_update_false_unsubscribes():
+    doc = LoroDoc()
+    updates = []
+
+    def callback(u):
+        updates.append(u)
+        return False
+
+    sub = doc.subscribe_local_update(callback)
+    doc.get_text("text").insert(0, "a")
+    doc.commit()
+    doc.get_text("text").insert(0, "b")
+    doc.commit()
+    assert len(updates) == 1
+    sub.unsubscribe()
+
+
+def test_ephemeral_subscriber_exception_is_reported():
+    store = EphemeralStore(1000)
+
+    def callback(event):
+        raise ValueError("bad")
+
+    sub = store.subscribe(callback)
+    with _Unraisable() as hook:
+        store.set("key", 1)
+    assert len(hook.errors) == 1
+    sub.unsubscribe()
+
+
+def test_for_each_reraises():
+    doc = LoroDoc()
+    lst = doc.get_list("list")
+    lst.push(1)
+    lst.push(2)
+    seen = []
+
+    def callback(v):
+        seen.append(v)
+        raise KeyError("stop")
+
+    with pytest.raises(KeyError):
+        lst.for_each(callback)
+    assert len(seen) == 1
+
+
+def test_travel_change_ancestors_reraises():
+    doc = LoroDoc()
+    doc.get_text("text").insert(0, "a")
+    doc.commit()
+
+    def callback(meta):
+        raise RuntimeError("travel")
+
+    with pytest.raises(RuntimeError):
+        doc.travel_change_ancestors([ID(doc.peer_id, 0)], callback)
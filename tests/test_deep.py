@@ -0,0 +1,86 @@
+import pytest
+from loro import (
+    DeepPolicy,
+    ExportMode,
+    InvalidArgumentError,
+    LoroDoc,
+    LoroList,
+    LoroMap,
+    LoroMovableList,
+    LoroText,
+)
+
+
+def test_set_deep_creates_containers_that_merge():
+    doc = LoroDoc()
+    todo = doc.get_map("todo")
+    todo.set_deep(
+        "item",
+        {"title": "Buy milk", "tags": ["home"], "meta": {"id": "a1"}},
+        DeepPolicy(text_keys=["title"], value_keys=["meta"]),
+    )
+    doc.commit()
+    item = todo.get("item").container
+    assert isinstance(item, LoroMap)
+    assert isinstance(item.get("title").container, LoroText)
+    assert isinstance(item.get("tags").container, LoroList)
+    assert item.get("meta").value == {"id": "a1"}
+
+    other = doc.fork()
+    other.get_map("todo").get("item").container.get("tags").container.push("urgent")
+    other.commit()
+    item.get("title").container.insert(8, " and eggs")
+    doc.commit()
+    doc.import_(other.export(ExportMode.Updates(doc.oplog_vv)))
+    assert doc.get_deep_value()["todo"]["item"] == {
+        "title": "Buy milk and eggs",
+        "tags": ["home", "urgent"],
+        "meta": {"id": "a1"},
+    }
+
+
+def test_update_deep_only_edits_changed_parts():
+    doc = LoroDoc()
+    todo = doc.get_map("todo")
+    policy = DeepPolicy(text_keys=["title"], movable_lists=True)
+    value = {"title": "Buy milk", "tags": ["a", "b", "c"], "done": False}
+    todo.set_deep("item", value, policy)
+    doc.commit()
+    item = todo.get("item").container
+    tags = item.get("tags").container
+    assert isinstance(tags, LoroMovableList)
+
+    ops = doc.len_ops
+    todo.update_deep("item", value, policy)
+    doc.commit()
+    assert doc.len_ops == ops
+
+    todo.update_deep(
+        "item", {"title": "Buy oat milk", "tags": ["a", "c", "d"], "due": 3}, policy
+    )
+    doc.commit()
+    assert todo.get("item").container.id == item.id
+    assert item.get("tags").container.id == tags.id
+    assert item.get_deep_value() == {
+        "title": "Buy oat milk",
+        "tags": ["a", "c", "d"],
+        "due": 3,
+    }
+    # Inserting "oat ", deleting "b", inserting "d", deleting "done" and setting "due".
+    assert doc.len_ops == ops + 4 + 1 + 1 + 1 + 1
+
+
+def test_from_json():
+    doc = LoroDoc.from_json(
+        {"notes": "Hello", "todos": [{"title": "a"}], "settings": {"dark": True}},
+        DeepPolicy(text_keys=["notes"]),
+    )
+    assert doc.get_text("notes").to_string() == "Hello"
+    assert isinstance(doc.get_list("todos")[0].container, LoroMap)
+    assert doc.get_deep_value() == {
+        "notes": "Hello",
+        "todos": [{"title": "a"}],
+        "settings": {"dark": True},
+    }
+    with pytest.raises(InvalidArgumentError):
+        LoroDoc.from_json({"count": 1})
@@ -0,0 +1,104 @@
+from loro import Diff, DiffBatch, LoroDoc, LoroMap, LoroText, TextDelta
+
+
+def test_diff_report():
+    doc = LoroDoc()
+    users = doc.get_map("users")
+    alice = users.insert_container("alice", LoroMap())
+    alice.insert("name", "Alice")
+    bio = alice.insert_container("bio", LoroText())
+    bio.insert(0, "line one\nline two\nline three\n")
+    todos = doc.get_list("todos")
+    todos.push("a")
+    todos.push("b")
+    doc.get_counter("visits").increment(2)
+    doc.commit()
+    v1 = doc.oplog_frontiers
+
+    alice.insert("name", "Alicia")
+    bio.delete(9, 8)
+    bio.insert(9, "line 2")
+    todos.delete(0, 1)
+    todos.push_container(LoroMap()).insert("done", True)
+    doc.get_counter("visits").decrement(5)
+    doc.commit()
+
+    report = doc.diff(v1, doc.oplog_frontiers).to_report()
+    entries = {entry.path: entry for entry in report.entries}
+    assert set(entries) == {"/users/alice", "/users/alice/bio", "/todos", "/todos/1", "/visits"}
+
+    assert entries["/users/alice"].changes == ['set "name" to "Alicia"']
+    assert entries["/users/alice"].json_patch == [
+        {"op": "add", "path": "/users/alice/name", "value": "Alicia"}
+    ]
+    assert entries["/todos"].json_patch == [
+        {"op": "remove", "path": "/todos/0"},
+        {"op": "add", "path": "/todos/1", "value": {}},
+    ]
+    assert entries["/todos/1"].json_patch == [
+        {"op": "add", "path": "/todos/1/done", "value": True}
+    ]
+    assert entries["/visits"].changes == ["decremented by 5"]
+
+    bio_entry = entries["/users/alice/bio"]
+    assert bio_entry.json_patch is None
+    assert bio_entry.changes == [
+        "deleted 8 characters at 9",
+        'inserted "line 2" at 9',
+    ]
+    assert bio_entry.unified_diff == (
+        "--- a/users/alice/bio\n"
+        "+++ b/users/alice/bio\n"
+        "@@ -1,3 +1,3 @@\n"
+        " line one\n"
+        "-line two\n"
+        "+line 2\n"
+        " line three\n"
+    )
+    assert "/users/alice/bio (Text)\n  deleted 8 characters at 9\n" in str(report)
+
+
+def test_diff_report_without_source():
+    batch = DiffBatch()
+    text = LoroDoc().get_text("text").id
+    delta = [TextDelta.Retain(2, None), TextDelta.Insert("!", None)]
+    batch.push(text, Diff.Text(delta))
+    [entry] = batch.to_report().entries
+    assert entry.path == "cid:root-text:Text"
+    assert entry.changes == ['inserted "!" at 2']
+    assert entry.unified_diff is None
+
+
+def test_diff_report_paths_at_target_version():
+    doc = LoroDoc()
+    todos = doc.get_list("todos")
+    todo = todos.push_container(LoroMap())
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    todo.insert("done", True)
+    doc.commit()
+    v2 = doc.oplog_frontiers
+
+    todos.insert(0, "first")
+    doc.commit()
+    [entry] = doc.diff(v1, v2).to_report().entries
+    assert entry.path == "/todos/0"
+    assert entry.json_patch == [{"op": "add", "path": "/todos/0/done", "value": True}]
+
+
+def test_diff_report_skips_keys_added_and_deleted():
+    doc = LoroDoc()
+    m = doc.get_map("m")
+    m.insert("kept", 1)
+    m.insert("gone", 1)
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    m.insert("x", 1)
+    doc.commit()
+    m.delete("x")
+    m.delete("gone")
+    doc.commit()
+
+    [entry] = doc.diff(v1, doc.oplog_frontiers).to_report().entries
+    assert entry.changes == ['deleted "gone"']
+    assert entry.json_patch == [{"op": "remove", "path": "/m/gone"}]
@@ -0,0 +1,53 @@
+import pytest
+
+from loro import (
+    DecodeError,
+    DetachedEditError,
+    LoroDoc,
+    LoroError,
+    OutOfBoundError,
+    VersionNotFoundError,
+    Frontiers,
+    ID,
+)
+
+
+def test_out_of_bound_error_attributes():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    text.insert(0, "abc")
+
+    with pytest.raises(OutOfBoundError) as exc:
+        text.insert(10, "x")
+    assert exc.value.pos == 10
+    assert exc.value.len == 3
+    assert isinstance(exc.value, LoroError)
+
+
+def test_decode_error():
+    doc = LoroDoc()
+    with pytest.raises(DecodeError):
+        doc.import_(b"not a loro blob")
+
+
+def test_edit_when_detached():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    text.insert(0, "abc")
+    doc.commit()
+    doc.checkout(Frontiers())
+
+    with pytest.raises(DetachedEditError):
+        text.insert(0, "x")
+
+
+def test_version_not_found():
+    doc = LoroDoc()
+    with pytest.raises(VersionNotFoundError) as exc:
+        doc.checkout(Frontiers.from_id(ID(1, 10)))
+    assert exc.value.id == ID(1, 10)
+
+
+def test_loro_error_is_not_base_exception_only():
+    assert issubclass(LoroError, Exception)
+    assert not issubclass(KeyboardInterrupt, LoroError)
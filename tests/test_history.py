@@ -0,0 +1,81 @@
+import pytest
+
+from loro import (
+    ContainerID,
+    ExportMode,
+    Frontiers,
+    ID,
+    LoroDoc,
+    LoroText,
+    OpContent,
+    VersionNotFoundError,
+    VersionVector,
+)
+
+
+def make_doc():
+    doc = LoroDoc()
+    doc.peer_id = 1
+    doc.get_text("text").insert(0, "hi")
+    doc.commit_with(timestamp=100, commit_msg="first")
+    doc.get_map("map").insert("k", 1)
+    doc.commit_with(timestamp=200, commit_msg="second")
+    other = LoroDoc()
+    other.peer_id = 2
+    other.import_(doc.export(ExportMode.Snapshot()))
+    other.get_map("map").delete("k")
+    other.commit_with(timestamp=300)
+    doc.import_(other.export(ExportMode.Updates(doc.oplog_vv)))
+    return doc
+
+
+def test_changes_decodes_ops_in_causal_order():
+    doc = make_doc()
+    changes = list(doc.changes())
+    assert [c.message for c in changes] == ["first", "second", None]
+    assert [c.id.peer for c in changes] == [1, 1, 2]
+    insert = changes[0].ops[0]
+    assert insert.container == ContainerID.Root("text", insert.container.container_type)
+    assert isinstance(insert.content, OpContent.TextInsert)
+    assert insert.content.text == "hi"
+    assert insert.id.peer == 1
+    assert isinstance(changes[1].ops[0].content, OpContent.MapInsert)
+    assert changes[1].ops[0].content.value == 1
+    delete = changes[2].ops[0]
+    assert isinstance(delete.content, OpContent.MapDelete)
+    assert delete.lamport > insert.lamport
+
+
+def test_changes_between_versions():
+    doc = make_doc()
+    start = Frontiers.from_id(ID(1, 0))
+    changes = list(doc.changes(from_=start))
+    assert [c.message for c in changes] == ["first", "second", None]
+    assert changes[0].len == 1
+    assert changes[0].ops[0].content.text == "i"
+    vv = VersionVector()
+    vv.set_end(ID(1, 1))
+    assert [c.message for c in doc.changes(to=vv)] == ["first"]
+
+
+def test_changes_filters():
+    doc = make_doc()
+    assert [c.id.peer for c in doc.changes(peers=[2])] == [2]
+    assert [c.message for c in doc.changes(start_time=150, end_time=250)] == ["second"]
+    map_id = doc.get_map("map").id
+    changes = list(doc.changes(containers=[map_id]))
+    assert len(changes) == 2
+    assert all(op.container == map_id for c in changes for op in c.ops)
+
+
+def test_changes_unknown_version():
+    doc = make_doc()
+    vv = VersionVector()
+    vv.set_end(ID(3, 10))
+    with pytest.raises(VersionNotFoundError):
+        doc.changes(to=vv)
+
+
+def test_get_change_has_no_ops():
+    doc = make_doc()
+    assert doc.get_change(next(doc.changes()).id).ops is None
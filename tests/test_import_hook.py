@@ -0,0 +1,137 @@
+import pytest
+from loro import ExportMode, ImportRejectedError, LoroDoc, OpContent, VersionVector
+
+
+def make_peer(peer, container, value):
+    doc = LoroDoc()
+    doc.peer_id = peer
+    doc.get_map(container).insert("value", value)
+    doc.commit()
+    return doc
+
+
+def test_hook_receives_decoded_changes():
+    alice = make_peer(1, "notes", "hi")
+    seen = []
+
+    def check(meta, changes):
+        seen.append((meta.change_num, changes))
+
+    doc = LoroDoc()
+    status = doc.import_checked(alice.export(ExportMode.Snapshot()), check, origin="sync")
+    assert status.rejected is None
+    ((change_num, changes),) = seen
+    assert change_num == 1
+    (change,) = changes
+    assert change.id.peer == 1
+    (op,) = change.ops
+    assert op.container == doc.get_map("notes").id
+    assert isinstance(op.content, OpContent.MapInsert)
+    assert doc.get_deep_value() == {"notes": {"value": "hi"}}
+
+
+def test_reject_whole_blob():
+    alice = make_peer(1, "notes", "hi")
+    doc = LoroDoc()
+    with pytest.raises(ImportRejectedError):
+        doc.import_checked(alice.export(ExportMode.Snapshot()), lambda meta, changes: False)
+    with pytest.raises(ZeroDivisionError):
+        doc.import_checked(alice.export(ExportMode.Snapshot()), lambda meta, changes: 1 / 0)
+    assert doc.get_deep_value() == {}
+    assert doc.oplog_vv == VersionVector()
+
+
+def test_reject_peers_and_their_dependents():
+    # "admin" may only be edited by peer 1.
+    alice = make_peer(1, "admin", "root")
+    bob = LoroDoc()
+    bob.peer_id = 2
+    bob.import_(alice.export(ExportMode.Snapshot()))
+    bob.get_map("admin").insert("value", "hacked")
+    bob.get_map("notes").insert("value", "hi")
+    bob.commit()
+    carol = LoroDoc()
+    carol.peer_id = 3
+    carol.import_(bob.export(ExportMode.Snapshot()))
+    carol.get_map("notes").insert("value", "depends on bob")
+    carol.commit()
+    dave = make_peer(4, "notes", "independent")
+    carol.import_(dave.export(ExportMode.Snapshot()))
+
+    def permissions(meta, changes):
+        return {
+            change.id.peer
+            for change in changes
+            for op in change.ops
+            if op.container == carol.get_map("admin").id and change.id.peer != 1
+        }
+
+    doc = LoroDoc()
+    status = doc.import_checked(carol.export(ExportMode.Snapshot()), permissions)
+    assert status.rejected.get(2) is not None
+    assert status.rejected.get(3) is not None
+    assert status.success.get(1) is not None
+    assert status.success.get(4) is not None
+    assert doc.get_deep_value() == {
+        "admin": {"value": "root"},
+        "notes": {"value": "independent"},
+    }
+
+
+def test_pending_changes_are_not_imported():
+    alice = make_peer(1, "notes", "a")
+    before = alice.oplog_vv
+    alice.get_map("notes").insert("value", "b")
+    alice.commit()
+    doc = LoroDoc()
+    status = doc.import_checked(alice.export(ExportMode.Updates(before)), lambda m, c: None)
+    assert status.pending is not None
+    assert doc.oplog_vv == VersionVector()
+
+
+def test_successive_imports_see_only_new_changes():
+    alice = make_peer(1, "notes", "a")
+    doc = LoroDoc()
+    doc.peer_id = 9
+    seen = []
+
+    def check(meta, changes):
+        seen.append(sorted(c.id.peer for c in changes))
+        return [2]
+
+    doc.import_checked(alice.export(ExportMode.Snapshot()), check)
+    # Local edits and plain imports between checked imports are accounted for.
+    doc.get_map("local").insert("value", 1)
+    doc.commit()
+    since = alice.oplog_vv
+    alice.get_map("notes").insert("value", "b")
+    alice.commit()
+    doc.import_(alice.export(ExportMode.Updates(since)))
+    # Bob edits concurrently with the latest changes of the document.
+    bob = LoroDoc()
+    bob.peer_id = 2
+    bob.import_(alice.export(ExportMode.Snapshot()))
+    carol = LoroDoc()
+    carol.peer_id = 3
+    carol.import_(alice.export(ExportMode.Updates(VersionVector())))
+    since = alice.oplog_vv
+    alice.get_map("notes").insert("value", "c")
+    alice.commit()
+    doc.import_checked(alice.export(ExportMode.Updates(since)), check)
+    carol.get_map("carol").insert("value", 3)
+    carol.commit()
+    status = doc.import_checked(carol.export(ExportMode.Updates(alice.oplog_vv)), check)
+    assert status.rejected is None
+    bob.get_map("notes").insert("value", "bob")
+    bob.commit()
+    status = doc.import_checked(bob.export(ExportMode.Updates(doc.oplog_vv)), check)
+    assert status.rejected.get(2) is not None
+    carol.get_map("carol").insert("value", 4)
+    carol.commit()
+    doc.import_checked(carol.export(ExportMode.Snapshot()), check)
+    assert seen == [[1], [1], [3], [2], [3]]
+    assert doc.get_deep_value() == {
+        "notes": {"value": "c"},
+        "local": {"value": 1},
+        "carol": {"value": 4},
+    }
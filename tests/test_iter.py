@@ -0,0 +1,139 @@
+from collections.abc import Mapping, MutableMapping, MutableSequence
+
+import pytest
+
+from loro import LoroDoc, LoroList, LoroMap, LoroText, ValueOrContainer
+
+
+def unwrap(v):
+    assert ValueOrContainer.is_value(v)
+    return v.value
+
+
+def test_list_iteration():
+    doc = LoroDoc()
+    lst = doc.get_list("list")
+    lst.extend([1, 2, 3])
+    assert [unwrap(v) for v in lst] == [1, 2, 3]
+    assert [unwrap(v) for v in reversed(lst)] == [3, 2, 1]
+    assert unwrap(lst[-1]) == 3
+    with pytest.raises(IndexError):
+        lst[-4]
+
+
+def test_list_mutable_sequence():
+    doc = LoroDoc()
+    lst = doc.get_list("list")
+    assert isinstance(lst, MutableSequence)
+    lst.append("a")
+    lst += ["b", "a"]
+    assert "a" in lst
+    assert "c" not in lst
+    assert lst.count("a") == 2
+    assert lst.index("a", 1) == 2
+    with pytest.raises(ValueError):
+        lst.index("c")
+    lst.remove("a")
+    assert lst.get_value() == ["b", "a"]
+    lst.reverse()
+    assert lst.get_value() == ["a", "b"]
+    assert lst.pop(0) == "a"
+    assert lst.get_value() == ["b"]
+
+
+def test_list_contains_container():
+    doc = LoroDoc()
+    lst = doc.get_list("list")
+    text = lst.insert_container(0, LoroText())
+    assert text in lst
+    assert lst.index(text) == 0
+    with pytest.raises(TypeError):
+        lst.reverse()
+
+
+def test_movable_list_reverse_keeps_containers():
+    doc = LoroDoc()
+    lst = doc.get_movable_list("list")
+    lst.push(1)
+    text = lst.push_container(LoroText())
+    text.insert(0, "hi")
+    lst.push(3)
+    lst.reverse()
+    assert lst.get_deep_value() == [3, "hi", 1]
+    assert isinstance(lst, MutableSequence)
+    assert [lst.get(i).value for i in (0, 2)] == [3, 1]
+
+
+def test_map_mapping_protocol():
+    doc = LoroDoc()
+    m = doc.get_map("map")
+    assert isinstance(m, MutableMapping)
+    assert isinstance(m, Mapping)
+    m.update({"a": 1}, b=2)
+    m.update([("c", 3)])
+    assert sorted(m) == ["a", "b", "c"]
+    assert sorted(m.keys()) == ["a", "b", "c"]
+    assert sorted(unwrap(v) for v in m.values()) == [1, 2, 3]
+    assert sorted((k, unwrap(v)) for k, v in m.items()) == [("a", 1), ("b", 2), ("c", 3)]
+    assert m.get("missing", 42) == 42
+    assert m.get("missing") is None
+    assert unwrap(m.pop("a")) == 1
+    assert m.pop("a", None) is None
+    with pytest.raises(KeyError):
+        m.pop("a")
+    assert unwrap(m.setdefault("d", 4)) == 4
+    assert unwrap(m.setdefault("d", 5)) == 4
+    key, _ = m.popitem()
+    assert key not in m
+
+
+def test_map_iteration_skips_deleted_entries():
+    doc = LoroDoc()
+    m = doc.get_map("map")
+    m["a"] = 1
+    m["b"] = 2
+    it = iter(m.items())
+    first, _ = next(it)
+    del m["b" if first == "a" else "a"]
+    assert list(it) == []
+
+
+def test_map_views_are_reiterable():
+    doc = LoroDoc()
+    m = doc.get_map("map")
+    m.update({"a": 1, "b": 2})
+    keys, values, items = m.keys(), m.values(), m.items()
+    assert sorted(keys) == sorted(keys) == ["a", "b"]
+    assert len(keys) == len(values) == len(items) == 2
+    assert "a" in keys and "c" not in keys
+    assert 2 in values and 3 not in values
+    assert ("a", 1) in items and ("a", 2) not in items
+    m["c"] = 3
+    assert len(keys) == 3
+    assert ("c", 3) in items
+
+
+def test_list_insert_clamps_and_pop_empty():
+    doc = LoroDoc()
+    for lst in (doc.get_list("list"), doc.get_movable_list("movable")):
+        lst.insert(-1, "a")
+        lst.insert(100, "c")
+        lst.insert(-1, "b")
+        lst.insert(-100, "start")
+        assert lst.get_value() == ["start", "a", "b", "c"]
+        for _ in range(4):
+            lst.pop()
+        with pytest.raises(IndexError):
+            lst.pop()
+
+
+def test_tree_walk():
+    doc = LoroDoc()
+    tree = doc.get_tree("tree")
+    root = tree.create()
+    a = tree.create(root)
+    b = tree.create(root)
+    a1 = tree.create(a)
+    other = tree.create()
+    assert list(tree) == [root, a, a1, b, other]
+    assert list(tree.walk(a)) == [a, a1]
@@ -0,0 +1,223 @@
+import random
+
+import pytest
+from loro import (
+    Frontiers,
+    JsonPatchError,
+    LoroDoc,
+    LoroList,
+    LoroMap,
+    LoroMovableList,
+    LoroText,
+)
+
+
+def test_apply_json_patch():
+    doc = LoroDoc()
+    doc.apply_json_patch(
+        [
+            {"op": "add", "path": "/users", "value": {"alice": {"name": "Alice", "tags": ["a", "b"]}}},
+            {"op": "add", "path": "/users/alice/tags/-", "value": "c"},
+            {"op": "add", "path": "/title", "value": "Hello"},
+            {"op": "test", "path": "/users/alice/name", "value": "Alice"},
+        ]
+    )
+    assert isinstance(doc.get_map("users").get("alice").container, LoroMap)
+    tasks = doc.get_movable_list("tasks")
+    for task in ["x", "y", "z"]:
+        tasks.push(task)
+    doc.commit()
+
+    doc.apply_json_patch(
+        [
+            {"op": "move", "from": "/tasks/0", "path": "/tasks/2"},
+            {"op": "replace", "path": "/title", "value": "Hello world"},
+            {"op": "copy", "from": "/users/alice", "path": "/users/bob"},
+            {"op": "replace", "path": "/users/bob/name", "value": "Bob"},
+            {"op": "remove", "path": "/users/alice/tags/0"},
+        ]
+    )
+    assert doc.get_deep_value() == {
+        "users": {
+            "alice": {"name": "Alice", "tags": ["b", "c"]},
+            "bob": {"name": "Bob", "tags": ["a", "b", "c"]},
+        },
+        "title": "Hello world",
+        "tasks": ["y", "z", "x"],
+    }
+    assert doc.get_text("title").to_string() == "Hello world"
+
+
+def test_failed_json_patch_leaves_doc_unchanged():
+    doc = LoroDoc()
+    doc.get_map("users").insert("alice", "Alice")
+    doc.commit()
+    users = doc.get_map("users").id
+    before = doc.get_deep_value()
+    version = doc.oplog_vv.encode()
+
+    patches = [
+        [
+            {"op": "add", "path": "/users/bob", "value": "Bob"},
+            {"op": "test", "path": "/users/alice", "value": "Alicia"},
+        ],
+        [{"op": "remove", "path": "/users/carol"}],
+        [{"op": "remove", "path": "/users"}],
+        [{"op": "rename", "path": "/users/alice"}],
+        # A container id is not a JSON value, even after a valid operation.
+        [
+            {"op": "add", "path": "/users/bob", "value": "Bob"},
+            {"op": "add", "path": "/users/carol", "value": {"map": users}},
+        ],
+        [{"op": "replace", "path": "/users/alice", "value": users}],
+    ]
+    for patch in patches:
+        with pytest.raises(JsonPatchError):
+            doc.apply_json_patch(patch)
+    assert doc.get_deep_value() == before
+    assert doc.oplog_vv.encode() == version
+
+
+def test_diff_to_json_patch_round_trip():
+    doc = LoroDoc()
+    users = doc.get_map("users")
+    alice = users.insert_container("alice", LoroMap())
+    alice.insert("name", "Alice")
+    bio = alice.insert_container("bio", LoroText())
+    bio.insert(0, "Hello")
+    todos = doc.get_list("todos")
+    todos.push("a")
+    todos.push("b")
+    tasks = doc.get_movable_list("tasks")
+    for task in ["x", "y", "z"]:
+        tasks.push(task)
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    copy = doc.fork_at(v1)
+
+    alice.insert("name", "Alicia")
+    bio.insert(5, " world")
+    todos.delete(0, 1)
+    todos.push_container(LoroMap()).insert("done", True)
+    tasks.mov(0, 2)
+    tasks.set(0, "w")
+    doc.commit()
+
+    patch = doc.diff(v1, doc.oplog_frontiers).to_json_patch()
+    assert {"op": "replace", "path": "/users/alice/bio", "value": "Hello world"} in patch
+    copy.apply_json_patch(patch)
+    assert copy.get_deep_value() == doc.get_deep_value()
+
+
+def test_diff_to_json_patch_from_empty_doc():
+    doc = LoroDoc()
+    doc.get_map("users").insert("alice", "Alice")
+    doc.get_list("todos").push("a")
+    doc.get_text("title").insert(0, "Hello")
+    doc.commit()
+
+    patch = doc.diff(Frontiers(), doc.oplog_frontiers).to_json_patch()
+    assert patch[:3] == [
+        {"op": "add", "path": "/users", "value": {}},
+        {"op": "add", "path": "/todos", "value": []},
+        {"op": "add", "path": "/title", "value": ""},
+    ]
+    other = LoroDoc()
+    other.apply_json_patch(patch)
+    assert other.get_deep_value() == doc.get_deep_value()
+
+
+def test_diff_to_json_patch_keeps_lists_of_maps():
+    doc = LoroDoc()
+    m = doc.get_map("m")
+    items = m.insert_container("items", LoroList())
+    items.push_container(LoroMap()).insert("name", "a")
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    m.insert("title", "T2")
+    doc.commit()
+
+    patch = doc.diff(v1, doc.oplog_frontiers).to_json_patch()
+    assert patch == [{"op": "add", "path": "/m/title", "value": "T2"}]
+    copy = doc.fork_at(v1)
+    copy.apply_json_patch(patch)
+    assert copy.get_deep_value() == {"m": {"items": [{"name": "a"}], "title": "T2"}}
+
+
+def test_diff_to_json_patch_of_moved_containers():
+    doc = LoroDoc()
+    tasks = doc.get_movable_list("tasks")
+    task = tasks.push_container(LoroMap())
+    items = task.insert_container("items", LoroList())
+    items.push(1)
+    tasks.push("b")
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    tasks.mov(0, 1)
+    items.push(2)
+    doc.commit()
+    v2 = doc.oplog_frontiers
+    task.insert("late", True)
+    doc.commit()
+
+    patch = doc.diff(v1, v2).to_json_patch()
+    copy = doc.fork_at(v1)
+    copy.apply_json_patch(patch)
+    assert copy.get_deep_value() == {"tasks": ["b", {"items": [1, 2]}]}
+
+
+def _random_edit(doc, rng, containers):
+    container = rng.choice(
+        [c for c in containers if doc.get_path_to_container(c.id) is not None]
+    )
+    value = rng.randint(0, 9)
+    child = rng.choice([LoroMap, LoroList, LoroMovableList, LoroText])()
+    if isinstance(container, LoroMap):
+        key = rng.choice("abcd")
+        r = rng.random()
+        if r < 0.4:
+            container.insert(key, value)
+        elif r < 0.7:
+            containers.append(container.insert_container(key, child))
+        else:
+            container.delete(key)
+    elif isinstance(container, (LoroList, LoroMovableList)):
+        n = len(container)
+        r = rng.random()
+        if r < 0.3:
+            container.insert(rng.randint(0, n), value)
+        elif r < 0.55:
+            containers.append(container.insert_container(rng.randint(0, n), child))
+        elif r < 0.75 and n:
+            container.delete(rng.randrange(n), 1)
+        elif isinstance(container, LoroMovableList) and n:
+            if rng.random() < 0.6:
+                container.mov(rng.randrange(n), rng.randrange(n))
+            else:
+                container.set(rng.randrange(n), value)
+    else:
+        n = len(container.to_string())
+        if n and rng.random() < 0.3:
+            container.delete(rng.randrange(n), 1)
+        else:
+            container.insert(rng.randint(0, n), rng.choice("xyz"))
+
+
+def test_diff_to_json_patch_of_random_histories():
+    for seed in range(200):
+        rng = random.Random(seed)
+        doc = LoroDoc()
+        doc.peer_id = 1
+        containers = [doc.get_map("m"), doc.get_list("l"), doc.get_movable_list("ml")]
+        versions = [Frontiers()]
+        for _ in range(rng.randint(1, 12)):
+            for _ in range(rng.randint(1, 4)):
+                _random_edit(doc, rng, containers)
+            doc.commit()
+            versions.append(doc.oplog_frontiers)
+        start, end = rng.choice(versions), rng.choice(versions)
+
+        patch = doc.diff(start, end).to_json_patch()
+        copy = doc.fork_at(start)
+        copy.apply_json_patch(patch)
+        assert copy.get_deep_value() == doc.fork_at(end).get_deep_value(), seed
@@ -0,0 +1,136 @@
+from typing import Dict, List, Optional
+
+import pytest
+from loro import LoroCounter, LoroDoc, LoroList, LoroModel, LoroText
+
+
+class Person(LoroModel):
+    name: str
+    age: Optional[int]
+
+
+class Task(LoroModel):
+    title: str
+    done: bool = False
+    priority: float = 1.0
+    tags: List[str] = []
+    scores: Dict[str, int] = {}
+    notes: LoroText
+    subtasks: LoroList
+    votes: LoroCounter
+    assignee: Person
+
+
+def test_typed_fields():
+    doc = LoroDoc()
+    task = Task(doc.get_map("task"), title="Write docs")
+    assert task.title == "Write docs"
+    assert task.done is False
+    assert task.priority == 1.0
+    task.done = True
+    task.priority = 2
+    task.tags = ["docs", "v1"]
+    assert doc.get_map("task").get_deep_value() == {
+        "title": "Write docs",
+        "done": True,
+        "priority": 2.0,
+        "tags": ["docs", "v1"],
+    }
+    assert Task(doc.get_map("task")) == task
+    assert Task(doc.get_map("other")) != task
+
+
+def test_validation():
+    task = Task(LoroDoc().get_map("task"))
+    with pytest.raises(TypeError):
+        task.title = 1
+    with pytest.raises(TypeError):
+        task.tags = ["ok", 2]
+    with pytest.raises(TypeError):
+        task.scores = {"a": "b"}
+    with pytest.raises(TypeError):
+        Task(LoroDoc().get_map("task"), unknown=1)
+    with pytest.raises(AttributeError):
+        task.title
+    del task.done
+    assert task.done is False
+
+
+def test_container_and_nested_fields():
+    doc = LoroDoc()
+    task = Task(doc.get_map("task"))
+    task.notes = "first draft"
+    task.notes.insert(0, "A ")
+    task.subtasks.append("review")
+    task.votes.increment(3)
+    task.assignee.name = "Ada"
+    assert task.assignee.age is None
+    assert isinstance(task.assignee, Person)
+    with pytest.raises(AttributeError):
+        task.subtasks = ["x"]
+    assert doc.get_map("task").get_deep_value() == {
+        "notes": "A first draft",
+        "subtasks": ["review"],
+        "votes": 3.0,
+        "assignee": {"name": "Ada"},
+    }
+    assert repr(task.assignee) == "Person(name='Ada')"
+
+
+class Project(LoroModel):
+    # Declared before the model it refers to.
+    lead: "Lead"
+
+
+class Lead(LoroModel):
+    name: str
+
+
+def test_forward_references():
+    project = Project(LoroDoc().get_map("project"))
+    project.lead.name = "Grace"
+    assert project.map.get_deep_value() == {"lead": {"name": "Grace"}}
+
+
+class Later(LoroModel):
+    owner: "Owner"
+    n: int = "oops"
+
+
+class Owner(LoroModel):
+    name: str
+
+
+def test_defaults_are_copied():
+    doc = LoroDoc()
+    task = Task(doc.get_map("task"))
+    task.tags.append("x")
+    task.scores["a"] = 1
+    # The default is not shared, and is only written when assigned.
+    assert task.tags == []
+    assert Task(doc.get_map("other")).scores == {}
+    assert doc.get_map("task").get_deep_value() == {}
+    task.tags = task.tags + ["x"]
+    assert doc.get_map("task").get_deep_value() == {"tags": ["x"]}
+
+
+def test_invalid_defaults():
+    with pytest.raises(TypeError, match="Broken.n"):
+
+        class Broken(LoroModel):
+            n: int = "oops"
+
+    with pytest.raises(TypeError):
+
+        class BrokenList(LoroModel):
+            tags: List[str] = [1]
+
+    with pytest.raises(TypeError):
+
+        class BrokenContainer(LoroModel):
+            notes: LoroText = "draft"
+
+    # The annotations refer to a class declared later, so the default is
+    # checked when they are resolved.
+    with pytest.raises(TypeError, match="Later.n"):
+        Later(LoroDoc().get_map("later")).n
@@ -0,0 +1,142 @@
+import pytest
+from loro import (
+    ContainerNotFoundError,
+    ExportMode,
+    InvalidArgumentError,
+    LoroDoc,
+    LoroList,
+    LoroMap,
+    LoroText,
+    VersionVector,
+)
+
+
+def make_doc():
+    doc = LoroDoc()
+    projects = doc.get_map("projects")
+    alpha = projects.insert_container("alpha", LoroMap())
+    alpha.insert("name", "Alpha")
+    notes = alpha.insert_container("notes", LoroText())
+    notes.insert(0, "alpha notes")
+    beta = projects.insert_container("beta", LoroMap())
+    beta.insert("name", "Beta secret")
+    projects.insert("owner", "carol")
+    projects.insert("SECRETKEY", "hunter2")
+    projects.delete("SECRETKEY")
+    tasks = doc.get_list("tasks")
+    tasks.push("secret task")
+    shared = tasks.push_container(LoroMap())
+    shared.insert("title", "shared task")
+    doc.get_text("secret").insert(0, "top secret")
+    doc.commit()
+    return doc, alpha, shared
+
+
+def test_export_subtrees():
+    doc, alpha, shared = make_doc()
+    data = doc.export(ExportMode.Snapshot(), containers=[alpha.id, shared.id])
+    for secret in [
+        b"Beta secret",
+        b"secret task",
+        b"top secret",
+        b"carol",
+        b"SECRETKEY",
+        b"hunter2",
+        b"owner",
+        b"beta",
+        b"secret",
+    ]:
+        assert secret not in data
+    other = LoroDoc()
+    other.import_(data)
+    assert other.get_deep_value() == {
+        "projects": {"alpha": {"name": "Alpha", "notes": "alpha notes"}},
+        "tasks": [None, {"title": "shared task"}],
+    }
+    # The containers keep their ids and their paths.
+    assert other.get_path_to_container(shared.id) is not None
+    assert other.get_map(alpha.id).get_deep_value()["notes"] == "alpha notes"
+
+
+def test_export_root_and_tree():
+    doc, _, _ = make_doc()
+    tree = doc.get_tree("tree")
+    root = tree.create()
+    hidden = tree.create(root)
+    visible = tree.create(root)
+    tree.get_meta(root).insert("title", "root")
+    tree.get_meta(hidden).insert("title", "hidden")
+    tree.get_meta(visible).insert("title", "visible")
+    doc.commit()
+    data = doc.export(
+        ExportMode.Snapshot(),
+        containers=[doc.get_list("tasks").id, tree.get_meta(visible).id],
+    )
+    other = LoroDoc()
+    other.import_(data)
+    value = other.get_deep_value()
+    assert value["tasks"] == ["secret task", {"title": "shared task"}]
+    assert "projects" not in value
+    assert "secret" not in value
+    assert b"hidden" not in data
+    other_tree = other.get_tree("tree")
+    assert not other_tree.is_node_deleted(visible)
+    assert not other_tree.contains(hidden)
+    assert other_tree.get_meta(visible).get_deep_value() == {"title": "visible"}
+    assert other_tree.get_meta(root).get_deep_value() == {}
+
+
+def test_export_at_version_and_errors():
+    doc, alpha, _ = make_doc()
+    frontiers = doc.oplog_frontiers
+    alpha.insert("name", "Renamed")
+    doc.commit()
+    other = LoroDoc()
+    other.import_(doc.export(ExportMode.SnapshotAt(frontiers), containers=[alpha.id]))
+    assert other.get_map(alpha.id).get("name").value == "Alpha"
+    with pytest.raises(InvalidArgumentError):
+        doc.export(ExportMode.Updates(VersionVector()), containers=[alpha.id])
+    detached = LoroMap()
+    with pytest.raises(ContainerNotFoundError):
+        doc.export(ExportMode.Snapshot(), containers=[detached.id])
+
+
+def test_sync_after_export():
+    doc, alpha, _ = make_doc()
+    other = LoroDoc()
+    other.import_(doc.export(ExportMode.Snapshot(), containers=[alpha.id]))
+    assert other.oplog_vv == doc.oplog_vv
+
+    # source -> receiver
+    alpha.insert("name", "Alpha 2")
+    doc.get_map("projects").insert("owner", "dave")
+    doc.commit()
+    other.import_(doc.export(ExportMode.Updates(other.oplog_vv)))
+    assert other.get_map(alpha.id).get("name").value == "Alpha 2"
+
+    # receiver -> source
+    other.get_map(alpha.id).get("notes").container.insert(0, "more ")
+    other.commit()
+    doc.import_(other.export(ExportMode.Updates(doc.oplog_vv)))
+    assert alpha.get("notes").container.to_string() == "more alpha notes"
+    assert doc.get_map("projects").get("owner").value == "dave"
+    assert doc.get_text("secret").to_string() == "top secret"
+
+
+def test_export_list_of_maps():
+    doc = LoroDoc()
+    a = doc.get_map("a")
+    items = a.insert_container("y", LoroList())
+    doc.commit()
+    item = items.push_container(LoroMap())
+    doc.commit()
+    item.insert("x", "q")
+    doc.get_map("b").insert("hidden", "secret")
+    doc.commit()
+    for target in [a, items, item]:
+        data = doc.export(ExportMode.Snapshot(), containers=[target.id])
+        assert b"secret" not in data
+        other = LoroDoc()
+        other.import_(data)
+        assert other.get_deep_value() == {"a": {"y": [{"x": "q"}]}}
+        assert other.oplog_vv == doc.oplog_vv
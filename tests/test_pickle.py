@@ -0,0 +1,80 @@
+import copy
+import pickle
+
+from loro import (
+    ContainerID,
+    ContainerType,
+    Cursor,
+    Frontiers,
+    ID,
+    LoroDoc,
+    LoroList,
+    Side,
+    TreeID,
+    VersionRange,
+    VersionVector,
+)
+
+
+def make_doc():
+    doc = LoroDoc()
+    doc.get_text("text").insert(0, "hello")
+    doc.get_map("map").insert("key", 1)
+    doc.commit()
+    return doc
+
+
+def test_pickle_doc():
+    doc = make_doc()
+    restored = pickle.loads(pickle.dumps(doc))
+    assert restored.get_deep_value() == doc.get_deep_value()
+    assert restored.oplog_vv == doc.oplog_vv
+    assert restored.peer_id != doc.peer_id
+
+
+def test_copy_doc():
+    doc = make_doc()
+    for other in (copy.copy(doc), copy.deepcopy(doc)):
+        assert other.get_deep_value() == doc.get_deep_value()
+        other.get_text("text").insert(0, "!")
+        assert doc.get_text("text").to_string() == "hello"
+
+
+def test_pickle_versions():
+    doc = make_doc()
+    frontiers = doc.oplog_frontiers
+    vv = doc.oplog_vv
+    assert pickle.loads(pickle.dumps(vv)) == vv
+    assert pickle.loads(pickle.dumps(frontiers)).encode() == frontiers.encode()
+
+    vr = VersionRange.from_vv(vv)
+    assert pickle.loads(pickle.dumps(vr)).inner() == vr.inner()
+
+
+def test_pickle_ids():
+    assert pickle.loads(pickle.dumps(ID(1, 2))) == ID(1, 2)
+    assert pickle.loads(pickle.dumps(TreeID(3, 4))) == TreeID(3, 4)
+
+    root = ContainerID.Root("text", ContainerType.Text())
+    assert pickle.loads(pickle.dumps(root)) == root
+    normal = ContainerID.Normal(7, 1, ContainerType.Map())
+    assert pickle.loads(pickle.dumps(normal)) == normal
+    assert copy.deepcopy(normal) == normal
+
+
+def test_pickle_cursor():
+    doc = make_doc()
+    cursor = doc.get_text("text").get_cursor(2, Side.Left)
+    restored = pickle.loads(pickle.dumps(cursor))
+    assert restored == cursor
+    assert doc.get_cursor_pos(restored).current.pos == 2
+    assert Cursor.decode(cursor.encode()) == cursor
+
+
+def test_pickle_values_with_container_refs():
+    doc = LoroDoc()
+    lst = doc.get_list("list")
+    lst.insert_container(0, LoroList())
+    lst.push(b"raw")
+    value = lst.get_value()
+    assert pickle.loads(pickle.dumps(value)) == value
@@ -0,0 +1,109 @@
+import hashlib
+import json
+
+from loro import (
+    ExportMode,
+    LoroCounter,
+    LoroDoc,
+    LoroMap,
+    LoroText,
+    RedactAction,
+    RedactPolicy,
+    VersionRange,
+)
+
+
+def make_doc():
+    doc = LoroDoc()
+    doc.peer_id = 1
+    users = doc.get_map("users")
+    alice = users.insert_container("alice", LoroMap())
+    alice.insert("name", "Alice")
+    alice.insert("email", "alice@example.com")
+    bio = alice.insert_container("bio", LoroText())
+    bio.insert(0, "Hello world")
+    bio.mark(0, 5, "bold", True)
+    bio.mark(6, 11, "link", "https://example.com")
+    doc.get_map("settings").insert("theme", "dark")
+    doc.get_counter("visits").increment(3)
+    doc.commit()
+    return doc, alice, bio
+
+
+def import_redacted(data):
+    doc = LoroDoc()
+    doc.import_(data)
+    return doc
+
+
+def test_redact_per_container_and_key():
+    doc, alice, bio = make_doc()
+    policy = RedactPolicy(
+        RedactAction.Keep,
+        containers={doc.get_map("users").id: RedactAction.Null},
+        keys={"email": RedactAction.Hash, "name": RedactAction.Drop},
+        marks=["bold"],
+        salt=b"pepper",
+    )
+    data = doc.redact(VersionRange.from_vv(doc.oplog_vv), policy)
+    assert b"alice@example.com" not in data
+    assert b"Alice" not in data
+    redacted = import_redacted(data)
+    digest = hashlib.sha256(b"pepper" + json.dumps("alice@example.com").encode())
+    assert redacted.get_deep_value() == {
+        "users": {
+            "alice": {"email": "sha256:" + digest.hexdigest(), "bio": "�" * 11},
+        },
+        "settings": {"theme": "dark"},
+        "visits": 3.0,
+    }
+    assert [d.attributes for d in redacted.get_text(bio.id).to_delta()] == [
+        {"bold": True},
+        None,
+    ]
+    assert redacted.oplog_vv == doc.oplog_vv
+
+
+def test_redact_range_only():
+    doc, alice, bio = make_doc()
+    start = doc.oplog_vv
+    doc.get_map("settings").insert("token", "s3cr3t")
+    doc.get_counter("visits").increment(2)
+    doc.commit()
+    updates = doc.redact(
+        doc.oplog_vv.sub_vec(start),
+        RedactPolicy(),
+        snapshot=False,
+    )
+    redacted = import_redacted(updates)
+    assert redacted.get_map("settings").get_deep_value() == {"theme": "dark", "token": None}
+    assert redacted.get_counter("visits").value == 3.0
+    assert redacted.get_map(alice.id).get("name").value == "Alice"
+
+    # The redacted history can be merged with the original one.
+    redacted.import_(doc.export(ExportMode.Snapshot()))
+    assert redacted.oplog_vv == doc.oplog_vv
+
+
+def test_redact_deleted_containers():
+    doc = LoroDoc()
+    users = doc.get_map("users")
+    bob = users.insert_container("bob", LoroMap())
+    bob.insert("ssn", "123-45-6789")
+    tree = doc.get_tree("tree")
+    node = tree.create()
+    tree.get_meta(node).insert("title", "TOPSECRET")
+    doc.commit()
+    users.delete("bob")
+    tree.delete(node)
+    doc.commit()
+    policy = RedactPolicy(
+        RedactAction.Keep,
+        containers={users.id: RedactAction.Null, tree.id: RedactAction.Null},
+    )
+    data = doc.redact(VersionRange.from_vv(doc.oplog_vv), policy)
+    assert b"123-45-6789" not in data
+    assert b"TOPSECRET" not in data
+    redacted = import_redacted(data)
+    assert redacted.oplog_vv == doc.oplog_vv
+    assert redacted.get_deep_value() == {"users": {}, "tree": []}
@@ -0,0 +1,233 @@
+import random
+
+import pytest
+from loro import ExpandType, InvalidArgumentError, LoroDoc, MarkStyle, StyleConfigMap
+
+
+def make_text(content):
+    doc = LoroDoc()
+    config = StyleConfigMap.default_rich_text_config()
+    config.insert("strikethrough", ExpandType.After)
+    doc.config_text_style(config)
+    text = doc.get_text("text")
+    text.insert(0, content)
+    return text
+
+
+def test_to_markdown():
+    text = make_text("Hello world, see the docs")
+    text.mark(0, 5, "bold", True)
+    text.mark(6, 11, "italic", True)
+    text.mark(21, 25, "link", "https://loro.dev/a b")
+    assert text.to_markdown() == "**Hello** *world*, see the [docs](https://loro.dev/a%20b)"
+
+
+def test_to_html():
+    text = make_text("Hello world, see the docs")
+    text.mark(0, 5, "bold", True)
+    text.mark(21, 25, "link", 'https://loro.dev/?a=1&b="2"')
+    assert text.to_html() == (
+        "<strong>Hello</strong> world, see the "
+        '<a href="https://loro.dev/?a=1&amp;b=&quot;2&quot;">docs</a>'
+    )
+
+
+def test_overlapping_marks_are_nested():
+    text = make_text("one two three")
+    text.mark(0, 7, "bold", True)
+    text.mark(4, 13, "italic", True)
+    assert text.to_html() == "<strong>one <em>two</em></strong><em> three</em>"
+    assert text.to_markdown() == "**one *two*** *three*"
+
+
+def test_whitespace_and_line_breaks_in_markdown():
+    text = make_text("a bold \nline")
+    text.mark(2, 12, "bold", True)
+    assert text.to_markdown() == "a **bold** \n**line**"
+    assert text.to_html() == "a <strong>bold <br>line</strong>"
+
+
+def test_escaping():
+    text = make_text("# 1 * 2 <b>\n1. x `y`")
+    assert text.to_markdown() == "\\# 1 \\* 2 \\<b\\>\n1\\. x \\`y\\`"
+    assert text.to_html() == "# 1 * 2 &lt;b&gt;<br>1. x `y`"
+    code = make_text("call f(*a)")
+    code.mark(5, 10, "code", True)
+    assert code.to_markdown() == "call `f(*a)`"
+
+
+def test_code_spans_containing_backticks():
+    text = make_text("run a``b or `x")
+    text.mark(4, 8, "code", True)
+    text.mark(12, 14, "code", True)
+    assert text.to_markdown() == "run ```a``b``` or `` `x ``"
+    imported = make_text("")
+    imported.import_markdown(text.to_markdown())
+    assert imported.to_string() == "run a``b or `x"
+    assert imported.to_markdown() == text.to_markdown()
+
+
+def test_unsafe_link_schemes_are_not_rendered():
+    text = make_text("click here or mail me")
+    text.mark(6, 10, "link", "java\tscript:alert(1)")
+    text.mark(14, 21, "link", "mailto:me@loro.dev")
+    assert text.to_markdown() == "click here or [mail me](mailto:me@loro.dev)"
+    assert text.to_html() == 'click here or <a href="mailto:me@loro.dev">mail me</a>'
+    text.mark(6, 10, "link", "/docs?at=12:30")
+    assert text.to_html().startswith('click <a href="/docs?at=12:30">here</a>')
+
+
+def test_custom_mark_styles():
+    text = make_text("marked and bold")
+    text.mark(0, 6, "highlight", "yellow")
+    text.mark(11, 15, "bold", True)
+    marks = {
+        "highlight": MarkStyle('<mark class="{value}">', "</mark>"),
+        "bold": None,
+    }
+    assert text.to_html(marks) == '<mark class="yellow">marked</mark> and bold'
+    # Marks without a style are ignored.
+    assert text.to_html() == "marked and <strong>bold</strong>"
+    assert MarkStyle.markdown_defaults()["code"] == MarkStyle("`", "`", verbatim=True)
+
+
+def attributes(text):
+    return [(d.insert, d.attributes) for d in text.to_delta()]
+
+
+def test_import_markdown():
+    text = make_text("old content")
+    text.import_markdown("**Hello** *world*, see [the *docs*](https://loro.dev) and `a*b`")
+    assert attributes(text) == [
+        ("Hello", {"bold": True}),
+        (" ", None),
+        ("world", {"italic": True}),
+        (", see ", None),
+        ("the ", {"link": "https://loro.dev"}),
+        ("docs", {"italic": True, "link": "https://loro.dev"}),
+        (" and ", None),
+        ("a*b", {"code": True}),
+    ]
+
+
+def test_import_markdown_literals_and_keys():
+    text = make_text("")
+    text.import_markdown("\\# not \\*bold\\* **x snake_case ~~gone~~", keys={"strikethrough": None})
+    assert attributes(text) == [("# not *bold* **x snake_case gone", None)]
+    text.import_markdown("~~done~~", keys={"strikethrough": "highlight"})
+    assert attributes(text) == [("done", {"highlight": True})]
+
+
+def test_import_markdown_strikethrough_and_unmatched_underline():
+    text = make_text("")
+    text.import_markdown("a ~~b~~ <u>c <u>d</u> e")
+    assert attributes(text) == [
+        ("a ", None),
+        ("b", {"strikethrough": True}),
+        (" <u>c ", None),
+        ("d", {"underline": True}),
+        (" e", None),
+    ]
+    text.import_markdown("x <u>y")
+    assert attributes(text) == [("x <u>y", None)]
+
+
+def test_failed_import_leaves_text_unchanged():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    text.insert(0, "old")
+    doc.commit()
+    ops = doc.len_ops
+    for diff in [False, True]:
+        with pytest.raises(InvalidArgumentError):
+            text.import_markdown("new ~~s~~ x", diff=diff)
+        with pytest.raises(InvalidArgumentError):
+            text.import_html("new <b>s</b> x", keys={"bold": "strong"}, diff=diff)
+        doc.commit()
+        assert text.to_string() == "old"
+        assert doc.len_ops == ops
+
+    # A key without a mark in the source does not need to be configured.
+    text.import_markdown("plain", diff=True)
+    assert text.to_string() == "plain"
+
+
+def test_import_html():
+    text = make_text("")
+    text.import_html(
+        "<p>Hello <b>bo<i>ld</i></b>   &amp; <a href='https://a?x=1&amp;y=2'>link</a></p>"
+        "<p>second<br>line &lt;x&gt;</p><script>ignored()</script>"
+    )
+    assert text.to_string() == "Hello bold & link\nsecond\nline <x>"
+    assert attributes(text)[1:5] == [
+        ("bo", {"bold": True}),
+        ("ld", {"bold": True, "italic": True}),
+        (" & ", None),
+        ("link", {"link": "https://a?x=1&y=2"}),
+    ]
+
+
+def test_export_import_round_trip():
+    text = make_text("one two three\nfour <five>")
+    text.mark(0, 7, "bold", True)
+    text.mark(4, 13, "italic", True)
+    text.mark(19, 25, "link", "https://loro.dev")
+    expected = attributes(text)
+    other = make_text("")
+    other.import_html(text.to_html())
+    assert attributes(other) == expected
+    # Markdown moves whitespace out of marks: " three" loses its italic space.
+    other.import_markdown(text.to_markdown())
+    assert other.to_string() == text.to_string()
+    assert attributes(other)[2:4] == [(" ", None), ("three", {"italic": True})]
+
+
+def test_markdown_round_trip_next_to_punctuation():
+    cases = [
+        ("Hello.World", 0, 6, "bold", "<strong>Hello.</strong>World"),
+        ('"hi"there', 0, 4, "italic", '<em>"hi"</em>there'),
+        ("foo(bar)baz", 3, 8, "bold", "foo<strong>(bar)</strong>baz"),
+        ("a_b", 1, 2, "italic", "a<em>\\_</em>b"),
+        ("foobarbaz", 3, 6, "bold", "foo**bar**baz"),
+    ]
+    for content, start, end, key, markdown in cases:
+        text = make_text(content)
+        text.mark(start, end, key, True)
+        assert text.to_markdown() == markdown
+        other = make_text("")
+        other.import_markdown(markdown)
+        assert attributes(other) == attributes(text)
+
+
+def test_markdown_round_trip_of_random_marks():
+    for seed in range(300):
+        rng = random.Random(seed)
+        content = "".join(rng.choice('ab.,!"()*_~\\<>') for _ in range(rng.randint(1, 12)))
+        text = make_text(content)
+        for _ in range(rng.randint(1, 3)):
+            start = rng.randrange(len(content))
+            end = rng.randint(start + 1, len(content))
+            text.mark(start, end, rng.choice(["bold", "italic", "underline"]), True)
+        other = make_text("")
+        other.import_markdown(text.to_markdown())
+        assert attributes(other) == attributes(text), (seed, text.to_markdown())
+
+
+def test_import_diff_generates_minimal_ops():
+    doc = LoroDoc()
+    doc.config_text_style(StyleConfigMap.default_rich_text_config())
+    text = doc.get_text("text")
+    text.import_markdown("Some **bold** text, [a link](https://loro.dev) here.")
+    doc.commit()
+    text.mark(0, 4, "comment", "keep me")
+    doc.commit()
+    ops = doc.len_ops
+    text.import_markdown("Some **bold** text, [a link](https://loro.dev) here.", diff=True)
+    doc.commit()
+    assert doc.len_ops == ops
+    text.import_markdown("Some bold *text*, [a link](https://loro.dev) there.", diff=True)
+    doc.commit()
+    # Inserting "t", unmarking bold and marking italic.
+    assert doc.len_ops - ops == 5
+    assert text.to_markdown() == "Some bold *text*, [a link](https://loro.dev) there."
+    assert attributes(text)[0] == ("Some", {"comment": "keep me"})
@@ -0,0 +1,147 @@
+from typing import List, Optional
+
+import pytest
+from loro import (
+    DocSchema,
+    ExportMode,
+    LoroDoc,
+    LoroList,
+    LoroMap,
+    LoroText,
+    Schema,
+    SchemaError,
+)
+
+TASK = Schema.map(
+    {
+        "title": Schema.text(),
+        "done": Schema.value(bool),
+        "estimate": Schema.value(Optional[float]),
+        "tags": Schema.value(List[str]),
+    },
+    required=["title"],
+)
+
+SCHEMA = DocSchema(
+    {
+        "tasks": Schema.movable_list(TASK),
+        "settings": Schema.map({}, extra=Schema.value(str)),
+        "notes": Schema.one_of(Schema.text(), Schema.list(Schema.value(str))),
+    }
+)
+
+
+def add_task(doc, title, **fields):
+    task = doc.get_movable_list("tasks").push_container(LoroMap())
+    task.insert_container("title", LoroText()).insert(0, title)
+    for key, value in fields.items():
+        task.insert(key, value)
+    return task
+
+
+def test_valid_document():
+    doc = LoroDoc()
+    add_task(doc, "write docs", done=False, estimate=2, tags=["docs"])
+    doc.get_map("settings").insert("theme", "dark")
+    doc.get_text("notes").insert(0, "hello")
+    doc.commit()
+    assert doc.validate(SCHEMA) == []
+
+
+def test_violations_report_paths():
+    doc = LoroDoc()
+    add_task(doc, "ok")
+    bad = doc.get_movable_list("tasks").push_container(LoroMap())
+    bad.insert_container("title", LoroList())
+    bad.insert("done", "yes")
+    bad.insert("owner", "bob")
+    doc.get_movable_list("tasks").push(1)
+    doc.get_map("settings").insert("size", 3)
+    doc.get_map("unknown").insert("a", 1)
+    doc.commit()
+    violations = doc.validate(SCHEMA)
+    assert [str(v) for v in violations] == [
+        "settings/size: expected str, found int",
+        'tasks/1/done: expected bool, found str',
+        'tasks/1/owner: unexpected key "owner"',
+        "tasks/1/title: expected LoroText, found LoroList",
+        "tasks/2: expected LoroMap, found int",
+        "unknown: unexpected root container",
+    ]
+    title = violations[3]
+    assert title.container == bad.get("title").container.id
+    assert [type(p).__name__ for p in title.path] == ["Index_Key", "Index_Seq", "Index_Key"]
+    assert (title.path[0].key, title.path[1].index) == ("tasks", 1)
+
+
+
+def test_validate_after_import():
+    peer = LoroDoc()
+    peer.get_list("notes").push(1)
+    doc = LoroDoc()
+    doc.import_(peer.export(ExportMode.Snapshot()))
+    (violation,) = doc.validate(SCHEMA)
+    assert str(violation) == "notes: expected LoroText | LoroList, found LoroList"
+
+
+def rename_todos(doc):
+    # Version 1 kept the tasks as plain strings in "todos".
+    tasks = doc.get_movable_list("tasks")
+    todos = doc.get_list("todos")
+    for title in todos.get_deep_value():
+        task = tasks.push_container(LoroMap())
+        task.insert_container("title", LoroText()).insert(0, title)
+    todos.clear()
+
+
+def add_done(doc):
+    for i in range(len(doc.get_movable_list("tasks"))):
+        doc.get_movable_list("tasks").get(i).container.insert("done", False)
+
+
+def test_migrations_run_in_one_commit():
+    doc = LoroDoc()
+    doc.get_list("todos").push("a")
+    doc.get_list("todos").push("b")
+    doc.commit()
+    schema = DocSchema(
+        {"tasks": Schema.movable_list(TASK)},
+        version=2,
+        migrations={1: rename_todos, 2: add_done},
+    )
+    changes = doc.len_changes
+    assert doc.migrate(schema) == 0
+    assert doc.len_changes == changes + 1
+    assert DocSchema.version_of(doc) == 2
+    assert doc.get_deep_value()["tasks"] == [
+        {"title": "a", "done": False},
+        {"title": "b", "done": False},
+    ]
+    assert doc.validate(schema) == []
+    # Nothing left to migrate.
+    assert doc.migrate(schema) == 2
+    assert doc.len_changes == changes + 1
+
+
+def test_failed_migration_leaves_document_untouched():
+    doc = LoroDoc()
+    doc.get_list("todos").push("a")
+    doc.commit()
+    ops = doc.len_ops
+
+    def fail(doc):
+        rename_todos(doc)
+        raise RuntimeError("boom")
+
+    schema = DocSchema({"tasks": Schema.movable_list(TASK)}, version=1, migrations={1: fail})
+    with pytest.raises(RuntimeError):
+        doc.migrate(schema)
+    assert doc.len_ops == ops
+
+    # Without the second migration, "done" is missing but not required: the
+    # result is invalid because "todos" is still an unknown root.
+    schema = DocSchema({"tasks": Schema.movable_list(TASK)}, version=1)
+    with pytest.raises(SchemaError) as info:
+        doc.migrate(schema)
+    assert [str(v) for v in info.value.violations] == ["todos: unexpected root container"]
+    assert doc.len_ops == ops
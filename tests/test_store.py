@@ -0,0 +1,100 @@
+import pytest
+
+from loro import ExportMode, LoroDoc, LoroStore
+
+
+def open_stores(tmp_path, **kwargs):
+    """Open a store with each backend, yielding a function that reopens it."""
+    yield lambda doc=None: LoroStore.open_directory(tmp_path / "dir", doc, **kwargs)
+    yield lambda doc=None: LoroStore.open_sqlite(tmp_path / "doc.db", doc, **kwargs)
+
+
+def test_local_updates_are_persisted(tmp_path):
+    for open_store in open_stores(tmp_path):
+        store = open_store()
+        text = store.doc.get_text("text")
+        text.insert(0, "hello")
+        store.doc.commit()
+        text.insert(5, " world")
+        store.doc.commit()
+        assert store.log_size > 0
+        # Not closed: everything committed must survive.
+        reopened = open_store()
+        assert reopened.doc.get_text("text").to_string() == "hello world"
+        store.close()
+        reopened.close()
+
+
+def test_compaction_clears_the_log(tmp_path):
+    for open_store in open_stores(tmp_path, compact_threshold=64):
+        with open_store() as store:
+            text = store.doc.get_text("text")
+            for i in range(20):
+                text.insert(0, f"edit {i} ")
+                store.doc.commit()
+                assert store.log_size <= 64
+        with open_store() as store:
+            assert store.doc.get_text("text").to_string().startswith("edit 19 ")
+
+
+def test_shallow_compaction(tmp_path):
+    for open_store in open_stores(tmp_path, shallow=True):
+        with open_store() as store:
+            store.doc.get_map("map").insert("k", 1)
+            store.doc.commit()
+            store.compact()
+            assert store.log_size == 0
+        with open_store() as store:
+            assert store.doc.is_shallow()
+            assert store.doc.get_deep_value() == {"map": {"k": 1}}
+
+
+def test_imports_are_persisted_by_flush(tmp_path):
+    remote = LoroDoc()
+    remote.get_list("list").push(1)
+    remote.commit()
+    for open_store in open_stores(tmp_path):
+        with open_store() as store:
+            store.doc.import_(remote.export(ExportMode.Snapshot()))
+            store.flush()
+        with open_store() as store:
+            assert store.doc.get_deep_value() == {"list": [1]}
+
+
+def test_existing_doc_is_persisted_on_open(tmp_path):
+    for open_store in open_stores(tmp_path):
+        doc = LoroDoc()
+        doc.get_map("map").insert("a", 1)
+        doc.commit()
+        open_store(doc).close()
+        with open_store() as store:
+            assert store.doc.get_deep_value() == {"map": {"a": 1}}
+
+
+def test_torn_log_record_is_ignored(tmp_path):
+    store = LoroStore.open_directory(tmp_path, fsync=False)
+    store.doc.get_text("text").insert(0, "kept")
+    store.doc.commit()
+    store.close()
+    with open(tmp_path / "log", "ab") as log:
+        log.write(b"\x10\x00\x00\x00garbage")
+    with LoroStore.open_directory(tmp_path) as store:
+        assert store.doc.get_text("text").to_string() == "kept"
+        store.doc.get_text("text").insert(4, "!")
+        store.doc.commit()
+    with LoroStore.open_directory(tmp_path) as store:
+        assert store.doc.get_text("text").to_string() == "kept!"
+
+
+def test_closed_store(tmp_path):
+    store = LoroStore.open_directory(tmp_path)
+    store.close()
+    assert store.closed
+    store.close()
+    with pytest.raises(ValueError):
+        store.compact()
+    # Edits after close are not persisted.
+    store.doc.get_text("text").insert(0, "lost")
+    store.doc.commit()
+    with LoroStore.open_directory(tmp_path) as reopened:
+        assert reopened.doc.get_text("text").to_string() == ""
@@ -0,0 +1,110 @@
+import pytest
+
+from loro import DecodeError, EphemeralStore, ExportMode, LoroDoc, SyncSession
+
+
+def pump(a, b, frames_from_a, frames_from_b=()):
+    """Deliver frames back and forth until both sides are quiet."""
+    to_b, to_a = list(frames_from_a), list(frames_from_b)
+    rounds = 0
+    while to_a or to_b:
+        rounds += 1
+        assert rounds < 20, "the sessions do not converge"
+        next_to_a = [reply for frame in to_b for reply in b.receive(frame)]
+        next_to_b = [reply for frame in to_a for reply in a.receive(frame)]
+        to_a, to_b = next_to_a, next_to_b
+
+
+def make_peer(peer_id, text):
+    doc = LoroDoc()
+    doc.peer_id = peer_id
+    doc.get_text("text").insert(0, text)
+    doc.commit()
+    return doc
+
+
+def test_handshake_syncs_both_docs():
+    doc_a, doc_b = make_peer(1, "a"), make_peer(2, "b")
+    a, b = SyncSession(doc_a), SyncSession(doc_b)
+    pump(a, b, a.start())
+    assert doc_a.get_deep_value() == doc_b.get_deep_value()
+    assert a.is_synced and b.is_synced
+    assert a.remote_version == doc_b.oplog_vv
+
+
+def test_flush_sends_local_edits():
+    doc_a, doc_b = make_peer(1, "a"), make_peer(2, "b")
+    a, b = SyncSession(doc_a), SyncSession(doc_b)
+    pump(a, b, a.start(), b.start())
+    doc_a.get_map("map").insert("k", 1)
+    doc_a.commit()
+    assert not a.is_synced
+    pump(a, b, a.flush())
+    assert doc_b.get_map("map").get_value() == {"k": 1}
+    assert a.is_synced
+    assert a.flush() == []
+    # Imported ops are not echoed back to their author.
+    assert b.flush() == []
+
+
+def test_frames_can_be_split_and_concatenated():
+    doc_a, doc_b = make_peer(1, "hello"), LoroDoc()
+    a, b = SyncSession(doc_a), SyncSession(doc_b)
+    hello = b"".join(a.start())
+    replies = []
+    for i in range(len(hello)):
+        replies += b.receive(hello[i : i + 1])
+    stream = b"".join(replies)
+    back = a.receive(stream[:3]) + a.receive(stream[3:])
+    for frame in back:
+        b.receive(frame)
+    assert doc_b.get_text("text").to_string() == "hello"
+
+
+def test_ephemeral_payloads():
+    store_a, store_b = EphemeralStore(30000), EphemeralStore(30000)
+    store_a.set("cursor", 1)
+    a = SyncSession(LoroDoc(), store_a)
+    b = SyncSession(LoroDoc(), store_b)
+    pump(a, b, a.start())
+    assert store_b.get("cursor") == 1
+    store_b.set("selection", [0, 2])
+    pump(a, b, [], b.flush())
+    assert store_a.get("selection") == [0, 2]
+
+
+def test_invalid_frame():
+    session = SyncSession(LoroDoc())
+    with pytest.raises(DecodeError):
+        session.receive(b"\x09\x01\x00\x00\x00\x00")
+
+
+def test_pending_updates_request_missing_ops():
+    doc_a = make_peer(1, "x")
+    vv = doc_a.oplog_vv
+    doc_a.get_text("text").insert(1, "y")
+    doc_a.commit()
+    later = doc_a.export(ExportMode.Updates(vv))
+    doc_b = LoroDoc()
+    a, b = SyncSession(doc_a), SyncSession(doc_b)
+    frame = bytes([1, 2]) + len(later).to_bytes(4, "big") + later
+    pump(a, b, [frame])
+    assert doc_b.get_text("text").to_string() == "xy"
+
+
+def test_receive_error_keeps_replies_and_remaining_bytes():
+    doc_a, doc_b = make_peer(1, "hello"), LoroDoc()
+    a, b = SyncSession(doc_a), SyncSession(doc_b)
+    hello = b"".join(a.start())
+    bad = bytes([1, 2, 0, 0, 0, 3]) + b"bad"
+    ack = bytes([1, 3]) + len(doc_a.oplog_vv.encode()).to_bytes(4, "big")
+    ack += doc_a.oplog_vv.encode()
+    with pytest.raises(DecodeError):
+        b.receive(hello + bad + ack[:4])
+    # The Hello and the Updates b answered with are not lost, and the end of
+    # the Ack is completed by the next chunk.
+    replies = b.receive(ack[4:])
+    assert replies
+    pump(a, b, [], replies)
+    assert doc_a.get_deep_value() == doc_b.get_deep_value()
+    assert b.remote_version == doc_a.oplog_vv
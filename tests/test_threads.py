@@ -0,0 +1,62 @@
+import threading
+from concurrent.futures import ThreadPoolExecutor
+
+from loro import ExportMode, LoroDoc
+
+
+def make_snapshot(n: int = 200) -> bytes:
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    for i in range(n):
+        text.insert(0, f"line {i}\n")
+    doc.commit()
+    return doc.export(ExportMode.Snapshot())
+
+
+def test_import_export_in_thread_pool():
+    snapshot = make_snapshot()
+
+    def work(_):
+        doc = LoroDoc()
+        doc.import_(snapshot)
+        return doc.export(ExportMode.Snapshot())
+
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(work, range(8)))
+
+    for blob in results:
+        doc = LoroDoc()
+        doc.import_(blob)
+        assert doc.get_text("text").to_string().startswith("line 199\n")
+
+
+def test_events_dispatched_from_worker_thread():
+    snapshot = make_snapshot(10)
+    doc = LoroDoc()
+    seen = []
+    sub = doc.subscribe_root(lambda e: seen.append(threading.get_ident()))
+
+    worker = threading.Thread(target=lambda: doc.import_(snapshot))
+    worker.start()
+    worker.join()
+
+    assert len(seen) == 1
+    assert seen[0] == worker.ident
+    sub.unsubscribe()
+
+
+def test_checkout_and_fork_release_gil():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    text.insert(0, "abc")
+    doc.commit()
+    frontiers = doc.oplog_frontiers
+    text.insert(3, "def")
+    doc.commit()
+
+    with ThreadPoolExecutor(max_workers=2) as pool:
+        fork = pool.submit(doc.fork).result()
+        pool.submit(doc.checkout, frontiers).result()
+
+    assert text.to_string() == "abc"
+    assert fork.get_text("text").to_string() == "abcdef"
@@ -1,4 +1,15 @@
-from loro import LoroDoc, UndoManager
+import pytest
+from loro import (
+    AbsolutePosition,
+    CursorWithPos,
+    ExportMode,
+    LoroDoc,
+    Side,
+    UndoError,
+    UndoItemMeta,
+    UndoManager,
+    UndoOrRedo,
+)
 
 
 def test_undo_manager_group_basic():
@@ -174,4 +185,234 @@ def test_undo_manager_group_undo_count():
     # Undo the initial change
     undo_manager.undo()
     assert text.to_string() == ""
-    assert undo_manager.undo_count() == 0 
\ No newline at end of file
+    assert undo_manager.undo_count() == 0 
+
+def test_undo_manager_scoped_to_containers():
+    """Each pane of an editor can have its own undo stack"""
+    doc = LoroDoc()
+    body = doc.get_text("body")
+    settings = doc.get_map("settings")
+    body_undo = UndoManager(doc, containers=[body.id])
+    settings_undo = UndoManager(doc, containers=[settings.id])
+
+    body.insert(0, "Hi")
+    doc.commit()
+    settings.insert("theme", "dark")
+    doc.commit()
+    body.insert(2, "!")
+    doc.commit()
+    assert body_undo.undo_count() == 2
+    assert settings_undo.undo_count() == 1
+
+    settings_undo.undo()
+    assert settings.get_deep_value() == {}
+    assert body.to_string() == "Hi!"
+
+    body_undo.undo()
+    assert body.to_string() == "Hi"
+    assert body_undo.redo_count() == 1
+
+    settings_undo.redo()
+    assert settings.get_deep_value() == {"theme": "dark"}
+    assert body.to_string() == "Hi"
+
+
+def test_undo_manager_scope_includes_children():
+    """Changes to descendants are recorded, and mixed changes are only partly reverted"""
+    doc = LoroDoc()
+    comments = doc.get_tree("comments")
+    undo = UndoManager(doc, containers=[comments.id])
+
+    node = comments.create()
+    comments.get_meta(node).insert("text", "first")
+    doc.commit()
+    comments.get_meta(node).insert("text", "edited")
+    doc.get_map("settings").insert("theme", "dark")
+    doc.commit()
+
+    undo.undo()
+    assert comments.get_meta(node).get_deep_value() == {"text": "first"}
+    assert doc.get_map("settings").get_deep_value() == {"theme": "dark"}
+    undo.undo()
+    assert comments.get_nodes(False) == []
+    assert undo.undo() is False
+
+
+def test_undo_manager_out_of_scope_edits_shift_positions():
+    """Out-of-scope local edits are transformed like remote edits"""
+    doc = LoroDoc()
+    body = doc.get_text("body")
+    undo = UndoManager(doc, containers=[body.id])
+    undo.add_exclude_origin_prefix("sys")
+    body.insert(0, "Hello world")
+    doc.commit()
+    body.delete(5, 6)
+    doc.commit()
+    doc.set_next_commit_origin("sys")
+    body.insert(0, ">> ")
+    doc.commit()
+    doc.get_text("title").insert(0, "Title")
+    doc.commit()
+    assert undo.undo_count() == 2
+
+    undo.undo()
+    assert body.to_string() == ">> Hello world"
+    undo.undo()
+    assert body.to_string() == ">> "
+    assert doc.get_text("title").to_string() == "Title"
+    undo.redo()
+    undo.redo()
+    assert body.to_string() == ">> Hello"
+
+
+def test_undo_manager_export_import_state():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    undo = UndoManager(doc, containers=[text.id])
+    text.insert(0, "Hello")
+    doc.commit()
+    text.insert(5, " world")
+    doc.commit()
+    undo.undo()
+    state = undo.export_state()
+
+    # Reload the document and the undo manager, as after a restart.
+    restored = LoroDoc()
+    restored.import_(doc.export(ExportMode.Snapshot()))
+    restored_undo = UndoManager(restored, containers=[text.id])
+    assert restored_undo.import_state(state) is True
+    assert restored_undo.undo_count() == 1
+    assert restored_undo.redo_count() == 1
+
+    restored_undo.redo()
+    assert restored.get_text("text").to_string() == "Hello world"
+    restored_undo.undo()
+    restored_undo.undo()
+    assert restored.get_text("text").to_string() == ""
+    assert restored_undo.can_undo() is False
+
+
+def test_undo_manager_import_stale_state():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    undo = UndoManager(doc, containers=[text.id])
+    text.insert(0, "Hello")
+    doc.commit()
+    state = undo.export_state()
+
+    other = LoroDoc()
+    other_undo = UndoManager(other, containers=[text.id])
+    other.get_text("text").insert(0, "Hi")
+    other.commit()
+    assert other_undo.import_state(state) is False
+    assert other_undo.undo_count() == 1
+
+    # The stacks of Loro's own undo manager cannot be exported.
+    with pytest.raises(UndoError):
+        UndoManager(doc).export_state()
+
+
+def test_undo_manager_of_the_whole_doc_export_import_state():
+    doc = LoroDoc()
+    undo = UndoManager(doc, exportable=True)
+    doc.get_text("text").insert(0, "Hello")
+    doc.commit()
+    doc.get_map("map").insert("key", "value")
+    doc.commit()
+    # A root created after the undo manager is recorded too.
+    doc.get_list("list").push(1)
+    doc.commit()
+    undo.undo()
+    state = undo.export_state()
+
+    restored = LoroDoc()
+    restored.import_(doc.export(ExportMode.Snapshot()))
+    restored_undo = UndoManager(restored, exportable=True)
+    assert restored_undo.import_state(state) is True
+    assert restored_undo.undo_count() == 2
+    assert restored_undo.redo_count() == 1
+
+    restored_undo.redo()
+    assert restored.get_deep_value() == {
+        "text": "Hello",
+        "map": {"key": "value"},
+        "list": [1],
+    }
+    restored_undo.undo()
+    restored_undo.undo()
+    restored_undo.undo()
+    assert restored.get_deep_value() == {"text": "", "map": {}, "list": []}
+    assert restored_undo.can_undo() is False
+
+
+def test_undo_manager_of_the_whole_doc_clears_stacks_on_peer_change():
+    doc = LoroDoc()
+    undo = UndoManager(doc, exportable=True)
+    doc.get_text("text").insert(0, "Hello")
+    doc.commit()
+    assert undo.can_undo() is True
+    doc.peer_id = 42
+    assert undo.can_undo() is False
+
+
+def test_undo_manager_on_push_meta_and_on_pop_cursors():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    undo = UndoManager(doc, containers=[text.id])
+    text.insert(0, "Hello world")
+    doc.commit()
+
+    # The selection before the change, kept with the undo item.
+    cursor = text.get_cursor(6, Side.Middle)
+    selection = CursorWithPos(cursor, doc.get_cursor_pos(cursor).current)
+
+    def on_push(kind, span, event):
+        if kind == UndoOrRedo.Redo:
+            return None
+        return UndoItemMeta("selection", [selection])
+
+    popped = []
+    undo.set_on_push(on_push)
+    undo.set_on_pop(lambda kind, span, meta: popped.append(meta))
+    text.delete(0, 6)
+    doc.commit()
+    assert undo.top_undo_value() == "selection"
+    assert undo.top_undo_meta().cursors[0].pos.pos == 6
+
+    undo.undo()
+    assert text.to_string() == "Hello world"
+    assert popped[0].value == "selection"
+    assert popped[0].cursors[0].pos.pos == 6
+    assert undo.top_redo_value() is None
+    assert undo.top_redo_meta().cursors == []
+
+    # The restored position follows the edits made after the item was pushed.
+    undo.redo()
+    remote = doc.fork()
+    remote.get_text("text").insert(0, ">> ")
+    doc.import_(remote.export(ExportMode.Updates(doc.oplog_vv)))
+    undo.undo()
+    assert text.to_string() == "Hello >> world"
+    assert popped[-1].cursors[0].pos.pos == 9
+    assert UndoItemMeta().value is None
+    assert AbsolutePosition(3).pos == 3
+
+
+def test_undo_manager_of_the_whole_doc_calls_listeners():
+    doc = LoroDoc()
+    text = doc.get_text("text")
+    undo = UndoManager(doc)
+    popped = []
+    undo.set_on_push(
+        lambda kind, span, event: UndoItemMeta("insert") if kind == UndoOrRedo.Undo else None
+    )
+    undo.set_on_pop(lambda kind, span, meta: popped.append(meta.value))
+    text.insert(0, "Hello")
+    doc.commit()
+    assert undo.top_undo_value() == "insert"
+    undo.undo()
+    assert text.to_string() == ""
+    assert undo.top_redo_value() is None
+    undo.redo()
+    assert text.to_string() == "Hello"
+    assert popped == ["insert", None]
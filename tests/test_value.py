@@ -0,0 +1,191 @@
+import collections
+import collections.abc
+import datetime
+import decimal
+import enum
+import sys
+import uuid
+
+import pytest
+import loro
+from loro import ExportMode, LoroDoc, LoroValueList, LoroValueMap
+
+
+def test_convert_more_python_types():
+    doc = LoroDoc()
+    m = doc.get_map("m")
+    m.insert("tuple", (1, (2, 3)))
+    m.insert("deque", collections.deque(["a", "b"]))
+    m.insert("bytearray", bytearray(b"ab"))
+    m.insert("memoryview", memoryview(b"cd"))
+    m.insert("decimal", decimal.Decimal("1.10"))
+    m.insert("datetime", datetime.datetime(2024, 1, 2, 3, 4, 5))
+    m.insert("date", datetime.date(2024, 1, 2))
+    assert m.get_deep_value() == {
+        "tuple": [1, [2, 3]],
+        "deque": ["a", "b"],
+        "bytearray": b"ab",
+        "memoryview": b"cd",
+        "decimal": "1.10",
+        "datetime": "2024-01-02T03:04:05",
+        "date": "2024-01-02",
+    }
+    with pytest.raises(TypeError):
+        m.insert("set", {1, 2})
+
+
+def test_conversion_hook():
+    doc = LoroDoc()
+    m = doc.get_map("m")
+    loro.set_conversion_hook(decimal.Decimal, float)
+    try:
+        m.insert("price", decimal.Decimal("1.5"))
+        assert m["price"].value == 1.5
+    finally:
+        loro.set_conversion_hook(decimal.Decimal, str)
+
+    class Point:
+        def __init__(self, x, y):
+            self.x, self.y = x, y
+
+    with pytest.raises(TypeError):
+        m.insert("point", Point(1, 2))
+    loro.set_conversion_hook(Point, lambda p: {"x": p.x, "y": p.y})
+    try:
+        m.insert("point", Point(1, 2))
+    finally:
+        loro.set_conversion_hook(Point, None)
+    assert m["point"].value == {"x": 1, "y": 2}
+
+
+def test_conversion_hook_returning_objects_with_hooks():
+    class Foo:
+        pass
+
+    doc = LoroDoc()
+    m = doc.get_map("m")
+    loro.set_conversion_hook(Foo, lambda o: o)
+    try:
+        with pytest.raises(TypeError):
+            m.insert("foo", Foo())
+    finally:
+        loro.set_conversion_hook(Foo, None)
+
+    # A hook may return an object converted by another hook.
+    loro.set_conversion_hook(Foo, lambda o: decimal.Decimal("2.5"))
+    try:
+        m.insert("foo", Foo())
+        assert m["foo"].value == "2.5"
+    finally:
+        loro.set_conversion_hook(Foo, None)
+
+
+def test_lazy_values():
+    loro.set_lazy_threshold(3)
+    try:
+        doc = LoroDoc()
+        items = doc.get_list("items")
+        for i in range(5):
+            items.push({"i": i, "xs": list(range(i))})
+        value = items.get_deep_value()
+        assert isinstance(value, LoroValueList)
+        assert isinstance(value, collections.abc.Sequence)
+        assert len(value) == 5
+        assert value[1] == {"i": 1, "xs": [0]}
+        assert isinstance(value[1]["xs"], list)
+        assert isinstance(value[-1]["xs"], LoroValueList)
+        assert value[-1]["xs"] == [0, 1, 2, 3.0]
+        assert [item["i"] for item in value] == [0, 1, 2, 3, 4]
+        assert {"i": 0, "xs": []} in value
+        assert value == [{"i": i, "xs": list(range(i))} for i in range(5)]
+        assert hash(value) == hash(doc.get_deep_value()["items"])
+        assert isinstance(value.to_python(), list)
+        assert isinstance(value.to_python()[-1]["xs"], list)
+
+        big = {str(i): i for i in range(5)}
+        doc.get_map("m").insert("big", big)
+        proxy = doc.get_map("m").get_deep_value()["big"]
+        assert isinstance(proxy, LoroValueMap)
+        assert proxy == big and dict(proxy) == big
+        assert proxy["3"] == 3 and proxy.get("9") is None and "4" in proxy
+
+        # A proxy is stored without being converted back.
+        doc.get_map("m").insert("copy", proxy)
+        assert doc.get_map("m")["copy"].value == big
+
+        # Proxies compare like lists and dicts, which are not equal to tuples.
+        assert value[-1]["xs"] != (0, 1, 2, 3)
+        assert value[-1]["xs"] == [0, 1, 2, 3]
+        assert proxy != tuple(big.items())
+
+        keys, values, items = proxy.keys(), proxy.values(), proxy.items()
+        assert len(keys) == len(values) == len(items) == 5
+        assert list(keys) == list(keys) and set(keys) == set(big)
+        assert sorted(values) == sorted(values) == sorted(big.values())
+        assert dict(items) == dict(items) == big
+        assert "3" in keys and "9" not in keys
+        assert 3 in values and 9 not in values
+        assert ("3", 3) in items and ("3", 4) not in items
+    finally:
+        loro.set_lazy_threshold(None)
+
+
+class Color(enum.Enum):
+    RED = "red"
+
+
+def test_custom_encoders_and_decoders():
+    loro.register_encoder(uuid.UUID, str)
+    loro.register_decoder("uuid.UUID", uuid.UUID)
+    loro.register_encoder(Color, lambda color: color.value, tag="color")
+    loro.register_decoder("color", Color)
+    try:
+        doc = LoroDoc()
+        task = doc.get_map("task")
+        task_id = uuid.uuid4()
+        task.insert("id", task_id)
+        task.insert("colors", [Color.RED])
+        assert task.get_deep_value() == {"id": task_id, "colors": [Color.RED]}
+
+        other = LoroDoc()
+        other.import_(doc.export(ExportMode.Snapshot()))
+        assert other.get_map("task")["id"].value == task_id
+
+        loro.register_decoder("color", None)
+        assert task["colors"].value == [{"$type": "color", "$value": "red"}]
+    finally:
+        loro.register_encoder(uuid.UUID, None)
+        loro.register_decoder("uuid.UUID", None)
+        loro.register_encoder(Color, None)
+        loro.register_decoder("color", None)
+
+
+def test_failing_decoder():
+    def decode(value):
+        return 1 / 0
+
+    errors = []
+    old_hook = sys.unraisablehook
+    sys.unraisablehook = lambda args: errors.append(args.exc_value)
+    loro.register_decoder("broken", decode)
+    try:
+        doc = LoroDoc()
+        m = doc.get_map("m")
+        m.insert("x", {"$type": "broken", "$value": 1})
+        m.insert("y", 2)
+        assert m.get_deep_value() == {"x": {"$type": "broken", "$value": 1}, "y": 2}
+    finally:
+        sys.unraisablehook = old_hook
+        loro.register_decoder("broken", None)
+    assert len(errors) == 1
+    assert isinstance(errors[0], ZeroDivisionError)
+
+
+def test_int_out_of_range():
+    doc = LoroDoc()
+    m = doc.get_map("m")
+    m.insert("min", -(2**63))
+    assert m["min"].value == -(2**63)
+    for value in [2**63, -(2**63) - 1, 10**400]:
+        with pytest.raises(OverflowError, match="64-bit"):
+            m.insert("big", value)
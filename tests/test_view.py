@@ -0,0 +1,77 @@
+import pytest
+from loro import (
+    DetachedEditError,
+    Frontiers,
+    LoroDoc,
+    LoroList,
+    LoroMap,
+    VersionNotFoundError,
+)
+
+
+def test_view_at_past_versions():
+    doc = LoroDoc()
+    doc.peer_id = 1
+    todos = doc.get_list("todos")
+    first = todos.push_container(LoroMap())
+    first.insert("title", "write docs")
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    first.insert("done", True)
+    doc.get_text("notes").insert(0, "hello")
+    doc.commit()
+    v2 = doc.oplog_frontiers
+    todos.delete(0, 1)
+    doc.commit()
+
+    view1 = doc.view_at(v1)
+    view2 = doc.view_at(v2)
+    assert not doc.is_detached()
+    assert view1.frontiers.encode() == v1.encode()
+    assert view1.get_deep_value()["todos"] == [{"title": "write docs"}]
+    assert view2.get_list("todos").get_deep_value() == [
+        {"title": "write docs", "done": True}
+    ]
+    assert view2.get_text("notes").to_string() == "hello"
+    assert view2.get_map(first.id).get("done").value is True
+    assert [v.value for v in view2.jsonpath("$.todos[*].title")] == ["write docs"]
+
+    # The views are not affected by new changes, and cannot be edited.
+    doc.get_text("notes").insert(5, " world")
+    doc.commit()
+    assert view2.get_text("notes").to_string() == "hello"
+    with pytest.raises(DetachedEditError):
+        view2.get_text("notes").insert(0, "!")
+    assert doc.get_deep_value() == {"todos": [], "notes": "hello world"}
+
+
+def test_view_at_empty_and_unknown_versions():
+    doc = LoroDoc()
+    doc.get_map("m").insert("a", 1)
+    doc.commit()
+    assert doc.view_at(Frontiers()).get_deep_value() == {}
+
+    other = LoroDoc()
+    other.get_map("m").insert("b", 2)
+    other.commit()
+    with pytest.raises(VersionNotFoundError):
+        doc.view_at(other.oplog_frontiers)
+
+
+def test_view_of_a_list_of_maps():
+    doc = LoroDoc()
+    a = doc.get_map("a")
+    items = a.insert_container("y", LoroList())
+    doc.commit()
+    item = items.push_container(LoroMap())
+    doc.commit()
+    item.insert("x", "q")
+    doc.commit()
+    v1 = doc.oplog_frontiers
+    item.insert("x", "r")
+    doc.commit()
+
+    view = doc.view_at(v1)
+    assert view.get_deep_value() == {"a": {"y": [{"x": "q"}]}}
+    assert view.get_deep_value() == doc.fork_at(v1).get_deep_value()
+    assert view.get_map(item.id).get("x").value == "q"